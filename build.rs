@@ -0,0 +1,9 @@
+fn main() {
+    // Compiles the eBPF programs and generates their Rust skeleton; needs clang and the libbpf headers
+    #[cfg(feature = "ebpf")]
+    {
+        let out = std::path::PathBuf::from(std::env::var_os("OUT_DIR").unwrap()).join("snoop.skel.rs");
+        libbpf_cargo::SkeletonBuilder::new().source("src/bpf/snoop.bpf.c").build_and_generate(&out).unwrap();
+        println!("cargo:rerun-if-changed=src/bpf/snoop.bpf.c");
+    }
+}
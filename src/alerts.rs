@@ -0,0 +1,224 @@
+use crate::{
+    records::Records,
+    rules::{self, Action, Hit, Metric, Op, Rule, RuleState, Snapshot},
+};
+use chrono::{DateTime, Local};
+use serde::{de::IntoDeserializer, Deserialize};
+use std::collections::VecDeque;
+
+/// Entries kept in the alert log
+const LOG_LINES: usize = 200;
+
+/// Fired alerts kept for the `alerts` history
+const HISTORY_LEN: usize = 1000;
+
+/// An alert as it was when it fired
+pub struct Fired {
+    pub at: DateTime<Local>,
+    pub rule: String,
+    pub value: f64,
+    pub pid: Option<i32>,
+    pub process: Option<String>,
+    pub resolved: Option<DateTime<Local>>,
+}
+
+/// Fires the loaded rules against each sample, keeping the banner and the alert log
+#[derive(Default)]
+pub struct Alerts {
+    state: RuleState,
+    /// One line per alert firing right now, shown in the banner
+    pub firing: Vec<String>,
+    /// Fired/resolved transitions, oldest first
+    pub log: VecDeque<String>,
+    /// Every alert that fired, oldest first
+    pub history: VecDeque<Fired>,
+}
+
+/// An alert that started or stopped firing
+pub struct Transition {
+    pub rule: String,
+    pub pid: Option<i32>,
+    pub message: String,
+    pub resolved: bool,
+    /// Webhook set on the rule itself, overriding the configured one
+    pub webhook: Option<String>,
+}
+
+fn message(rule: &Rule, hit: &Hit) -> String {
+    match (hit.pid, &hit.process) {
+        (Some(pid), Some(name)) => format!("{} (PID {} {}: {:.1})", rule.name, pid, name, hit.value),
+        _ => format!("{} (value {:.1})", rule.name, hit.value),
+    }
+}
+
+impl Alerts {
+    /// Evaluates `rules` against a new sample, returning the alerts that fired or resolved
+    pub fn update(&mut self, rules: &[Rule], snap: &Snapshot) -> Vec<Transition> {
+        let step = self.state.step(rules, snap);
+        let at = Local::now();
+        let now = at.format("%H:%M:%S");
+        let mut transitions: Vec<Transition> = vec![];
+        for (i, hit) in &step.fired {
+            if self.history.len() == HISTORY_LEN {
+                self.history.pop_front();
+            }
+            self.history.push_back(Fired { at, rule: rules[*i].name.clone(), value: hit.value, pid: hit.pid, process: hit.process.clone(), resolved: None });
+            let message = message(&rules[*i], hit);
+            self.push_log(format!("{} FIRED    {}", now, message));
+            transitions.push(Transition { rule: rules[*i].name.clone(), pid: hit.pid, message, resolved: false, webhook: rules[*i].webhook.clone() });
+        }
+        for (name, pid) in step.resolved {
+            let message = match pid {
+                Some(pid) => format!("{} (PID {})", name, pid),
+                None => name.clone(),
+            };
+            self.push_log(format!("{} resolved {}", now, message));
+            if let Some(fired) = self.history.iter_mut().rev().find(|f| f.rule == name && f.pid == pid && f.resolved.is_none()) {
+                fired.resolved = Some(at);
+            }
+            // The rule may have been removed since it fired
+            let webhook = rules.iter().find(|r| r.name == name).and_then(|r| r.webhook.clone());
+            transitions.push(Transition { rule: name, pid, message, resolved: true, webhook });
+        }
+        self.firing = step.firing.iter().map(|(i, hit)| message(&rules[*i], hit)).collect();
+        transitions
+    }
+
+    pub fn get_history(&self) -> Records {
+        let mut records = Records::new(&["FIRED", "RULE", "VALUE", "PROCESS", "RESOLVED"]);
+        for fired in self.history.iter().rev() {
+            let process = match (fired.pid, &fired.process) {
+                (Some(pid), Some(name)) => format!("{} {}", pid, name),
+                _ => "-".to_string(),
+            };
+            let resolved = match fired.resolved {
+                Some(at) => format!("{} ({}s)", at.format("%H:%M:%S"), (at - fired.at).num_seconds()),
+                None => "firing".to_string(),
+            };
+            records.push(vec![fired.at.format("%Y-%m-%d %H:%M:%S").to_string(), fired.rule.clone(), format!("{:.1}", fired.value), process, resolved]);
+        }
+        records
+    }
+
+    /// Adds an event from outside the rule set, such as a watchdog report, to the log
+    pub fn note(&mut self, message: &str) {
+        let line = format!("{} {}", Local::now().format("%H:%M:%S"), message);
+        self.push_log(line);
+    }
+
+    fn push_log(&mut self, line: String) {
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+}
+
+/// Parses "30s", "5m", "1h" or plain seconds
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => text.split_at(i),
+        None => (text, "s"),
+    };
+    let number = number.parse::<u64>().ok()?;
+    match unit {
+        "s" => Some(number),
+        "m" => number.checked_mul(60),
+        "h" => number.checked_mul(3600),
+        _ => None,
+    }
+}
+
+/// Builds a rule from `<metric> <op> <threshold> [pattern] [for <duration>]`, named after the expression
+pub fn parse_alert(args: &[String]) -> Result<Rule, String> {
+    let usage = "usage: alert <metric> >|< <threshold> [process pattern] [for <duration>]";
+    let [metric, op, threshold, rest @ ..] = args else {
+        return Err(usage.to_string());
+    };
+    let metric = Metric::deserialize(metric.as_str().into_deserializer())
+        .map_err(|_: serde::de::value::Error| format!("alert: unknown metric '{}'", metric))?;
+    let op = match op.as_str() {
+        ">" => Op::Above,
+        "<" => Op::Below,
+        _ => return Err(usage.to_string()),
+    };
+    let threshold = threshold.parse::<f64>().map_err(|_| format!("alert: invalid threshold '{}'", threshold))?;
+    let mut pattern: Option<String> = None;
+    let mut duration: Option<u64> = None;
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        if arg == "for" {
+            let text = rest.next().ok_or(usage)?;
+            duration = Some(parse_duration(text).ok_or_else(|| format!("alert: invalid duration '{}'", text))?);
+        } else if pattern.is_none() {
+            pattern = Some(arg.clone());
+        } else {
+            return Err(usage.to_string());
+        }
+    }
+    Ok(Rule { name: args.join(" "), metric, op, threshold, pattern, action: Action::Alert, duration, webhook: None })
+}
+
+/// Adds a rule parsed from the command line to `rules` if the set stays valid
+pub fn add(rules: &mut Vec<Rule>, args: &[String]) -> Result<String, String> {
+    let rule = parse_alert(args)?;
+    let mut candidate = rules.clone();
+    candidate.push(rule);
+    let errors = rules::validate(&candidate);
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    *rules = candidate;
+    Ok(format!("Added alert {}", rules::describe(rules.last().unwrap())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_duration_reads_units() {
+        assert_eq!(parse_duration("30"), Some(30));
+        assert_eq!(parse_duration("30s"), Some(30));
+        assert_eq!(parse_duration("5m"), Some(300));
+        assert_eq!(parse_duration("2h"), Some(7200));
+        for text in ["", "m", "5d", "1.5h", "-5m", "5 m"] {
+            assert_eq!(parse_duration(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_duration_refuses_overflow() {
+        assert_eq!(parse_duration(&format!("{}h", u64::MAX / 3600 + 1)), None);
+        assert_eq!(parse_duration(&format!("{}m", u64::MAX)), None);
+        assert_eq!(parse_duration(&format!("{}s", u64::MAX)), Some(u64::MAX));
+    }
+
+    #[test]
+    fn parse_alert_reads_pattern_and_duration() {
+        let rule = parse_alert(&args("proc_cpu > 90 chrome for 5m")).unwrap();
+        assert_eq!(rule.name, "proc_cpu > 90 chrome for 5m");
+        assert_eq!(rule.metric, Metric::ProcCpu);
+        assert_eq!(rule.op, Op::Above);
+        assert_eq!(rule.threshold, 90.0);
+        assert_eq!(rule.pattern.as_deref(), Some("chrome"));
+        assert_eq!(rule.duration, Some(300));
+        assert_eq!(rule.action, Action::Alert);
+        let rule = parse_alert(&args("load < 0.5")).unwrap();
+        assert_eq!((rule.op, rule.pattern, rule.duration), (Op::Below, None, None));
+    }
+
+    #[test]
+    fn parse_alert_refuses_bad_input() {
+        assert_eq!(parse_alert(&args("nope > 1")).unwrap_err(), "alert: unknown metric 'nope'");
+        assert_eq!(parse_alert(&args("cpu > x")).unwrap_err(), "alert: invalid threshold 'x'");
+        assert_eq!(parse_alert(&args("cpu > 90 for 5d")).unwrap_err(), "alert: invalid duration '5d'");
+        for line in ["cpu > ", "cpu = 90", "cpu > 90 for", "proc_cpu > 90 a b"] {
+            assert!(parse_alert(&args(line)).unwrap_err().starts_with("usage: alert"), "{}", line);
+        }
+    }
+}
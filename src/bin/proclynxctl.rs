@@ -0,0 +1,34 @@
+//! Runs a command in the running proclynx and prints its output, e.g. `proclynxctl ptable | less`
+use std::{
+    env,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    process,
+};
+
+#[path = "../paths.rs"]
+#[allow(dead_code)]
+mod paths;
+
+fn main() {
+    let command: Vec<String> = env::args().skip(1).collect();
+    if command.is_empty() {
+        eprintln!("usage: proclynxctl <command> [args]");
+        process::exit(2);
+    }
+    let path = paths::control_socket();
+    let mut stream = match UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("proclynxctl: cannot connect to {}: {} (is proclynx running?)", path.display(), e);
+            process::exit(1);
+        },
+    };
+    let mut output = String::new();
+    let result = writeln!(stream, "{}", command.join(" ")).and_then(|_| stream.read_to_string(&mut output));
+    if let Err(e) = result {
+        eprintln!("proclynxctl: {}", e);
+        process::exit(1);
+    }
+    let _ = io::stdout().write_all(output.as_bytes());
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: GPL-2.0
+// Programs behind `execsnoop` and `opensnoop`, built into a skeleton by build.rs with the ebpf feature
+#include <linux/bpf.h>
+#include <bpf/bpf_helpers.h>
+
+#define TASK_COMM_LEN 16
+#define NAME_LEN 256
+
+#define KIND_EXEC 0
+#define KIND_OPEN 1
+
+// Read field by field in snoop.rs, keep the two in step
+struct event {
+	__u32 pid;
+	__u32 uid;
+	__u8 kind;
+	char comm[TASK_COMM_LEN];
+	char name[NAME_LEN];
+};
+
+struct {
+	__uint(type, BPF_MAP_TYPE_RINGBUF);
+	__uint(max_entries, 256 * 1024);
+} events SEC(".maps");
+
+// Layout of /sys/kernel/tracing/events/sched/sched_process_exec/format, declared here to do
+// without a generated vmlinux.h
+struct exec_ctx {
+	__u64 common;
+	// __data_loc: the offset of the string in the low 16 bits, its length in the high ones
+	__u32 filename_loc;
+	__s32 pid;
+	__s32 old_pid;
+};
+
+// Layout of /sys/kernel/tracing/events/syscalls/sys_enter_openat/format
+struct openat_ctx {
+	__u64 common;
+	__s32 syscall_nr;
+	__u32 pad;
+	__u64 dfd;
+	const char *filename;
+	__u64 flags;
+	__u64 mode;
+};
+
+static __always_inline struct event *reserve(__u8 kind)
+{
+	struct event *e = bpf_ringbuf_reserve(&events, sizeof(*e), 0);
+
+	if (!e)
+		return 0;
+	e->kind = kind;
+	e->pid = bpf_get_current_pid_tgid() >> 32;
+	e->uid = (__u32)bpf_get_current_uid_gid();
+	bpf_get_current_comm(e->comm, sizeof(e->comm));
+	return e;
+}
+
+SEC("tracepoint/sched/sched_process_exec")
+int handle_exec(struct exec_ctx *ctx)
+{
+	struct event *e = reserve(KIND_EXEC);
+
+	if (!e)
+		return 0;
+	bpf_probe_read_kernel_str(e->name, sizeof(e->name), (void *)ctx + (ctx->filename_loc & 0xFFFF));
+	bpf_ringbuf_submit(e, 0);
+	return 0;
+}
+
+SEC("tracepoint/syscalls/sys_enter_openat")
+int handle_openat(struct openat_ctx *ctx)
+{
+	struct event *e = reserve(KIND_OPEN);
+
+	if (!e)
+		return 0;
+	bpf_probe_read_user_str(e->name, sizeof(e->name), ctx->filename);
+	bpf_ringbuf_submit(e, 0);
+	return 0;
+}
+
+char LICENSE[] SEC("license") = "GPL";
@@ -0,0 +1,136 @@
+use crate::rules::ProcessSample;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// CPU limit and usage of a process's cgroup, both in percent of a single CPU
+pub struct CpuQuota {
+    pub limit: f64,
+    pub usage: f64,
+}
+
+/// Where the cgroup's CPU limit lives, which differs between cgroup v1 and v2
+struct CgroupFiles {
+    cgroup: String,
+    /// Directory of the cgroup in the controller carrying the limit, plus that controller's root
+    limit_dir: PathBuf,
+    limit_root: PathBuf,
+    v2: bool,
+}
+
+fn locate(pid: i32) -> Option<CgroupFiles> {
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let mut v2: Option<&str> = None;
+    let mut cpu: Option<(&str, &str)> = None;
+    for line in cgroups.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if controllers.is_empty() {
+            v2 = Some(path);
+        }
+        if controllers.split(',').any(|c| c == "cpu") {
+            cpu = Some((controllers, path));
+        }
+    }
+    if let Some((cpu_mount, cpu_path)) = cpu {
+        let limit_root = Path::new(CGROUP_ROOT).join(cpu_mount);
+        return Some(CgroupFiles {
+            cgroup: cpu_path.to_string(),
+            limit_dir: limit_root.join(cpu_path.trim_start_matches('/')),
+            limit_root,
+            v2: false,
+        });
+    }
+    let path = v2?;
+    Some(CgroupFiles {
+        cgroup: path.to_string(),
+        limit_dir: Path::new(CGROUP_ROOT).join(path.trim_start_matches('/')),
+        limit_root: PathBuf::from(CGROUP_ROOT),
+        v2: true,
+    })
+}
+
+/// Limit of a single cgroup directory, if it sets one
+fn read_limit(dir: &Path, v2: bool) -> Option<f64> {
+    let (quota, period) = if v2 {
+        let max = fs::read_to_string(dir.join("cpu.max")).ok()?;
+        let mut fields = max.split_whitespace();
+        (fields.next()?.parse::<f64>().ok()?, fields.next()?.parse::<f64>().ok()?)
+    } else {
+        (
+            fs::read_to_string(dir.join("cpu.cfs_quota_us")).ok()?.trim().parse::<f64>().ok()?,
+            fs::read_to_string(dir.join("cpu.cfs_period_us")).ok()?.trim().parse::<f64>().ok()?,
+        )
+    };
+    if quota < 0.0 || period <= 0.0 {
+        return None;
+    }
+    Some(quota / period * 100.0)
+}
+
+/// Whether `cgroup` is `parent` or one of its descendants
+fn is_within(cgroup: &str, parent: &str) -> bool {
+    let parent = parent.trim_end_matches('/');
+    cgroup == parent || cgroup.strip_prefix(parent).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Whether `pid` lives in a cgroup created by a container runtime
+pub fn in_container(pid: i32) -> bool {
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", pid)).unwrap_or_default();
+    ["docker", "containerd", "kubepods", "libpod", "lxc"].iter().any(|runtime| cgroups.contains(runtime))
+}
+
+/// Returns the cgroup of `pid` and, when it or an ancestor caps CPU time, the effective quota and
+/// current usage: the CPU the sampler last measured for the processes in the cgroup and below it
+pub fn cpu_quota(pid: i32, processes: &[ProcessSample]) -> Option<(String, Option<CpuQuota>)> {
+    let files = locate(pid)?;
+    // A cgroup can never use more than its most restrictive ancestor allows
+    let limit = files
+        .limit_dir
+        .ancestors()
+        .take_while(|dir| dir.starts_with(&files.limit_root))
+        .filter_map(|dir| read_limit(dir, files.v2))
+        .reduce(f64::min);
+    let Some(limit) = limit else {
+        return Some((files.cgroup, None));
+    };
+    let usage = processes
+        .iter()
+        .filter(|p| locate(p.pid).is_some_and(|other| is_within(&other.cgroup, &files.cgroup)))
+        .map(|p| p.cpu)
+        .sum();
+    Some((files.cgroup, Some(CpuQuota { limit, usage })))
+}
+
+/// Renders `[#####-----] 180% of 200% quota` style text gauges
+pub fn gauge(quota: &CpuQuota, width: usize) -> String {
+    let ratio = (quota.usage / quota.limit).clamp(0.0, 1.0);
+    let filled = (ratio * width as f64).round() as usize;
+    format!(
+        "[{}{}] {:.0}% of {:.0}% quota ({:.0}% used)",
+        "#".repeat(filled),
+        "-".repeat(width - filled),
+        quota.usage,
+        quota.limit,
+        ratio * 100.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_within_counts_the_cgroup_and_its_descendants() {
+        assert!(is_within("/system.slice/docker-1.scope", "/system.slice"));
+        assert!(is_within("/system.slice", "/system.slice/"));
+        assert!(is_within("/user.slice", "/"));
+        assert!(!is_within("/system.slice2", "/system.slice"));
+        assert!(!is_within("/", "/system.slice"));
+    }
+}
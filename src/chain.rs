@@ -0,0 +1,108 @@
+/// One command of a line like `a; b && c`
+pub struct Step {
+    pub line: String,
+    /// Joined to the step before by `&&`, so it only runs if that one did not fail
+    pub after_success: bool,
+}
+
+/// Splits a command line at `;` and `&&` outside quotes. `alias` and `watch` keep the whole
+/// line, since what follows them is a command line of its own.
+pub fn split(line: &str) -> Vec<Step> {
+    if matches!(line.split_whitespace().next(), Some("alias" | "watch")) {
+        return vec![Step { line: line.to_string(), after_success: false }];
+    }
+    let mut steps: Vec<Step> = vec![];
+    let mut current = String::new();
+    let mut after_success = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ';') => {
+                steps.push(Step { line: std::mem::take(&mut current), after_success });
+                after_success = false;
+                continue;
+            },
+            (None, '&') if chars.peek() == Some(&'&') => {
+                chars.next();
+                steps.push(Step { line: std::mem::take(&mut current), after_success });
+                after_success = true;
+                continue;
+            },
+            _ => {},
+        }
+        current.push(c);
+    }
+    steps.push(Step { line: current, after_success });
+    steps.retain(|step| !step.line.trim().is_empty());
+    steps
+}
+
+/// Where `c` first appears outside quotes in `line`
+pub fn unquoted(line: &str, c: char) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (at, next) in line.char_indices() {
+        match (quote, next) {
+            (Some(q), next) if next == q => quote = None,
+            (None, '\'' | '"') => quote = Some(next),
+            (None, next) if next == c => return Some(at),
+            _ => {},
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(line: &str) -> Vec<(String, bool)> {
+        split(line).into_iter().map(|step| (step.line, step.after_success)).collect()
+    }
+
+    #[test]
+    fn split_at_semicolons_and_ands() {
+        assert_eq!(lines("uname; hostname && df"), vec![("uname".to_string(), false), (" hostname ".to_string(), false), (" df".to_string(), true)]);
+    }
+
+    #[test]
+    fn split_keeps_quoted_separators() {
+        assert_eq!(lines("ignite sh -c 'a; b && c'"), vec![("ignite sh -c 'a; b && c'".to_string(), false)]);
+        assert_eq!(lines(r#"ignite sh -c "a && b"; uname"#), vec![(r#"ignite sh -c "a && b""#.to_string(), false), (" uname".to_string(), false)]);
+    }
+
+    #[test]
+    fn split_drops_empty_steps() {
+        assert!(split("").is_empty());
+        assert!(split(" ;; && ").is_empty());
+        assert_eq!(lines("uname;; ;hostname"), vec![("uname".to_string(), false), ("hostname".to_string(), false)]);
+    }
+
+    #[test]
+    fn split_ignores_trailing_separators() {
+        assert_eq!(lines("uname;"), vec![("uname".to_string(), false)]);
+        assert_eq!(lines("uname &&"), vec![("uname ".to_string(), false)]);
+    }
+
+    #[test]
+    fn split_leaves_pipes_to_each_step() {
+        assert_eq!(lines("ptable | head 5 && ports|grep 22"), vec![("ptable | head 5 ".to_string(), false), (" ports|grep 22".to_string(), true)]);
+        assert_eq!(lines("ports|&&uname"), vec![("ports|".to_string(), false), ("uname".to_string(), true)]);
+        // A single `&` is not a separator
+        assert_eq!(lines("ptable | grep a&b"), vec![("ptable | grep a&b".to_string(), false)]);
+    }
+
+    #[test]
+    fn split_keeps_alias_and_watch_whole() {
+        assert_eq!(split("alias x=uname; hostname").len(), 1);
+        assert_eq!(split("watch 2 uname && hostname").len(), 1);
+    }
+
+    #[test]
+    fn unquoted_skips_quotes() {
+        assert_eq!(unquoted("a '|' | b", '|'), Some(6));
+        assert_eq!(unquoted("a \"|\"", '|'), None);
+    }
+}
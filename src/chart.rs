@@ -0,0 +1,47 @@
+/// Block characters from lowest to highest
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One-line chart of the last `width` values, scaled to the largest of them
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().copied().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|v| {
+            if max <= 0.0 {
+                BARS[0]
+            } else {
+                BARS[((v / max) * (BARS.len() - 1) as f64).round().clamp(0.0, (BARS.len() - 1) as f64) as usize]
+            }
+        })
+        .collect()
+}
+
+/// Dots of a braille cell from the bottom up, for the left and the right column
+const DOTS: [[u32; 4]; 2] = [[0x40, 0x04, 0x02, 0x01], [0x80, 0x20, 0x10, 0x08]];
+
+/// Area chart of the last `width * 2` values in braille, `height` lines tall, scaled against `max`;
+/// every character holds two samples and four levels, so a few lines give a usable picture
+pub fn braille(values: &[f64], max: f64, width: usize, height: usize) -> Vec<String> {
+    let values = &values[values.len().saturating_sub(width * 2)..];
+    // Dots lit in each column, the newest on the right
+    let mut levels: Vec<usize> = vec![0; width * 2 - values.len()];
+    levels.extend(values.iter().map(|v| if max <= 0.0 { 0 } else { ((v / max) * (height * 4) as f64).round().clamp(0.0, (height * 4) as f64) as usize }));
+    (0..height)
+        .rev()
+        .map(|line| {
+            levels
+                .chunks(2)
+                .map(|pair| {
+                    let mut cell = 0x2800;
+                    for (side, level) in pair.iter().enumerate() {
+                        for dot in &DOTS[side][..level.saturating_sub(line * 4).min(4)] {
+                            cell |= dot;
+                        }
+                    }
+                    char::from_u32(cell).unwrap_or(' ')
+                })
+                .collect()
+        })
+        .collect()
+}
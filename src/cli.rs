@@ -0,0 +1,65 @@
+use crate::{config::Config, theme};
+use clap::{builder::PossibleValuesParser, Parser, Subcommand};
+use std::path::PathBuf;
+
+/// Interactive process and system monitor
+#[derive(Parser, Default)]
+#[command(name = "proclynx", version)]
+pub struct Cli {
+    /// How often metrics and live views refresh, overriding refresh_ms in config.toml
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(100..))]
+    pub refresh: Option<u64>,
+    /// Colour theme, overriding theme in config.toml
+    #[arg(long, value_name = "NAME", value_parser = PossibleValuesParser::new(theme::NAMES))]
+    pub theme: Option<String>,
+    /// Command line run at startup after config.toml's startup commands; may be repeated
+    #[arg(long = "cmd", value_name = "COMMAND")]
+    pub commands: Vec<String>,
+    /// Refuse commands that signal, start or stop processes and services
+    #[arg(long)]
+    pub readonly: bool,
+    /// Settings file to use instead of config.toml in the config directory
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Serve command lines from stdin for `connect` on another host
+    #[arg(long, hide = true)]
+    pub agent: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run one built-in command without the TUI and print its output
+    Exec {
+        /// Print JSON instead of text
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+        /// Print CSV with a header row instead of text
+        #[arg(long)]
+        csv: bool,
+        /// Command line as typed at the prompt, e.g. `ptable --sort cpu` or "sensors | grep Core"
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        line: Vec<String>,
+    },
+}
+
+/// How `exec` prints the command's result
+#[derive(Clone, Copy)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl Cli {
+    /// Puts the flags over the settings read from config.toml, at startup and on every reload
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(refresh) = self.refresh {
+            config.refresh_ms = refresh;
+        }
+        if let Some(theme) = &self.theme {
+            config.theme = theme.clone();
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use arboard::Clipboard;
+
+/// The system clipboard, opened on first use and kept for as long as proclynx runs: on X11 and
+/// Wayland what was copied is served by the `Clipboard` that set it and goes away with it
+#[derive(Default)]
+pub struct Keeper {
+    clipboard: Option<Clipboard>,
+}
+
+impl Keeper {
+    fn open(&mut self) -> Result<&mut Clipboard, String> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard::new().map_err(|e| e.to_string())?);
+        }
+        Ok(self.clipboard.as_mut().unwrap())
+    }
+
+    pub fn read(&mut self) -> Result<String, String> {
+        self.open()?.get_text().map_err(|e| e.to_string())
+    }
+
+    pub fn write(&mut self, text: &str) -> Result<(), String> {
+        self.open()?.set_text(text).map_err(|e| e.to_string())
+    }
+}
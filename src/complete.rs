@@ -0,0 +1,84 @@
+use crate::{
+    help, network,
+    rules::Snapshot,
+    theme::{self, Theme},
+};
+use std::collections::BTreeMap;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    widgets::{Block, Borders, Clear, List, ListItem},
+    Frame,
+};
+
+/// Most candidates the popup lists at once
+const MAX_SHOWN: usize = 8;
+
+/// Commands whose arguments are processes, by PID or name
+const PROCESS_COMMANDS: [&str; 9] = ["find", "kill", "dump", "schedlat", "trace", "perfstat", "watchdog", "guard", "notify-on-spawn"];
+
+/// Candidates for the word at the end of the input line
+pub struct Completion {
+    /// Byte offset in the input where the word starts
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+/// What the last word of `input` could become: a command or alias name first, then arguments
+/// from live data — processes for the commands taking one, interfaces for `graph net`, mount points
+/// for anything starting with `/` — and the fixed choices of a few commands
+pub fn complete(input: &str, snapshot: Option<&Snapshot>, aliases: &BTreeMap<String, String>) -> Completion {
+    let start = input.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    let word = &input[start..];
+    let before: Vec<&str> = input[..start].split_whitespace().collect();
+    let mut options: Vec<String> = match before.as_slice() {
+        [] => help::COMMANDS.iter().flat_map(|c| c.usage.iter().filter_map(|u| u.split_whitespace().next())).map(|n| n.to_string()).chain(aliases.keys().cloned()).collect(),
+        _ if word.starts_with('/') => snapshot.map(|s| s.filesystems.iter().map(|f| f.mount_point.clone()).collect()).unwrap_or_default(),
+        ["help"] => help::COMMANDS.iter().map(|c| c.name.to_string()).collect(),
+        ["graph"] => vec!["cpu".to_string(), "net".to_string()],
+        ["graph", "net"] => network::interfaces(),
+        ["theme"] => theme::NAMES.iter().map(|n| n.to_string()).collect(),
+        ["edit"] => vec!["config".to_string(), "rules".to_string(), "theme".to_string()],
+        ["service"] => vec!["start".to_string(), "stop".to_string(), "restart".to_string()],
+        [command, ..] if PROCESS_COMMANDS.contains(command) => {
+            let processes = snapshot.map(|s| s.processes.as_slice()).unwrap_or_default();
+            // Digits mean a PID, anything else a name
+            if word.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+                processes.iter().map(|p| p.pid.to_string()).collect()
+            } else {
+                processes.iter().map(|p| p.name.clone()).collect()
+            }
+        },
+        _ => vec![],
+    };
+    options.sort();
+    options.dedup();
+    options.retain(|o| o.starts_with(word) && o != word);
+    Completion { start, candidates: options }
+}
+
+/// The longest start all of `candidates` share
+pub fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix = candidates.first().cloned().unwrap_or_default();
+    for candidate in candidates {
+        while !candidate.starts_with(&prefix) {
+            prefix.pop();
+        }
+    }
+    prefix
+}
+
+/// The candidates in a small box just below the input line, starting under the word they complete
+pub fn render<B: Backend>(f: &mut Frame<B>, input_area: Rect, column: u16, candidates: &[String], theme: &Theme) {
+    let mut items: Vec<ListItem> = candidates.iter().take(MAX_SHOWN).map(|c| ListItem::new(c.clone())).collect();
+    if candidates.len() > MAX_SHOWN {
+        items.push(ListItem::new(format!("... {} more", candidates.len() - MAX_SHOWN)));
+    }
+    let width = candidates.iter().take(MAX_SHOWN).map(|c| c.chars().count()).max().unwrap_or(0).max(12) as u16 + 2;
+    let height = (items.len() as u16 + 2).min(f.size().bottom().saturating_sub(input_area.bottom()));
+    let x = (input_area.x + column).min(input_area.right().saturating_sub(width));
+    let area = Rect { x, y: input_area.bottom(), width: width.min(input_area.width), height };
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).border_style(theme.border())).style(theme.text());
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
@@ -0,0 +1,391 @@
+use crate::{
+    keys::{Keymap, Keys},
+    mqtt::MqttConfig,
+    paths,
+    ptable::{self, ProcessRow, SortColumn},
+    rules::Snapshot,
+    theme, webhook,
+};
+use nix::unistd::getuid;
+use serde::Deserialize;
+use std::{collections::BTreeMap, error::Error, fs, io::ErrorKind, path::PathBuf, sync::OnceLock, time::Duration};
+use tui::style::Color;
+
+/// Settings read from `config.toml` in the config directory, e.g.
+///
+/// ```toml
+/// refresh_ms = 1000
+/// sort = "cpu"
+/// theme = "dark"
+/// history_size = 1000
+/// startup = ["ptable"]
+///
+/// [aliases]
+/// top = "ptable --sort cpu"
+/// hot = "sensors | grep -i core"
+///
+/// [keys]
+/// down = ["j", "Down"]
+/// up = ["k", "Up"]
+/// kill = "x"
+///
+/// [[colors]]
+/// user = "root"
+/// color = "lightred"
+///
+/// [[colors]]
+/// user = "@me"
+/// color = "default"
+///
+/// [[colors]]
+/// container = true
+/// color = "blue"
+///
+/// [[colors]]
+/// state = "T"
+/// color = "magenta"
+///
+/// [notify]
+/// desktop = true
+/// long_job_secs = 30
+/// toast_secs = 5
+/// webhook = "https://hooks.slack.com/services/..."
+/// webhook_format = "slack"
+///
+/// [alarms]
+/// temp = 90
+/// disk = 95
+///
+/// [serve]
+/// token = "change-me"
+///
+/// [gauges]
+/// warning = 70
+/// critical = 90
+///
+/// [mqtt]
+/// broker = "192.168.1.10:1883"
+/// prefix = "homelab"
+/// interval_secs = 30
+/// ```
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How often the sampler refreshes metrics and live views
+    pub refresh_ms: u64,
+    /// Column `ptable` sorts by when not given `--sort`
+    pub sort: String,
+    /// Colour theme of the UI: dark, light, solarized or monochrome, adjusted by theme.toml
+    pub theme: String,
+    /// Most command lines kept in the history file, the oldest dropped first
+    pub history_size: usize,
+    /// Command lines run in order at launch
+    pub startup: Vec<String>,
+    /// Names expanding to a command line, followed by whatever was typed after the name
+    pub aliases: BTreeMap<String, String>,
+    /// Keys bound to an action instead of its default ones
+    pub keys: BTreeMap<String, Keys>,
+    /// Row colouring rules for the process table, tried in order
+    pub colors: Vec<ColorRule>,
+    pub notify: NotifyConfig,
+    pub alarms: Alarms,
+    pub gauges: Gauges,
+    pub serve: ServeConfig,
+    /// Metrics published to a broker from startup
+    pub mqtt: Option<MqttConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            refresh_ms: 2000,
+            sort: "pid".to_string(),
+            theme: "dark".to_string(),
+            history_size: 1000,
+            startup: vec![],
+            aliases: BTreeMap::new(),
+            keys: BTreeMap::new(),
+            colors: vec![],
+            notify: NotifyConfig::default(),
+            alarms: Alarms::default(),
+            gauges: Gauges::default(),
+            serve: ServeConfig::default(),
+            mqtt: None,
+        }
+    }
+}
+
+/// Thresholds checked on every sample from startup, shown as warnings rather than alerts
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Alarms {
+    /// Any component above this many °C
+    pub temp: Option<f64>,
+    /// Any filesystem more than this many percent full
+    pub disk: Option<f64>,
+    /// Used memory in percent
+    pub mem: Option<f64>,
+}
+
+impl Alarms {
+    /// One warning per component, filesystem or resource over its threshold
+    pub fn check(&self, snap: &Snapshot) -> Vec<String> {
+        let mut warnings: Vec<String> = vec![];
+        if let Some(limit) = self.temp {
+            for component in snap.components.iter().filter(|c| c.temp > limit) {
+                warnings.push(format!("{} {:.0}°C", component.label, component.temp));
+            }
+        }
+        if let Some(limit) = self.disk {
+            for fs in snap.filesystems.iter().filter(|f| f.total > 0) {
+                let used = fs.total.saturating_sub(fs.available) as f64 * 100.0 / fs.total as f64;
+                if used > limit {
+                    warnings.push(format!("{} {:.0}% full", fs.mount_point, used));
+                }
+            }
+        }
+        if let Some(limit) = self.mem.filter(|limit| snap.mem > *limit) {
+            warnings.push(format!("memory {:.0}% > {}%", snap.mem, limit));
+        }
+        warnings
+    }
+}
+
+/// Settings of the JSON API started with `serve`
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServeConfig {
+    /// Secret `POST /kill` must send as `Authorization: Bearer <token>`; without one the endpoint is off
+    pub token: Option<String>,
+}
+
+/// Percentages at which the bars of `memory` and `df` turn to the warning and then the alert colour
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Gauges {
+    pub warning: f64,
+    pub critical: f64,
+}
+
+impl Default for Gauges {
+    fn default() -> Gauges {
+        Gauges { warning: 75.0, critical: 90.0 }
+    }
+}
+
+/// Where events worth interrupting the user for are sent besides the UI
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Desktop notifications for fired alerts, watchdog events and finished long jobs
+    pub desktop: bool,
+    /// Jobs that ran at least this many seconds notify when they finish
+    pub long_job_secs: u64,
+    /// How long a toast about a background event stays in the corner of the output, 0 for none
+    pub toast_secs: u64,
+    /// URL every alert rule posts to when it fires or resolves, unless the rule sets its own
+    pub webhook: Option<String>,
+    /// "slack" (the default) or "alertmanager"
+    pub webhook_format: webhook::Format,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> NotifyConfig {
+        NotifyConfig { desktop: true, long_job_secs: 10, toast_secs: 5, webhook: None, webhook_format: webhook::Format::default() }
+    }
+}
+
+/// Colours the rows matching every condition it sets
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorRule {
+    /// Owner of the process, or "@me" for whoever runs proclynx
+    pub user: Option<String>,
+    /// One or more ps state letters (R, S, D, T, Z, ...)
+    pub state: Option<String>,
+    /// Whether the process runs inside a container
+    pub container: Option<bool>,
+    /// Substring of the process name
+    pub name: Option<String>,
+    /// Colour name ("red", "lightblue", ...), "#rrggbb", or "default" to keep the normal colour
+    pub color: String,
+}
+
+impl ColorRule {
+    pub fn matches(&self, row: &ProcessRow) -> bool {
+        let user = match self.user.as_deref() {
+            None => true,
+            Some("@me") => row.uid == getuid().as_raw(),
+            Some(user) => row.user == user,
+        };
+        user && self.state.as_ref().map(|s| s.contains(row.state)).unwrap_or(true)
+            && self.container.map(|c| c == row.container).unwrap_or(true)
+            && self.name.as_ref().map(|n| row.name.contains(n.as_str())).unwrap_or(true)
+    }
+
+    /// The foreground colour to apply, `None` for "default"
+    pub fn fg(&self) -> Option<Color> {
+        parse_color(&self.color).ok().flatten()
+    }
+}
+
+/// Set from `--config`
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Reads settings from `path` instead of config.toml in the config directory
+pub fn set_path(path: PathBuf) {
+    let _ = PATH.set(path);
+}
+
+pub fn path() -> PathBuf {
+    PATH.get().cloned().unwrap_or_else(|| paths::config_dir().join("config.toml"))
+}
+
+pub fn parse_color(name: &str) -> Result<Option<Color>, String> {
+    let color = match name.to_lowercase().as_str() {
+        "default" | "reset" => return Ok(None),
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.len() == 7 && hex.is_ascii() && hex.starts_with('#') => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("invalid colour '{}'", name));
+            Color::Rgb(channel(1)?, channel(3)?, channel(5)?)
+        },
+        _ => return Err(format!("unknown colour '{}'", name)),
+    };
+    Ok(Some(color))
+}
+
+/// Faster refreshes mostly measure proclynx itself
+const MIN_REFRESH_MS: u64 = 100;
+
+impl Config {
+    pub fn refresh(&self) -> Duration {
+        Duration::from_millis(self.refresh_ms)
+    }
+
+    /// Whether a colour rule looks at `container`, which the process table only reads when asked
+    pub fn colors_by_container(&self) -> bool {
+        self.colors.iter().any(|rule| rule.container.is_some())
+    }
+
+    pub fn sort_column(&self) -> SortColumn {
+        ptable::parse_sort(&self.sort).unwrap_or(SortColumn::Pid)
+    }
+
+    /// Replaces an alias at the start of `line` with its command line, keeping the arguments typed after it
+    pub fn expand_alias(&self, line: &str) -> String {
+        let line = line.trim_start();
+        let name = line.split_whitespace().next().unwrap_or("");
+        match self.aliases.get(name) {
+            Some(expansion) => format!("{}{}", expansion, &line[name.len()..]),
+            None => line.to_string(),
+        }
+    }
+
+    /// Defines `name` as `expansion`, or removes it when `expansion` is `None`, both here and in
+    /// the [aliases] table of `config.toml`, leaving the rest of the file as it was written
+    pub fn set_alias(&mut self, name: &str, expansion: Option<&str>) -> Result<(), String> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("alias names are letters, digits, - and _, not '{}'", name));
+        }
+        let text = match fs::read_to_string(path()) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("reading {}: {}", path().display(), e)),
+        };
+        let mut lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+        let entry = expansion.map(|e| format!("{} = {}", name, toml::Value::String(e.to_string())));
+        let is_table = |line: &str| line.trim_start().starts_with('[');
+        let start = lines.iter().position(|l| l.trim() == "[aliases]");
+        match start {
+            Some(start) => {
+                let end = lines[start + 1..].iter().position(|l| is_table(l)).map(|i| start + 1 + i).unwrap_or(lines.len());
+                let existing = lines[start + 1..end].iter().position(|l| l.split('=').next().map(|key| key.trim() == name).unwrap_or(false));
+                match (existing, entry) {
+                    (Some(i), Some(entry)) => lines[start + 1 + i] = entry,
+                    (Some(i), None) => {
+                        lines.remove(start + 1 + i);
+                    },
+                    (None, Some(entry)) => {
+                        // After the last entry rather than the blank lines that separate the next table
+                        let last = lines[start + 1..end].iter().rposition(|l| !l.trim().is_empty()).map(|i| start + 2 + i).unwrap_or(start + 1);
+                        lines.insert(last, entry);
+                    },
+                    (None, None) => return Err(format!("no alias named '{}'", name)),
+                }
+            },
+            None => match entry {
+                Some(entry) => {
+                    if lines.last().map(|l| !l.trim().is_empty()).unwrap_or(false) {
+                        lines.push(String::new());
+                    }
+                    lines.push("[aliases]".to_string());
+                    lines.push(entry);
+                },
+                None => return Err(format!("no alias named '{}'", name)),
+            },
+        }
+        let text = lines.iter().map(|l| format!("{}\n", l)).collect::<String>();
+        // Written only if it still reads back, with the alias changed
+        let config: Config = toml::from_str(&text).map_err(|e| format!("{} could not be updated: {}", path().display(), e))?;
+        if config.aliases.get(name).map(|e| e.as_str()) != expansion {
+            return Err(format!("{} could not be updated: [aliases] is not a plain table", path().display()));
+        }
+        if let Some(dir) = path().parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("creating {}: {}", dir.display(), e))?;
+        }
+        fs::write(path(), text).map_err(|e| format!("writing {}: {}", path().display(), e))?;
+        match expansion {
+            Some(expansion) => self.aliases.insert(name.to_string(), expansion.to_string()),
+            None => self.aliases.remove(name),
+        };
+        Ok(())
+    }
+
+    /// Reads `config.toml`; a missing file means the defaults
+    pub fn load() -> Result<Config, Box<dyn Error>> {
+        let text = match fs::read_to_string(path()) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let config: Config = toml::from_str(&text)?;
+        if config.refresh_ms < MIN_REFRESH_MS {
+            return Err(format!("refresh_ms must be at least {}", MIN_REFRESH_MS).into());
+        }
+        if ptable::parse_sort(&config.sort).is_none() {
+            return Err(format!("sort must be one of pid, cpu, mem, read, write, rq or name, not '{}'", config.sort).into());
+        }
+        if theme::Theme::builtin(&config.theme).is_none() {
+            return Err(format!("theme must be one of {}, not '{}'", theme::NAMES.join(", "), config.theme).into());
+        }
+        Keymap::new(&config.keys)?;
+        let gauges = &config.gauges;
+        if !(0.0..=100.0).contains(&gauges.warning) || !(gauges.warning..=100.0).contains(&gauges.critical) {
+            return Err(format!("gauges: need 0 <= warning <= critical <= 100, not {} and {}", gauges.warning, gauges.critical).into());
+        }
+        for rule in &config.colors {
+            parse_color(&rule.color)?;
+        }
+        if let Some(url) = &config.notify.webhook {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(format!("notify.webhook must be an http:// or https:// URL, not '{}'", url).into());
+            }
+        }
+        Ok(config)
+    }
+}
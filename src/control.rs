@@ -0,0 +1,73 @@
+use crate::paths;
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::Duration,
+};
+
+/// How long a client gets to send its command line
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Unix socket taking one command line per connection, as typed into the input box, and answering
+/// with the output lines. The UI loop runs the commands; see `requests`.
+pub struct Control {
+    pub path: PathBuf,
+    receiver: Receiver<(String, UnixStream)>,
+}
+
+impl Control {
+    pub fn start() -> io::Result<Control> {
+        let path = paths::control_socket();
+        if path.exists() {
+            // A socket nobody answers on is left over from an instance that did not shut down cleanly
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(ErrorKind::AddrInUse, format!("another proclynx is listening on {}", path.display())));
+            }
+            fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().filter_map(|s| s.ok()) {
+                let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+                let mut line = String::new();
+                let read = stream.try_clone().and_then(|s| BufReader::new(s).read_line(&mut line));
+                if read.is_err() || line.trim().is_empty() {
+                    let _ = writeln!(stream, "expected a command line");
+                    continue;
+                }
+                if sender.send((line.trim().to_string(), stream)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Control { path, receiver })
+    }
+
+    /// Command lines received since the last call, each with the connection to answer on
+    pub fn requests(&self) -> Vec<(String, UnixStream)> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub fn reply(mut stream: UnixStream, lines: &[String]) {
+    for line in lines {
+        if writeln!(stream, "{}", line).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use crate::records::Records;
+use hickory_resolver::{error::ResolveErrorKind, proto::rr::RecordType, Resolver};
+use std::time::Instant;
+
+/// A, AAAA and CNAME records of `name` through the system resolver configuration, each with the
+/// time its query took; a query that found nothing gets a row saying so
+pub fn dig(name: &str) -> Result<Records, String> {
+    let resolver = Resolver::from_system_conf().map_err(|e| format!("Error reading resolver configuration: {}", e))?;
+    let mut records = Records::new(&["NAME", "TTL", "TYPE", "DATA", "QUERY"]);
+    for record_type in [RecordType::A, RecordType::AAAA] {
+        let started = Instant::now();
+        let result = resolver.lookup(name, record_type);
+        let elapsed = format!("{:.1} ms", started.elapsed().as_secs_f64() * 1000.0);
+        match result {
+            // CNAMEs followed on the way come back alongside the final records
+            Ok(lookup) => {
+                for record in lookup.record_iter() {
+                    let data = record.data().map(|d| d.to_string()).unwrap_or_default();
+                    records.push(vec![record.name().to_string(), record.ttl().to_string(), record.record_type().to_string(), data, elapsed.clone()]);
+                }
+            },
+            Err(e) => {
+                let data = match e.kind() {
+                    ResolveErrorKind::NoRecordsFound { .. } => "no records".to_string(),
+                    _ => format!("failed: {}", e),
+                };
+                records.push(vec![name.to_string(), "-".to_string(), record_type.to_string(), data, elapsed]);
+            },
+        }
+    }
+    Ok(records)
+}
@@ -0,0 +1,210 @@
+use crate::records::Records;
+use pretty_bytes::converter::convert;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+/// `stop` waits out the container's grace period, 10 seconds unless configured otherwise
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Container {
+    id: String,
+    names: Vec<String>,
+    image: String,
+    status: String,
+}
+
+impl Container {
+    /// The name without the leading slash the API puts on it
+    fn name(&self) -> &str {
+        self.names.first().map(|n| n.trim_start_matches('/')).unwrap_or("")
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct CpuUsage {
+    #[serde(default)]
+    total_usage: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct CpuStats {
+    #[serde(default)]
+    cpu_usage: CpuUsage,
+    #[serde(default)]
+    system_cpu_usage: u64,
+    #[serde(default)]
+    online_cpus: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct MemoryStats {
+    #[serde(default)]
+    usage: u64,
+    #[serde(default)]
+    limit: u64,
+    #[serde(default)]
+    stats: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct NetworkStats {
+    rx_bytes: u64,
+    tx_bytes: u64,
+}
+
+#[derive(Deserialize)]
+struct Stats {
+    #[serde(default)]
+    cpu_stats: CpuStats,
+    #[serde(default)]
+    precpu_stats: CpuStats,
+    #[serde(default)]
+    memory_stats: MemoryStats,
+    #[serde(default)]
+    networks: HashMap<String, NetworkStats>,
+}
+
+impl Stats {
+    /// Percent of one core, the way `docker stats` counts it
+    fn cpu(&self) -> f64 {
+        let cpu = self.cpu_stats.cpu_usage.total_usage.saturating_sub(self.precpu_stats.cpu_usage.total_usage);
+        let system = self.cpu_stats.system_cpu_usage.saturating_sub(self.precpu_stats.system_cpu_usage);
+        if system == 0 {
+            return 0.0;
+        }
+        cpu as f64 / system as f64 * self.cpu_stats.online_cpus.max(1) as f64 * 100.0
+    }
+
+    /// Usage without the page cache, which the kernel can reclaim (`inactive_file` on cgroup v2)
+    fn mem(&self) -> u64 {
+        let stats = &self.memory_stats.stats;
+        let cache = ["total_inactive_file", "inactive_file", "cache"].iter().find_map(|key| stats.get(*key)?.as_u64()).unwrap_or(0);
+        self.memory_stats.usage.saturating_sub(cache)
+    }
+
+    fn net(&self) -> (u64, u64) {
+        self.networks.values().fold((0, 0), |(rx, tx), n| (rx + n.rx_bytes, tx + n.tx_bytes))
+    }
+}
+
+/// `DOCKER_HOST` when it names a Unix socket, then the Docker socket, then rootless and rootful Podman
+fn find_socket() -> Option<PathBuf> {
+    if let Some(path) = env::var("DOCKER_HOST").ok().and_then(|h| h.strip_prefix("unix://").map(PathBuf::from)) {
+        return Some(path);
+    }
+    let mut candidates = vec![PathBuf::from("/var/run/docker.sock")];
+    if let Ok(runtime) = env::var("XDG_RUNTIME_DIR") {
+        candidates.push(Path::new(&runtime).join("podman/podman.sock"));
+    }
+    candidates.push(PathBuf::from("/run/podman/podman.sock"));
+    candidates.into_iter().find(|p| p.exists())
+}
+
+/// Undoes `Transfer-Encoding: chunked`
+fn dechunk(body: &str) -> String {
+    let mut out = String::new();
+    let mut rest = body;
+    while let Some((size, tail)) = rest.split_once("\r\n") {
+        let Ok(size) = usize::from_str_radix(size.trim(), 16) else {
+            break;
+        };
+        if size == 0 || tail.len() < size {
+            break;
+        }
+        out.push_str(&tail[..size]);
+        rest = tail[size..].trim_start_matches("\r\n");
+    }
+    out
+}
+
+/// Sends one HTTP request over the socket and returns the status code and body
+fn request(socket: &Path, method: &str, path: &str) -> io::Result<(u16, String)> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    // HTTP/1.0 has the daemon close the connection after the body instead of keeping it open
+    write!(stream, "{} {} HTTP/1.0\r\nHost: docker\r\nContent-Length: 0\r\n\r\n", method, path)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP response"))?;
+    let chunked = head.lines().any(|l| l.to_lowercase().starts_with("transfer-encoding:") && l.to_lowercase().contains("chunked"));
+    Ok((status, if chunked { dechunk(body) } else { body.to_string() }))
+}
+
+/// The daemon puts the reason of a failed request in a JSON `message`
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().map(|m| m.to_string()))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+fn list_containers(socket: &Path) -> Result<Vec<Container>, String> {
+    match request(socket, "GET", "/containers/json") {
+        Ok((200, body)) => serde_json::from_str(&body).map_err(|e| format!("docker: unexpected container list: {}", e)),
+        Ok((status, body)) => Err(format!("docker: listing containers failed ({}): {}", status, error_message(&body))),
+        Err(e) => Err(format!("docker: {}: {}", socket.display(), e)),
+    }
+}
+
+fn get_stats(socket: &Path, id: &str) -> Option<Stats> {
+    match request(socket, "GET", &format!("/containers/{}/stats?stream=false", id)) {
+        Ok((200, body)) => serde_json::from_str(&body).ok(),
+        _ => None,
+    }
+}
+
+/// Running containers with their CPU, memory and network totals
+pub fn get_containers() -> Result<Records, String> {
+    let socket = find_socket().ok_or("docker: no Docker or Podman socket found; set DOCKER_HOST=unix:///path/to/socket")?;
+    let containers = list_containers(&socket)?;
+    // Each stats call blocks for a sampling interval, so ask for all of them at once
+    let stats: Vec<Option<Stats>> = thread::scope(|s| {
+        let handles: Vec<_> = containers.iter().map(|c| s.spawn(|| get_stats(&socket, &c.id))).collect();
+        handles.into_iter().map(|h| h.join().ok().flatten()).collect()
+    });
+    let mut records = Records::new(&["ID", "NAME", "IMAGE", "%CPU", "MEM / LIMIT", "NET RX / TX", "STATUS"]);
+    for (container, stats) in containers.iter().zip(stats) {
+        let id: String = container.id.chars().take(12).collect();
+        let (cpu, mem, net) = match &stats {
+            Some(stats) => {
+                let (rx, tx) = stats.net();
+                (
+                    format!("{:.1}", stats.cpu()),
+                    format!("{} / {}", convert(stats.mem() as f64), convert(stats.memory_stats.limit as f64)),
+                    format!("{} / {}", convert(rx as f64), convert(tx as f64)),
+                )
+            },
+            None => ("-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        records.push(vec![id, container.name().to_string(), container.image.clone(), cpu, mem, net, container.status.clone()]);
+    }
+    Ok(records)
+}
+
+/// Stops (SIGTERM, then SIGKILL after the container's timeout) or kills a container by ID or name
+pub fn signal_container(action: &str, target: &str) -> String {
+    let Some(socket) = find_socket() else {
+        return "docker: no Docker or Podman socket found; set DOCKER_HOST=unix:///path/to/socket".to_string();
+    };
+    match request(&socket, "POST", &format!("/containers/{}/{}", target, action)) {
+        Ok((204, _)) => format!("docker: {} {}", if action == "stop" { "stopped" } else { "killed" }, target),
+        Ok((304, _)) => format!("docker: {} is not running", target),
+        Ok((status, body)) => format!("docker: {} {} failed ({}): {}", action, target, status, error_message(&body)),
+        Err(e) => format!("docker: {}: {}", socket.display(), e),
+    }
+}
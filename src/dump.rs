@@ -0,0 +1,115 @@
+use crate::{paging::PagingTracker, procio::IoTracker, rules::Snapshot, sched::SchedTracker};
+use chrono::Local;
+use std::{collections::BTreeMap, error::Error, fmt::Write as _, fs};
+
+/// Sampler state the report draws its recent metrics from
+pub struct Metrics<'a> {
+    pub snapshot: Option<&'a Snapshot>,
+    pub io: &'a IoTracker,
+    pub paging: &'a PagingTracker,
+    pub sched: &'a SchedTracker,
+}
+
+fn section(report: &mut String, title: &str, body: Result<String, std::io::Error>) {
+    let _ = writeln!(report, "== {} ==", title);
+    match body {
+        Ok(body) => {
+            report.push_str(body.trim_end());
+            report.push('\n');
+        },
+        Err(e) => {
+            let _ = writeln!(report, "(unavailable: {})", e);
+        },
+    }
+    report.push('\n');
+}
+
+/// Open file descriptors and what they point at
+fn file_descriptors(pid: i32) -> Result<String, std::io::Error> {
+    let mut fds: Vec<(u32, String)> = fs::read_dir(format!("/proc/{}/fd", pid))?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let fd = e.file_name().to_str()?.parse::<u32>().ok()?;
+            let target = fs::read_link(e.path()).map(|t| t.display().to_string()).unwrap_or_else(|_| "?".to_string());
+            Some((fd, target))
+        })
+        .collect();
+    fds.sort();
+    Ok(fds.iter().map(|(fd, target)| format!("{:>5} -> {}", fd, target)).collect::<Vec<String>>().join("\n"))
+}
+
+/// Mapped size per backing file (or [heap], [stack], anonymous), largest first
+fn maps_summary(pid: i32) -> Result<String, std::io::Error> {
+    let maps = fs::read_to_string(format!("/proc/{}/maps", pid))?;
+    let mut sizes: BTreeMap<String, (u64, usize)> = BTreeMap::new();
+    for line in maps.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some((start, end)) = fields.first().and_then(|r| r.split_once('-')) else {
+            continue;
+        };
+        let (Ok(start), Ok(end)) = (u64::from_str_radix(start, 16), u64::from_str_radix(end, 16)) else {
+            continue;
+        };
+        let name = if fields.len() > 5 { fields[5..].join(" ") } else { "[anonymous]".to_string() };
+        let entry = sizes.entry(name).or_insert((0, 0));
+        entry.0 += end - start;
+        entry.1 += 1;
+    }
+    let mut sizes: Vec<(String, (u64, usize))> = sizes.into_iter().collect();
+    sizes.sort_by_key(|(_, (size, _))| std::cmp::Reverse(*size));
+    let total: u64 = sizes.iter().map(|(_, (size, _))| size).sum();
+    let mut summary = format!("{} regions, {} KiB mapped\n", sizes.iter().map(|(_, (_, n))| n).sum::<usize>(), total / 1024);
+    for (name, (size, regions)) in sizes {
+        let _ = writeln!(summary, "{:>12} KiB {:>5} regions  {}", size / 1024, regions, name);
+    }
+    Ok(summary)
+}
+
+fn environment(pid: i32) -> Result<String, std::io::Error> {
+    let environ = fs::read(format!("/proc/{}/environ", pid))?;
+    Ok(environ
+        .split(|b| *b == 0)
+        .filter(|v| !v.is_empty())
+        .map(|v| String::from_utf8_lossy(v).to_string())
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn recent_metrics(pid: i32, metrics: &Metrics) -> String {
+    let mut report = String::new();
+    if let Some(process) = metrics.snapshot.and_then(|s| s.processes.iter().find(|p| p.pid == pid)) {
+        let _ = writeln!(report, "cpu {:.1}%  mem {:.1}%  major faults {:.1}/s", process.cpu, process.mem, process.major_faults);
+    }
+    if let Some((read, write)) = metrics.io.rates.get(&pid) {
+        let _ = writeln!(report, "disk read {:.0} B/s  write {:.0} B/s", read, write);
+    }
+    if let Some(faults) = metrics.paging.process_faults.get(&pid) {
+        let _ = writeln!(report, "major faults {:.1}/s", faults);
+    }
+    if let Some(series) = metrics.sched.series.get(&pid) {
+        let values: Vec<String> = series.iter().map(|v| format!("{:.2}", v)).collect();
+        let _ = writeln!(report, "run queue latency ms/s, oldest first: {}", values.join(" "));
+    }
+    if report.is_empty() {
+        report.push_str("(no samples yet)\n");
+    }
+    report
+}
+
+/// Writes everything worth keeping about `pid` to `path`, returning the report size in bytes
+pub fn dump(pid: i32, path: &str, metrics: &Metrics) -> Result<usize, Box<dyn Error>> {
+    // Fail early, and with a clear message, if the process is already gone
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).map_err(|e| format!("PID {}: {}", pid, e))?;
+    let mut report = String::new();
+    let _ = writeln!(report, "proclynx dump of PID {} taken {}", pid, Local::now().format("%Y-%m-%d %H:%M:%S %z"));
+    let _ = writeln!(report, "command line: {}\n", String::from_utf8_lossy(&cmdline).replace('\0', " ").trim_end());
+    section(&mut report, "status", fs::read_to_string(format!("/proc/{}/status", pid)));
+    section(&mut report, "limits", fs::read_to_string(format!("/proc/{}/limits", pid)));
+    section(&mut report, "cgroup", fs::read_to_string(format!("/proc/{}/cgroup", pid)));
+    section(&mut report, "recent metrics", Ok(recent_metrics(pid, metrics)));
+    section(&mut report, "file descriptors", file_descriptors(pid));
+    section(&mut report, "memory maps", maps_summary(pid));
+    section(&mut report, "environment", environment(pid));
+    fs::write(path, &report)?;
+    Ok(report.len())
+}
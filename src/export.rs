@@ -0,0 +1,52 @@
+use crate::{ptable::ProcessTable, records::Records, rules::Snapshot};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+/// Writes every view, as text and as CSV, into `target`, a directory or (if it ends in .tar/.tar.gz)
+/// an archive, and returns the files written
+pub fn export_all(target: &str, views: &[(&str, Records)], table: &ProcessTable, snapshot: Option<&Snapshot>) -> Result<Vec<String>, Box<dyn Error>> {
+    let archive = [".tar", ".tar.gz", ".tgz"].iter().any(|ext| target.ends_with(ext));
+    let dir: PathBuf = if archive {
+        env::temp_dir().join(format!("proclynx-export-{}", process::id()))
+    } else {
+        PathBuf::from(target)
+    };
+    fs::create_dir_all(&dir)?;
+    let mut written: Vec<String> = vec![];
+    for (name, records) in views {
+        let file = format!("{}.txt", name);
+        fs::write(dir.join(&file), records.lines().join("\n") + "\n")?;
+        written.push(file);
+        let file = format!("{}.csv", name);
+        fs::write(dir.join(&file), records.to_csv())?;
+        written.push(file);
+    }
+    fs::write(dir.join("ptable.json"), serde_json::to_string_pretty(&table.rows)?)?;
+    written.push("ptable.json".to_string());
+    if let Some(snapshot) = snapshot {
+        fs::write(dir.join("snapshot.json"), serde_json::to_string_pretty(snapshot)?)?;
+        written.push("snapshot.json".to_string());
+    }
+    if archive {
+        let result = pack(&dir, target);
+        let _ = fs::remove_dir_all(&dir);
+        result?;
+    }
+    Ok(written)
+}
+
+fn pack(dir: &Path, target: &str) -> Result<(), Box<dyn Error>> {
+    let compress = if target.ends_with(".tar") { "-cf" } else { "-czf" };
+    // tar resolves the archive path against its own working directory, not ours
+    let target = env::current_dir()?.join(target);
+    let output = Command::new("tar").arg("-C").arg(dir).arg(compress).arg(&target).arg(".").output()?;
+    if !output.status.success() {
+        return Err(format!("tar: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use crate::{config::Gauges, rules::Snapshot, theme::Theme};
+use pretty_bytes::converter::convert;
+use sysinfo::{System, SystemExt};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Gauge, Paragraph},
+    Frame,
+};
+
+/// Rows each gauge takes, borders included
+const HEIGHT: u16 = 3;
+
+/// Longest label shown beside a bar before it is cut
+const MAX_LABEL: usize = 24;
+
+/// Used out of total bytes of one resource
+pub struct Usage {
+    pub label: String,
+    pub used: u64,
+    pub total: u64,
+}
+
+impl Usage {
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+/// RAM and swap as of now
+pub fn memory() -> Vec<Usage> {
+    let mut sys = System::new();
+    sys.refresh_memory();
+    vec![
+        Usage { label: "Memory".to_string(), used: sys.used_memory(), total: sys.total_memory() },
+        Usage { label: "Swap".to_string(), used: sys.used_swap(), total: sys.total_swap() },
+    ]
+}
+
+/// Used space of each filesystem in the snapshot
+pub fn filesystems(snap: &Snapshot) -> Vec<Usage> {
+    snap.filesystems
+        .iter()
+        .map(|fs| Usage { label: fs.mount_point.clone(), used: fs.total.saturating_sub(fs.available), total: fs.total })
+        .collect()
+}
+
+/// Colour of a bar at `percent`: the accent, then the warning and alert colours past each threshold
+fn color(percent: f64, thresholds: &Gauges, theme: &Theme) -> Color {
+    let color = if percent >= thresholds.critical {
+        theme.alert_bg
+    } else if percent >= thresholds.warning {
+        theme.warning_bg
+    } else {
+        theme.accent
+    };
+    // An unset colour would leave the bar invisible
+    if color == Color::Reset {
+        Color::Gray
+    } else {
+        color
+    }
+}
+
+/// Rows `render` needs for `usages`
+pub fn height(usages: &[Usage]) -> u16 {
+    usages.len() as u16 * HEIGHT
+}
+
+/// One bordered gauge per resource, labelled with its byte counts
+pub fn render<B: Backend>(f: &mut Frame<B>, area: Rect, usages: &[Usage], thresholds: &Gauges, theme: &Theme) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(usages.iter().map(|_| Constraint::Length(HEIGHT)).collect::<Vec<_>>())
+        .split(area);
+    for (usage, chunk) in usages.iter().zip(chunks) {
+        let percent = usage.percent();
+        let label = format!("{} of {} ({:.1}%)", convert(usage.used as f64), convert(usage.total as f64), percent);
+        let gauge = Gauge::default()
+            .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(usage.label.as_str()))
+            .style(theme.text())
+            .gauge_style(Style::default().fg(color(percent, thresholds, theme)).bg(theme.background))
+            .ratio((percent / 100.0).clamp(0.0, 1.0))
+            .label(Span::styled(label, Style::default().add_modifier(Modifier::BOLD)))
+            .use_unicode(true);
+        f.render_widget(gauge, chunk);
+    }
+}
+
+/// Rows `render_bars` needs for `usages`, leaving at least half of `available` to what is below
+pub fn bars_height(usages: &[Usage], available: u16) -> u16 {
+    (usages.len() as u16 + 2).min(available / 2)
+}
+
+/// One single-line bar per resource in a shared box, labelled on the left, for many of them at once
+pub fn render_bars<B: Backend>(f: &mut Frame<B>, area: Rect, title: &str, usages: &[Usage], thresholds: &Gauges, theme: &Theme) {
+    let block = Block::default().borders(Borders::ALL).border_style(theme.border()).title(title);
+    let inner = block.inner(area);
+    f.render_widget(block.style(theme.text()), area);
+    let width = usages.iter().map(|u| u.label.chars().count()).max().unwrap_or(0).min(MAX_LABEL);
+    for (i, usage) in usages.iter().take(inner.height as usize).enumerate() {
+        let row = Rect { y: inner.y + i as u16, height: 1, ..inner };
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(width as u16 + 1), Constraint::Min(1)])
+            .split(row);
+        let label: String = usage.label.chars().take(width).collect();
+        f.render_widget(Paragraph::new(label).style(theme.text()), columns[0]);
+        let percent = usage.percent();
+        let gauge = Gauge::default()
+            .style(theme.text())
+            .gauge_style(Style::default().fg(color(percent, thresholds, theme)).bg(theme.background))
+            .ratio((percent / 100.0).clamp(0.0, 1.0))
+            .label(Span::styled(format!("{:.0}% of {}", percent, convert(usage.total as f64)), Style::default().add_modifier(Modifier::BOLD)))
+            .use_unicode(true);
+        f.render_widget(gauge, columns[1]);
+    }
+}
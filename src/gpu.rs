@@ -0,0 +1,126 @@
+use crate::records::Records;
+use pretty_bytes::converter::convert;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, process::Command};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GpuSample {
+    pub name: String,
+    /// Degrees Celsius
+    pub temp: Option<f64>,
+    /// Busy percentage
+    pub util: Option<f64>,
+    /// VRAM in bytes
+    pub mem_used: u64,
+    pub mem_total: u64,
+}
+
+impl GpuSample {
+    pub fn mem_percent(&self) -> Option<f64> {
+        if self.mem_total == 0 {
+            return None;
+        }
+        Some(self.mem_used as f64 * 100.0 / self.mem_total as f64)
+    }
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    fs::read_to_string(path).ok()?.trim().parse::<f64>().ok()
+}
+
+/// AMD (amdgpu) and Intel cards expose their counters through the DRM sysfs tree
+fn read_drm_gpus() -> Vec<GpuSample> {
+    let mut gpus: Vec<GpuSample> = vec![];
+    let mut cards: Vec<_> = match fs::read_dir("/sys/class/drm") {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.file_name().and_then(|n| n.to_str()).map(|n| n.starts_with("card") && !n.contains('-')).unwrap_or(false))
+            .collect(),
+        Err(_) => return gpus,
+    };
+    cards.sort();
+    for card in cards {
+        let device = card.join("device");
+        let util = read_number(&device.join("gpu_busy_percent"));
+        let mem_used = read_number(&device.join("mem_info_vram_used")).unwrap_or(0.0) as u64;
+        let mem_total = read_number(&device.join("mem_info_vram_total")).unwrap_or(0.0) as u64;
+        // hwmon reports millidegrees; take the first temperature input ("edge" on amdgpu)
+        let temp = fs::read_dir(device.join("hwmon"))
+            .ok()
+            .and_then(|mut entries| entries.next())
+            .and_then(|e| e.ok())
+            .and_then(|e| read_number(&e.path().join("temp1_input")))
+            .map(|t| t / 1000.0);
+        if util.is_none() && mem_total == 0 && temp.is_none() {
+            continue;
+        }
+        gpus.push(GpuSample {
+            name: card.file_name().unwrap().to_string_lossy().to_string(),
+            temp,
+            util,
+            mem_used,
+            mem_total,
+        });
+    }
+    gpus
+}
+
+/// NVIDIA cards are only reachable through the driver's nvidia-smi tool
+fn read_nvidia_gpus() -> Vec<GpuSample> {
+    let output = match Command::new("nvidia-smi")
+        .args(["--query-gpu=name,temperature.gpu,utilization.gpu,memory.used,memory.total", "--format=csv,noheader,nounits"])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return vec![],
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            // memory columns are in MiB
+            let mib = |f: &str| f.parse::<u64>().unwrap_or(0) * 1024 * 1024;
+            Some(GpuSample {
+                name: fields[0].to_string(),
+                temp: fields[1].parse::<f64>().ok(),
+                util: fields[2].parse::<f64>().ok(),
+                mem_used: mib(fields[3]),
+                mem_total: mib(fields[4]),
+            })
+        })
+        .collect()
+}
+
+pub fn read_gpus() -> Vec<GpuSample> {
+    let mut gpus = read_drm_gpus();
+    gpus.extend(read_nvidia_gpus());
+    gpus
+}
+
+pub fn get_gpu_information() -> Records {
+    let mut records = Records::new(&["Name", "Temperature", "Utilization", "VRAM"]);
+    for gpu in read_gpus() {
+        let temp = gpu.temp.map(|t| format!("{:.0}°C", t)).unwrap_or_else(|| "-".to_string());
+        let util = gpu.util.map(|u| format!("{:.0}%", u)).unwrap_or_else(|| "-".to_string());
+        let mem = match gpu.mem_percent() {
+            Some(percent) => format!("{} / {} ({:.0}%)", convert(gpu.mem_used as f64), convert(gpu.mem_total as f64), percent),
+            None => "-".to_string(),
+        };
+        records.push(vec![gpu.name, temp, util, mem]);
+    }
+    records
+}
+
+/// Whether `pid` holds a GPU device open (an NVIDIA device node or a DRM card/render node)
+pub fn uses_gpu(pid: i32) -> bool {
+    let Ok(fds) = fs::read_dir(format!("/proc/{}/fd", pid)) else {
+        return false;
+    };
+    fds.filter_map(|e| e.ok())
+        .filter_map(|e| fs::read_link(e.path()).ok())
+        .any(|target| target.starts_with("/dev/dri") || target.to_str().map(|t| t.starts_with("/dev/nvidia")).unwrap_or(false))
+}
@@ -0,0 +1,189 @@
+use crate::{chart, history::History, network, rules::Snapshot, theme::Theme};
+use pretty_bytes::converter::convert;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    symbols::Marker,
+    text::Span,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Sparkline},
+    Frame,
+};
+
+/// How far back the graphs reach
+const WINDOW_SECS: u64 = 300;
+
+/// Width of the per-core labels beside their sparklines
+const LABEL_WIDTH: u16 = 14;
+
+/// Plotted from the history store by `graph`
+pub enum Graph {
+    Cpu,
+    /// Throughput of one interface, or of all of them summed
+    Net(Option<String>),
+}
+
+impl Graph {
+    pub fn parse(args: &[String]) -> Result<Graph, String> {
+        match (args.first().map(|s| s.as_str()), args.get(1)) {
+            (Some("cpu"), None) => Ok(Graph::Cpu),
+            (Some("net"), None) => Ok(Graph::Net(None)),
+            (Some("net"), Some(interface)) if args.len() == 2 => {
+                let interfaces = network::interfaces();
+                if !interfaces.contains(interface) {
+                    return Err(format!("unknown interface '{}'; try {}", interface, interfaces.join(", ")));
+                }
+                Ok(Graph::Net(Some(interface.clone())))
+            },
+            _ => Err("usage: graph cpu | graph net [interface]".to_string()),
+        }
+    }
+}
+
+/// Samples from the last `WINDOW_SECS`
+fn window(history: &History) -> Vec<&Snapshot> {
+    let now = history.samples.back().map(|s| s.time).unwrap_or(0);
+    history.samples.iter().filter(|s| s.time + WINDOW_SECS >= now).collect()
+}
+
+/// Received and transmitted bytes per second of each sample
+fn net_values(samples: &[&Snapshot], interface: Option<&str>) -> (Vec<f64>, Vec<f64>) {
+    samples
+        .iter()
+        .map(|s| match interface {
+            Some(name) => s.interfaces.iter().find(|i| i.name == name).map(|i| (i.rx, i.tx)).unwrap_or((0.0, 0.0)),
+            None => (s.net_rx, s.net_tx),
+        })
+        .unzip()
+}
+
+fn core_values(samples: &[&Snapshot], core: usize) -> Vec<f64> {
+    samples.iter().map(|s| s.cores.get(core).copied().unwrap_or(0.0)).collect()
+}
+
+/// Lines of the braille charts in the text form
+const BRAILLE_HEIGHT: usize = 4;
+
+/// A braille chart with the scale beside its top and bottom lines
+fn braille_lines(values: &[f64], max: f64, top: &str, width: usize) -> Vec<String> {
+    let rows = chart::braille(values, max, width, BRAILLE_HEIGHT);
+    let last = rows.len().saturating_sub(1);
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let label = match i {
+                0 => top,
+                _ if i == last => "0",
+                _ => "",
+            };
+            format!("{:>14} ┤{}", label, row)
+        })
+        .collect()
+}
+
+/// The graph as text, for `exec`, `proclynxctl` and remote hosts
+pub fn lines(graph: &Graph, history: &History, width: usize) -> Vec<String> {
+    let samples = window(history);
+    match graph {
+        Graph::Cpu => {
+            let cores = samples.last().map(|s| s.cores.len()).unwrap_or(0);
+            let overall: Vec<f64> = samples.iter().map(|s| s.cpu).collect();
+            let mut vec = vec![format!("{:<8} {:>6.1}%", "cpu", overall.last().copied().unwrap_or(0.0))];
+            vec.extend(braille_lines(&overall, 100.0, "100%", width));
+            for core in 0..cores {
+                let values = core_values(&samples, core);
+                vec.push(format!("{:<8} {:>6.1}%  {}", format!("cpu{}", core), values.last().copied().unwrap_or(0.0), chart::sparkline(&values, width)));
+            }
+            vec
+        },
+        Graph::Net(interface) => {
+            let (rx, tx) = net_values(&samples, interface.as_deref());
+            // Both on one scale, so the larger direction is visibly larger
+            let top = rx.iter().chain(&tx).copied().fold(0.0, f64::max).max(1.0);
+            let rate = |values: &[f64]| format!("{}/s", convert(values.last().copied().unwrap_or(0.0)));
+            let mut vec = vec![format!("{} (bytes/s, last {} minutes)", interface.as_deref().unwrap_or("all interfaces"), WINDOW_SECS / 60)];
+            vec.push(format!("{:<4} {:>14}", "rx", rate(&rx)));
+            vec.extend(braille_lines(&rx, top, &format!("{}/s", convert(top)), width));
+            vec.push(format!("{:<4} {:>14}", "tx", rate(&tx)));
+            vec.extend(braille_lines(&tx, top, &format!("{}/s", convert(top)), width));
+            vec
+        },
+    }
+}
+
+pub fn render<B: Backend>(f: &mut Frame<B>, area: Rect, graph: &Graph, history: &History, theme: &Theme) {
+    match graph {
+        Graph::Cpu => render_cpu(f, area, history, theme),
+        Graph::Net(interface) => render_net(f, area, history, interface.as_deref(), theme),
+    }
+}
+
+fn time_axis() -> Axis<'static> {
+    Axis::default().bounds([-(WINDOW_SECS as f64), 0.0]).labels(vec![Span::raw(format!("-{}s", WINDOW_SECS)), Span::raw("now")])
+}
+
+/// Overall utilization as a line chart over the window, each core as a sparkline underneath
+fn render_cpu<B: Backend>(f: &mut Frame<B>, area: Rect, history: &History, theme: &Theme) {
+    let samples = window(history);
+    let now = samples.last().map(|s| s.time).unwrap_or(0) as f64;
+    let cores = samples.last().map(|s| s.cores.len()).unwrap_or(0);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let points: Vec<(f64, f64)> = samples.iter().map(|s| (s.time as f64 - now, s.cpu)).collect();
+    let dataset = Dataset::default()
+        .name("cpu %")
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(theme.accent))
+        .data(&points);
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(format!("CPU, last {} minutes", WINDOW_SECS / 60)))
+        .style(theme.text())
+        .x_axis(time_axis())
+        .y_axis(Axis::default().bounds([0.0, 100.0]).labels(vec![Span::raw("0"), Span::raw("50"), Span::raw("100")]));
+    f.render_widget(chart, chunks[0]);
+
+    let block = Block::default().borders(Borders::ALL).border_style(theme.border()).title("Per core");
+    let inner = block.inner(chunks[1]);
+    f.render_widget(block.style(theme.text()), chunks[1]);
+    // As many cores as there are lines; the text output has all of them
+    for core in 0..cores.min(inner.height as usize) {
+        let row = Rect { y: inner.y + core as u16, height: 1, ..inner };
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(LABEL_WIDTH), Constraint::Min(1)])
+            .split(row);
+        let values = core_values(&samples, core);
+        let label = format!("cpu{:<3} {:>5.1}%", core, values.last().copied().unwrap_or(0.0));
+        f.render_widget(Paragraph::new(label).style(theme.text()), columns[0]);
+        // Sparkline takes integers; the newest values that fit, scaled against 100%
+        let data: Vec<u64> = values.iter().skip(values.len().saturating_sub(columns[1].width as usize)).map(|v| v.round() as u64).collect();
+        f.render_widget(Sparkline::default().data(&data).max(100).style(Style::default().fg(theme.accent)), columns[1]);
+    }
+}
+
+/// Received and transmitted bytes per second as two lines, scaled to the busiest moment in the window
+fn render_net<B: Backend>(f: &mut Frame<B>, area: Rect, history: &History, interface: Option<&str>, theme: &Theme) {
+    let samples = window(history);
+    let now = samples.last().map(|s| s.time).unwrap_or(0) as f64;
+    let (rx, tx) = net_values(&samples, interface);
+    let points = |values: &[f64]| -> Vec<(f64, f64)> { samples.iter().zip(values).map(|(s, v)| (s.time as f64 - now, *v)).collect() };
+    let (rx_points, tx_points) = (points(&rx), points(&tx));
+    let top = rx.iter().chain(&tx).copied().fold(0.0, f64::max).max(1.0);
+    let line = |name: String, color, data| Dataset::default().name(name).marker(Marker::Braille).graph_type(GraphType::Line).style(Style::default().fg(color)).data(data);
+    let rate = |values: &[f64]| convert(values.last().copied().unwrap_or(0.0));
+    let datasets = vec![
+        line(format!("rx {}/s", rate(&rx)), theme.accent, &rx_points),
+        line(format!("tx {}/s", rate(&tx)), theme.goto, &tx_points),
+    ];
+    let title = format!("Network {}, last {} minutes", interface.unwrap_or("all interfaces"), WINDOW_SECS / 60);
+    let chart = Chart::new(datasets)
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(title))
+        .style(theme.text())
+        .x_axis(time_axis())
+        .y_axis(Axis::default().bounds([0.0, top]).labels(vec![Span::raw("0"), Span::raw(format!("{}/s", convert(top / 2.0))), Span::raw(format!("{}/s", convert(top)))]));
+    f.render_widget(chart, area);
+}
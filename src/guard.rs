@@ -0,0 +1,207 @@
+use crate::{paths, rules::Snapshot};
+use chrono::Local;
+use nix::{
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
+use pretty_bytes::converter::convert;
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process,
+};
+
+/// Seconds a process gets to exit after SIGTERM before it is sent SIGKILL
+pub const DEFAULT_GRACE: u64 = 10;
+
+/// Limits enforced on every process whose name contains `pattern`
+pub struct Guard {
+    pub id: usize,
+    pub pattern: String,
+    /// Resident memory in bytes
+    pub mem: Option<u64>,
+    /// CPU usage in percent of one core
+    pub cpu: Option<f64>,
+    pub grace: u64,
+}
+
+impl Guard {
+    pub fn describe(&self) -> String {
+        let mut limits: Vec<String> = vec![];
+        if let Some(mem) = self.mem {
+            limits.push(format!("mem {}", convert(mem as f64)));
+        }
+        if let Some(cpu) = self.cpu {
+            limits.push(format!("cpu {}%", cpu));
+        }
+        format!("[{}] '{}' {}, SIGKILL after {}s", self.id, self.pattern, limits.join(" "), self.grace)
+    }
+
+    /// Why the process breaks this guard's limits, if it does
+    fn violation(&self, rss: u64, cpu: f64) -> Option<String> {
+        match (self.mem, self.cpu) {
+            (Some(mem), _) if rss > mem => Some(format!("rss {} > {}", convert(rss as f64), convert(mem as f64))),
+            (_, Some(limit)) if cpu > limit => Some(format!("cpu {:.1}% > {}%", cpu, limit)),
+            _ => None,
+        }
+    }
+}
+
+/// Parses "2G", "512M", "1.5G" or plain bytes, with binary multiples
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim_end_matches(['B', 'b']);
+    let (number, multiplier) = match text.char_indices().last()? {
+        (i, 'K' | 'k') => (&text[..i], 1u64 << 10),
+        (i, 'M' | 'm') => (&text[..i], 1 << 20),
+        (i, 'G' | 'g') => (&text[..i], 1 << 30),
+        (i, 'T' | 't') => (&text[..i], 1 << 40),
+        _ => (text, 1),
+    };
+    let number = number.parse::<f64>().ok().filter(|n| *n > 0.0)?;
+    Some((number * multiplier as f64) as u64)
+}
+
+pub fn audit_path() -> PathBuf {
+    paths::state_dir().join("guard.log")
+}
+
+fn audit(line: &str) -> io::Result<()> {
+    fs::create_dir_all(paths::state_dir())?;
+    let mut file = OpenOptions::new().create(true).append(true).open(audit_path())?;
+    writeln!(file, "{} {}", Local::now().format("%Y-%m-%d %H:%M:%S"), line)
+}
+
+/// A process sent SIGTERM that has not been seen to exit yet
+struct Terminating {
+    /// What sent it: "guard [1]" or "rule 'name'"
+    by: String,
+    name: String,
+    since: u64,
+    grace: u64,
+}
+
+#[derive(Default)]
+pub struct Guards {
+    pub list: Vec<Guard>,
+    next_id: usize,
+    terminating: HashMap<i32, Terminating>,
+}
+
+impl Guards {
+    pub fn add(&mut self, pattern: &str, mem: Option<u64>, cpu: Option<f64>, grace: u64) -> &Guard {
+        self.next_id += 1;
+        self.list.push(Guard { id: self.next_id, pattern: pattern.to_string(), mem, cpu, grace });
+        self.list.last().unwrap()
+    }
+
+    pub fn remove(&mut self, id: usize) -> bool {
+        let before = self.list.len();
+        self.list.retain(|g| g.id != id);
+        self.list.len() != before
+    }
+
+    /// Sends SIGTERM to processes over a limit and SIGKILL to those still running after the grace
+    /// period, writing every action to the audit log. Returns the same lines for the user.
+    pub fn check(&mut self, snap: &Snapshot) -> Vec<String> {
+        let mut events: Vec<String> = vec![];
+        let alive: HashMap<i32, &str> = snap.processes.iter().map(|p| (p.pid, p.name.as_str())).collect();
+        self.terminating.retain(|pid, t| {
+            if alive.get(pid) != Some(&t.name.as_str()) {
+                events.push(format!("{}: PID {} {} exited", t.by, pid, t.name));
+                return false;
+            }
+            if snap.time.saturating_sub(t.since) < t.grace {
+                return true;
+            }
+            match kill(Pid::from_raw(*pid), Signal::SIGKILL) {
+                Ok(_) => events.push(format!("{}: SIGKILL PID {} {}, still running {}s after SIGTERM", t.by, pid, t.name, t.grace)),
+                Err(e) => events.push(format!("{}: SIGKILL PID {} {} failed: {}", t.by, pid, t.name, e)),
+            }
+            false
+        });
+        let own_pid = process::id() as i32;
+        let mut violations: Vec<(String, i32, String, String, u64)> = vec![];
+        for guard in &self.list {
+            for p in snap.processes.iter().filter(|p| p.pid != own_pid && p.name.contains(guard.pattern.as_str())) {
+                if let Some(reason) = guard.violation(p.rss, p.cpu) {
+                    violations.push((format!("guard [{}]", guard.id), p.pid, p.name.clone(), reason, guard.grace));
+                }
+            }
+        }
+        for (by, pid, name, reason, grace) in violations {
+            events.extend(self.terminate(by, pid, &name, &reason, snap.time, grace));
+        }
+        events.into_iter().map(audited).collect()
+    }
+
+    /// Sends SIGTERM to a process an autokill rule fired on, then SIGKILL after the default grace
+    /// period like a guard would, writing both to the audit log
+    pub fn autokill(&mut self, rule: &str, pid: i32, name: &str, reason: &str, time: u64) -> Option<String> {
+        if pid == process::id() as i32 {
+            return None;
+        }
+        self.terminate(format!("rule '{}'", rule), pid, name, reason, time, DEFAULT_GRACE).map(audited)
+    }
+
+    /// Sends SIGTERM unless the process is already being terminated, tracking it for SIGKILL
+    fn terminate(&mut self, by: String, pid: i32, name: &str, reason: &str, time: u64, grace: u64) -> Option<String> {
+        if self.terminating.contains_key(&pid) {
+            return None;
+        }
+        match kill(Pid::from_raw(pid), Signal::SIGTERM) {
+            Ok(_) => {
+                let event = format!("{}: SIGTERM PID {} {}, {}", by, pid, name, reason);
+                self.terminating.insert(pid, Terminating { by, name: name.to_string(), since: time, grace });
+                Some(event)
+            },
+            // Not retried: the next sample would fail the same way and flood the log
+            Err(e) => {
+                let event = format!("{}: SIGTERM PID {} {} failed: {}; {}", by, pid, name, e, reason);
+                self.terminating.insert(pid, Terminating { by, name: name.to_string(), since: time, grace: u64::MAX });
+                Some(event)
+            },
+        }
+    }
+}
+
+/// Writes `event` to the audit log, noting on the event itself if that failed
+fn audited(mut event: String) -> String {
+    if let Err(e) = audit(&event) {
+        event.push_str(&format!(" (not audited: {})", e));
+    }
+    event
+}
+
+/// The audit log, oldest entry first
+pub fn get_audit_log() -> Vec<String> {
+    match fs::read_to_string(audit_path()) {
+        Ok(text) => text.lines().map(|l| l.to_string()).collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec!["No guard actions recorded yet".to_string()],
+        Err(e) => vec![format!("Error reading {}: {}", audit_path().display(), e)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_reads_binary_multiples() {
+        assert_eq!(parse_size("100"), Some(100));
+        assert_eq!(parse_size("4k"), Some(4096));
+        assert_eq!(parse_size("512M"), Some(512 << 20));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("2GB"), Some(2 << 30));
+        assert_eq!(parse_size("1.5G"), Some(3 << 29));
+        assert_eq!(parse_size("1T"), Some(1 << 40));
+    }
+
+    #[test]
+    fn parse_size_refuses_nonsense() {
+        for text in ["", "G", "0", "0M", "-1G", "abc", "2X", "1,5G"] {
+            assert_eq!(parse_size(text), None, "{}", text);
+        }
+    }
+}
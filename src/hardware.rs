@@ -0,0 +1,139 @@
+use crate::records::Records;
+use pretty_bytes::converter::convert;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Loaded kernel modules from /proc/modules, optionally only those whose name contains `filter`
+pub fn get_modules(filter: Option<&str>) -> Result<Records, String> {
+    let modules = fs::read_to_string("/proc/modules").map_err(|e| format!("Error reading /proc/modules: {}", e))?;
+    let mut records = Records::new(&["Module", "Size", "Used by", "Dependents"]);
+    let mut rows: Vec<Vec<&str>> = modules
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .filter(|fields| fields.len() >= 4)
+        .filter(|fields| filter.map(|f| fields[0].contains(f)).unwrap_or(true))
+        .collect();
+    rows.sort_by_key(|fields| fields[0]);
+    for fields in rows {
+        let size = fields[1].parse::<f64>().map(convert).unwrap_or_else(|_| fields[1].to_string());
+        // "-" marks a module nobody depends on; otherwise a comma-terminated list
+        let dependents = fields[3].trim_end_matches(',').replace(',', ", ");
+        let dependents = if dependents == "-" { String::new() } else { dependents };
+        records.push(vec![fields[0].to_string(), size, fields[2].to_string(), dependents]);
+    }
+    Ok(records)
+}
+
+/// Vendor, device and class names from a pci.ids/usb.ids database, keyed by lowercase hex ids
+#[derive(Default)]
+struct IdDatabase {
+    vendors: HashMap<String, String>,
+    devices: HashMap<(String, String), String>,
+    classes: HashMap<String, String>,
+}
+
+impl IdDatabase {
+    fn load(paths: &[&str]) -> IdDatabase {
+        let mut db = IdDatabase::default();
+        let Some(text) = paths.iter().find_map(|p| fs::read(p).ok()) else {
+            return db;
+        };
+        let text = String::from_utf8_lossy(&text);
+        let mut vendor = String::new();
+        let mut class: Option<String> = None;
+        for line in text.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let depth = line.chars().take_while(|c| *c == '\t').count();
+            let Some((id, name)) = line.trim_start().split_once("  ") else {
+                continue;
+            };
+            match depth {
+                // Class section ("C 03  Display controller") comes after all vendors
+                0 if id.starts_with("C ") => {
+                    let id = id[2..].to_lowercase();
+                    db.classes.insert(id.clone(), name.to_string());
+                    class = Some(id);
+                },
+                0 => {
+                    vendor = id.to_lowercase();
+                    class = None;
+                    db.vendors.insert(vendor.clone(), name.to_string());
+                },
+                1 => match &class {
+                    Some(class) => {
+                        db.classes.insert(format!("{}{}", class, id.to_lowercase()), name.to_string());
+                    },
+                    None => {
+                        db.devices.insert((vendor.clone(), id.to_lowercase()), name.to_string());
+                    },
+                },
+                _ => {},
+            }
+        }
+        db
+    }
+
+    fn vendor(&self, vendor: &str) -> String {
+        self.vendors.get(vendor).cloned().unwrap_or_else(|| format!("vendor {}", vendor))
+    }
+
+    fn device(&self, vendor: &str, device: &str) -> String {
+        self.devices.get(&(vendor.to_string(), device.to_string())).cloned().unwrap_or_else(|| format!("device {}", device))
+    }
+}
+
+fn read_attr(dir: &Path, name: &str) -> String {
+    fs::read_to_string(dir.join(name)).map(|s| s.trim().to_string()).unwrap_or_default()
+}
+
+fn sorted_entries(dir: &str) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+/// PCI devices from /sys/bus/pci, named through pci.ids when it is installed
+pub fn get_pci_devices() -> Records {
+    let db = IdDatabase::load(&["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids", "/usr/share/pci.ids"]);
+    let mut records = Records::new(&["Slot", "Class", "Vendor", "Device"]);
+    for dir in sorted_entries("/sys/bus/pci/devices") {
+        let vendor = read_attr(&dir, "vendor").trim_start_matches("0x").to_lowercase();
+        let device = read_attr(&dir, "device").trim_start_matches("0x").to_lowercase();
+        let class = read_attr(&dir, "class").trim_start_matches("0x").to_lowercase();
+        // class is "ccssii"; prefer the subclass name over the base class name
+        let class_name = class
+            .get(..4)
+            .and_then(|c| db.classes.get(c))
+            .or_else(|| class.get(..2).and_then(|c| db.classes.get(c)))
+            .cloned()
+            .unwrap_or_else(|| format!("class {}", class));
+        let slot = dir.file_name().unwrap().to_string_lossy().to_string();
+        records.push(vec![slot, class_name, db.vendor(&vendor), db.device(&vendor, &device)]);
+    }
+    records
+}
+
+/// USB devices from /sys/bus/usb, preferring the strings the device reports about itself
+pub fn get_usb_devices() -> Records {
+    let db = IdDatabase::load(&["/usr/share/hwdata/usb.ids", "/usr/share/misc/usb.ids", "/var/lib/usbutils/usb.ids"]);
+    let mut records = Records::new(&["Bus", "Device", "ID", "Manufacturer", "Product"]);
+    for dir in sorted_entries("/sys/bus/usb/devices") {
+        // Interfaces ("1-1:1.0") have no idVendor and are not devices in their own right
+        let vendor = read_attr(&dir, "idVendor").to_lowercase();
+        if vendor.is_empty() {
+            continue;
+        }
+        let product = read_attr(&dir, "idProduct").to_lowercase();
+        let manufacturer = Some(read_attr(&dir, "manufacturer")).filter(|m| !m.is_empty()).unwrap_or_else(|| db.vendor(&vendor));
+        let product_name = Some(read_attr(&dir, "product")).filter(|p| !p.is_empty()).unwrap_or_else(|| db.device(&vendor, &product));
+        records.push(vec![read_attr(&dir, "busnum"), read_attr(&dir, "devnum"), format!("{}:{}", vendor, product), manufacturer, product_name]);
+    }
+    records
+}
@@ -0,0 +1,588 @@
+/// A command as `help` describes it
+pub struct Command {
+    pub name: &'static str,
+    /// One line per form the command takes
+    pub usage: &'static [&'static str],
+    pub summary: &'static str,
+    /// Flag and what it does
+    pub flags: &'static [(&'static str, &'static str)],
+    pub examples: &'static [&'static str],
+}
+
+/// Every command, in the order `help` lists them
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "ptable",
+        usage: &["ptable [--sort pid|cpu|mem|read|write|rq|name] [--asc|--desc]"],
+        summary: "prints the process table with per-second disk read/write rates and run queue latency, %CPU green, yellow from 50 and red from 90, zombies and root's processes marked; config.toml [[colors]] rules colour the rows, theme.toml cpu_low, cpu_mid, cpu_high, zombie and root the rest",
+        flags: &[("--sort (column)", "orders the rows by that column, sort in config.toml by default"), ("--asc, --desc", "smallest or largest first")],
+        examples: &["ptable --sort cpu", "ptable | grep chrome"],
+    },
+    Command {
+        name: "desc",
+        usage: &["desc"],
+        summary: "prints the process table sorted by PID, largest first",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "pstree",
+        usage: &["pstree", "pstree kill (pid)"],
+        summary: "shows the processes under their parents; Left and Right fold and open branches, and kill asks before terminating a process with others under it, which pstree kill does without asking",
+        flags: &[],
+        examples: &["pstree", "pstree | grep ssh", "pstree kill 4242"],
+    },
+    Command {
+        name: "find",
+        usage: &["find (pid)"],
+        summary: "prints the name, CPU, memory, command line and cgroup CPU quota of a process",
+        flags: &[],
+        examples: &["find 1"],
+    },
+    Command {
+        name: "kill",
+        usage: &["kill (pid|name)", "kill :(port)", "kill"],
+        summary: "sends SIGTERM to a process, to whatever listens on a port, or to the process chosen with pick",
+        flags: &[],
+        examples: &["kill 4242", "kill firefox", "kill :8080"],
+    },
+    Command {
+        name: "pick",
+        usage: &["pick [n]"],
+        summary: "lists processes whose PID or name is on the clipboard, or selects candidate n for kill",
+        flags: &[],
+        examples: &["pick", "pick 2"],
+    },
+    Command {
+        name: "ignite",
+        usage: &["ignite [--bg] [--env KEY=VALUE]... [--cwd (dir)] (cmd) [args]"],
+        summary: "starts a process and shows its output and exit status as it runs; Esc leaves it running in the background, where its exit is still reported; arguments are split like a shell does, so quote the ones with spaces. Jobs keep running when proclynx exits, but one started without --bg prints to proclynx and is killed by SIGPIPE the next time it prints; its output cannot be piped",
+        flags: &[("--bg", "starts it without showing its output, which goes to a log file under ~/.local/state/proclynx/jobs; jobs (id) shows it later"), ("--env KEY=VALUE", "sets an environment variable for the process; repeat for more"), ("--cwd (dir)", "starts the process in dir")],
+        examples: &["ignite sleep 60", "ignite --cwd /srv/app --env RUST_LOG=debug ./server --port 8080", "ignite sh -c 'tar czf /tmp/home.tgz ~ | tee /tmp/log'"],
+    },
+    Command {
+        name: "history",
+        usage: &["history"],
+        summary: "lists the command lines entered before, numbered from the oldest; !! runs the last one again and !n the one numbered n, with any words typed after them added, also after ; or &&",
+        flags: &[],
+        examples: &["history | grep ssh", "!!", "!42 --sort cpu", "hostname; !!"],
+    },
+    Command {
+        name: "jobs",
+        usage: &["jobs [id]", "killjob (id)"],
+        summary: "lists the processes started with ignite, with their status and how long they have run, or shows what one of them printed; killjob sends SIGTERM to one still running",
+        flags: &[],
+        examples: &["jobs", "jobs 2", "killjob 2"],
+    },
+    Command {
+        name: "watchdog",
+        usage: &["watchdog (name|pid) [--restart (cmd) [args]]", "watchdog [list]", "watchdog remove (id)"],
+        summary: "reports when a process exits or re-ignites a command each time it does, lists watched processes with their restart counts, or stops watching one",
+        flags: &[("--restart (cmd) [args]", "runs the command again whenever the process exits")],
+        examples: &["watchdog nginx", "watchdog worker --restart ./worker --queue jobs"],
+    },
+    Command {
+        name: "notify-on-spawn",
+        usage: &["notify-on-spawn (pattern) [--desktop]", "notify-on-spawn [list]", "notify-on-spawn remove (id)"],
+        summary: "logs every new process whose name contains the pattern, or lists or removes spawn watches; processes living under 2s can slip through",
+        flags: &[("--desktop", "also sends a desktop notification")],
+        examples: &["notify-on-spawn ssh --desktop"],
+    },
+    Command {
+        name: "guard",
+        usage: &["guard (pattern) [--mem 2G] [--cpu 95] [--grace 10s]", "guard [list]", "guard log", "guard remove (id)"],
+        summary: "sends SIGTERM to matching processes over a limit, then SIGKILL if they outlive the grace period; lists guards, shows the audit log of every signal sent, or removes a guard",
+        flags: &[("--mem (size)", "resident memory limit"), ("--cpu (percent)", "CPU limit"), ("--grace (duration)", "time between SIGTERM and SIGKILL")],
+        examples: &["guard chrome --mem 4G", "guard ffmpeg --cpu 95 --grace 30s"],
+    },
+    Command {
+        name: "dump",
+        usage: &["dump (pid) (path)"],
+        summary: "writes status, limits, fds, memory maps, environment, cgroup and recent metrics of a process to a file",
+        flags: &[],
+        examples: &["dump 4242 /tmp/4242.txt"],
+    },
+    Command {
+        name: "copy",
+        usage: &["copy [n]"],
+        summary: "puts the previous output, or its line n, on the clipboard",
+        flags: &[],
+        examples: &["copy", "copy 3"],
+    },
+    Command {
+        name: "watch",
+        usage: &["watch (interval) (command)"],
+        summary: "re-runs a command every interval, refreshing the output in place until Esc; commands that change the system, write files or settings or connect somewhere are refused",
+        flags: &[],
+        examples: &["watch 2s ports", "watch 1m df -h | grep /home"],
+    },
+    Command {
+        name: "snapshot",
+        usage: &["snapshot save (name)", "snapshot diff (a) (b|now)", "snapshot list"],
+        summary: "keeps the process table and system metrics, then shows new/exited processes and large CPU/MEM changes between two of them",
+        flags: &[],
+        examples: &["snapshot save before", "snapshot diff before now"],
+    },
+    Command {
+        name: "sysinfo",
+        usage: &["sysinfo"],
+        summary: "prints the system name, kernel and OS version and host name",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "uname",
+        usage: &["uname"],
+        summary: "prints the kernel version",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "release",
+        usage: &["release"],
+        summary: "prints the OS version",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "hostname",
+        usage: &["hostname"],
+        summary: "prints the hostname",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "lscpu",
+        usage: &["lscpu"],
+        summary: "lists the processor information",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "memory",
+        usage: &["memory"],
+        summary: "shows RAM and swap as gauges over their total, used and free bytes; [gauges] in config.toml sets when they turn to the warning and alert colours",
+        flags: &[],
+        examples: &["memory", "watch 2s memory"],
+    },
+    Command {
+        name: "pressure",
+        usage: &["pressure"],
+        summary: "prints CPU, memory and I/O stall averages (PSI) and hugepage usage",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "paging",
+        usage: &["paging"],
+        summary: "live swap in/out and major page fault rates with history, and the processes faulting the most",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "vmstat",
+        usage: &["vmstat [count]"],
+        summary: "samples run queue, swap in/out, block I/O, interrupts and context switches per second, 5 times by default",
+        flags: &[],
+        examples: &["vmstat 10"],
+    },
+    Command {
+        name: "schedlat",
+        usage: &["schedlat (pid)"],
+        summary: "live run queue latency of a process: time spent waiting for a CPU, with history",
+        flags: &[],
+        examples: &["schedlat 4242"],
+    },
+    Command {
+        name: "graph",
+        usage: &["graph cpu", "graph net [interface]"],
+        summary: "live chart of overall CPU utilization with a sparkline per core, or of bytes received and transmitted per second on one interface or all of them, over the last 5 minutes, drawn in braille dots; Esc stops it",
+        flags: &[],
+        examples: &["graph cpu", "graph net eth0"],
+    },
+    Command {
+        name: "sensors",
+        usage: &["sensors"],
+        summary: "prints the labels of various components with their associated temperatures",
+        flags: &[],
+        examples: &["sensors | grep -i core"],
+    },
+    Command {
+        name: "thermals",
+        usage: &["thermals"],
+        summary: "live temperature of every component with its min/max and a sparkline of its history",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "hddtemp",
+        usage: &["hddtemp [-max|-crit]"],
+        summary: "prints the temperature of internal HDD/SSD/NVMe drives",
+        flags: &[("-max", "the highest temperature each drive reached"), ("-crit", "the temperature each drive considers critical")],
+        examples: &[],
+    },
+    Command {
+        name: "gputemp",
+        usage: &["gputemp [-max]"],
+        summary: "prints the temperature of the GPU",
+        flags: &[("-max", "the highest temperature the GPU reached")],
+        examples: &[],
+    },
+    Command {
+        name: "gpu",
+        usage: &["gpu"],
+        summary: "prints temperature, utilization and VRAM usage of each GPU",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "df",
+        usage: &["df [-h|-k|-m]", "df -i"],
+        summary: "prints the disk filesystem information with growth per day and days until full under a bar of used space per filesystem, or inode totals, used and free inodes",
+        flags: &[("-h", "human-readable units"), ("-k, -m", "KiB or MiB instead of bytes"), ("-i", "inodes instead of space")],
+        examples: &["df -h", "df -i"],
+    },
+    Command {
+        name: "lsblk",
+        usage: &["lsblk"],
+        summary: "prints block devices and their partitions as a tree with sizes, filesystems and mount points",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "network",
+        usage: &["network", "network live"],
+        summary: "prints each interface's link state, MTU, MAC, IPv4/IPv6 addresses and RX/TX bytes per second, or those rates live, busiest first",
+        flags: &[],
+        examples: &["network live"],
+    },
+    Command {
+        name: "nethogs",
+        usage: &["nethogs"],
+        summary: "live per-process TCP send/receive rates",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "connections",
+        usage: &["connections [filter]", "ss [filter]"],
+        summary: "lists TCP/UDP sockets with their state, addresses and owning process, optionally only those matching an address or process",
+        flags: &[],
+        examples: &["ss firefox", "connections 10.0.0.1"],
+    },
+    Command {
+        name: "ports",
+        usage: &["ports"],
+        summary: "lists listening TCP/UDP ports with their address and owning process",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "portwatch",
+        usage: &["portwatch (port)", "portwatch [list]", "portwatch remove (port)"],
+        summary: "alerts when the port starts or stops listening on TCP or UDP",
+        flags: &[],
+        examples: &["portwatch 5432"],
+    },
+    Command {
+        name: "route",
+        usage: &["route"],
+        summary: "prints the default gateways and the IPv4/IPv6 routing table",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "ping",
+        usage: &["ping (host)"],
+        summary: "live round-trip latency sparkline with min/avg/max and loss; Esc stops it",
+        flags: &[],
+        examples: &["ping 1.1.1.1"],
+    },
+    Command {
+        name: "dig",
+        usage: &["dig (name)"],
+        summary: "resolves A/AAAA records and CNAMEs through the system resolver, with query times",
+        flags: &[],
+        examples: &["dig example.com"],
+    },
+    Command {
+        name: "arp",
+        usage: &["arp"],
+        summary: "lists IP-to-MAC neighbour entries with their state (REACHABLE, STALE, ...)",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "wifi",
+        usage: &["wifi"],
+        summary: "prints SSID, signal strength, link quality, bitrate and frequency of wireless interfaces",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "who",
+        usage: &["who"],
+        summary: "lists logged-in users with their TTY, login time and originating host",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "journal",
+        usage: &["journal [unit]"],
+        summary: "follows the systemd journal, optionally for a single unit",
+        flags: &[],
+        examples: &["journal sshd"],
+    },
+    Command {
+        name: "services",
+        usage: &["services"],
+        summary: "lists systemd services with their state, failed ones first",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "service",
+        usage: &["service start|stop|restart (unit)"],
+        summary: "controls a systemd service through systemctl",
+        flags: &[],
+        examples: &["service restart nginx"],
+    },
+    Command {
+        name: "lsmod",
+        usage: &["lsmod [filter]"],
+        summary: "lists loaded kernel modules with size, use count and dependents",
+        flags: &[],
+        examples: &["lsmod nvidia"],
+    },
+    Command {
+        name: "lspci",
+        usage: &["lspci"],
+        summary: "lists PCI devices with their class, vendor and device names",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "lsusb",
+        usage: &["lsusb"],
+        summary: "lists USB devices with their bus, ID, manufacturer and product",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "docker",
+        usage: &["docker", "docker stop|kill (container)"],
+        summary: "lists running Docker or Podman containers with CPU, memory and network I/O, or stops or kills one, through the API socket",
+        flags: &[],
+        examples: &["docker stop web"],
+    },
+    Command {
+        name: "pods",
+        usage: &["pods [namespace]"],
+        summary: "lists Kubernetes pods with phase, restarts and CPU and memory from metrics-server; needs the kubernetes feature",
+        flags: &[],
+        examples: &["pods kube-system"],
+    },
+    Command {
+        name: "trace",
+        usage: &["trace (pid)"],
+        summary: "attaches strace and counts the process's syscalls live; Esc detaches and shows a histogram",
+        flags: &[],
+        examples: &["trace 4242"],
+    },
+    Command {
+        name: "perfstat",
+        usage: &["perfstat (pid) [seconds]"],
+        summary: "counts IPC, cache misses and branch mispredictions of a process with perf stat, 5 seconds by default",
+        flags: &[],
+        examples: &["perfstat 4242 10"],
+    },
+    Command {
+        name: "execsnoop",
+        usage: &["execsnoop", "opensnoop"],
+        summary: "streams new process executions or file opens system-wide from eBPF; needs the ebpf feature and root",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "alert",
+        usage: &["alert (metric) >|< (threshold) [pattern] [for 30s|5m]", "alert list|log|clear", "alert remove (name)"],
+        summary: "adds an alert rule evaluated on every sample; lists rules with what is firing, shows or clears the alert log, or removes a rule",
+        flags: &[],
+        examples: &["alert cpu > 90 for 30s", "alert mem > 80 chrome"],
+    },
+    Command {
+        name: "alerts",
+        usage: &["alerts"],
+        summary: "every alert fired this session, newest first, with the value at trigger time and when it resolved",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "rules",
+        usage: &["rules load|save [file]", "rules list|check", "rules test (recording)"],
+        summary: "loads or exports alert rules as YAML (rules.yaml in the config directory by default), lists them or dry-runs them against the current system, or replays a recorded session and reports when each rule would have fired",
+        flags: &[],
+        examples: &["rules save", "rules test session.jsonl"],
+    },
+    Command {
+        name: "record",
+        usage: &["record start (file.csv|file.jsonl)", "record stop"],
+        summary: "writes every sample to a CSV file, or full snapshots to JSON lines that rules test can replay",
+        flags: &[],
+        examples: &["record start session.jsonl"],
+    },
+    Command {
+        name: "export",
+        usage: &["export all (dir|file.tar|file.tar.gz)"],
+        summary: "writes system info, df, sensors, memory, network, routes, connections, the process table and the latest sample to one directory or archive",
+        flags: &[],
+        examples: &["export all /tmp/box.tar.gz"],
+    },
+    Command {
+        name: "report",
+        usage: &["report (file.html|file.md)"],
+        summary: "writes system info, CPU, memory, disks, sensors, network and the top processes as one HTML or Markdown report",
+        flags: &[],
+        examples: &["report box.html"],
+    },
+    Command {
+        name: "serve",
+        usage: &["serve [addr]", "serve stop"],
+        summary: "serves JSON at /processes, /system, /sensors and POST /kill {\"pid\": n, \"signal\": \"SIGTERM\"} (default 127.0.0.1:9100); /kill needs token under [serve] in config.toml, sent as Authorization: Bearer <token> with Content-Type: application/json, and refuses requests carrying an Origin",
+        flags: &[],
+        examples: &["serve 0.0.0.0:9100"],
+    },
+    Command {
+        name: "mqtt",
+        usage: &["mqtt [start [host[:port]] | stop]"],
+        summary: "publishes cpu, mem, swap, load, temp, disk and network metrics to an MQTT broker as <prefix>/<host>/<metric>, with Home Assistant discovery; [mqtt] in config.toml starts it at launch",
+        flags: &[],
+        examples: &["mqtt start 192.168.1.10"],
+    },
+    Command {
+        name: "connect",
+        usage: &["connect (user@host) [path]", "disconnect [user@host]", "hosts"],
+        summary: "opens a tab running every command but help, clear, edit, macro and watch on another host through ssh and proclynx --agent there, closes it, or lists the connected hosts; live views show their first screen",
+        flags: &[],
+        examples: &["connect admin@nas"],
+    },
+    Command {
+        name: "macro",
+        usage: &["macro record (name)", "macro stop", "macro play (name)", "macro list"],
+        summary: "records and replays command and key sequences",
+        flags: &[],
+        examples: &["macro record morning"],
+    },
+    Command {
+        name: "alias",
+        usage: &["alias [<name>=<command line>]", "unalias <name>"],
+        summary: "lists the aliases, or makes a name stand for a command line and saves it under [aliases] in config.toml; arguments typed after an alias are appended to its command line",
+        flags: &[],
+        examples: &["alias pt=ptable --sort cpu --desc", "alias hot=sensors | grep -i core", "unalias pt"],
+    },
+    Command {
+        name: "config",
+        usage: &["config [reload]"],
+        summary: "shows the settings from config.toml, or re-reads it (refresh_ms, sort, theme, history_size, startup, [aliases], [keys], [[colors]], [notify], [alarms], [gauges], [mqtt])",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "edit",
+        usage: &["edit config|rules|theme"],
+        summary: "opens the file in $EDITOR, then validates and applies it",
+        flags: &[],
+        examples: &["edit config"],
+    },
+    Command {
+        name: "theme",
+        usage: &["theme [dark|light|solarized|monochrome]"],
+        summary: "lists the colour themes or switches to one; theme in config.toml sets it at launch and theme.toml overrides single colours (text = \"white\")",
+        flags: &[],
+        examples: &["theme solarized"],
+    },
+    Command {
+        name: "keys",
+        usage: &["keys"],
+        summary: "lists the key bound to each action; rebind them in the [keys] section of config.toml, e.g. down = [\"j\", \"Down\"]",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "clear",
+        usage: &["clear [-f]"],
+        summary: "empties the output pane, keeping the last 10 cleared outputs for unclear; asks first when a live view is still updating",
+        flags: &[("-f", "stops a live view and clears it without asking")],
+        examples: &["clear -f"],
+    },
+    Command {
+        name: "unclear",
+        usage: &["unclear"],
+        summary: "puts back the output the last clear took away, the one before that when run again",
+        flags: &[],
+        examples: &[],
+    },
+    Command {
+        name: "help",
+        usage: &["help [command]"],
+        summary: "lists every command with the keys, or shows the usage, flags and examples of one",
+        flags: &[],
+        examples: &["help df"],
+    },
+];
+
+/// What works on the output of any command
+const PIPE: &str = "(command) | grep [-v] [-i] (text) | head [n] | tail [n] --> filters the output lines or process table rows, e.g. ptable | grep chrome";
+
+/// Running several commands from one line
+const CHAIN: &str = "(command); (command) --> runs both, one after the other; (command) && (command) --> runs the second only if the first did not fail, after an ignite once it exited 0, e.g. ignite ./backup.sh && snapshot save after-backup";
+
+/// What the mouse does
+const MOUSE: &str = "click --> selects a process table row, or sorts by a column header (again to reverse); wheel --> moves through the table or scrolls the output, Esc goes back";
+
+/// The command `name` is listed under, including the other names it answers to
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name || c.usage.iter().any(|u| u.split_whitespace().next() == Some(name)))
+}
+
+/// `help`: one line per command, then the keys
+pub fn lines(keys: &[String]) -> Vec<String> {
+    let mut vec = vec!["COMMANDS".to_string()];
+    vec.extend(COMMANDS.iter().map(|c| format!("{} --> {}", c.usage.join(" | "), c.summary)));
+    vec.push(PIPE.to_string());
+    vec.push(CHAIN.to_string());
+    vec.push(String::new());
+    vec.push("KEYS (outside editing mode; help (command) for details)".to_string());
+    vec.extend(keys.iter().cloned());
+    vec.push(MOUSE.to_string());
+    vec
+}
+
+/// `help <command>`: its usage, flags and examples
+pub fn command(name: &str) -> Result<Vec<String>, String> {
+    let command = find(name).ok_or_else(|| format!("help: no command '{}'; help lists them all", name))?;
+    let mut vec = vec!["USAGE".to_string()];
+    vec.extend(command.usage.iter().map(|u| format!("  {}", u)));
+    vec.push(String::new());
+    vec.push(command.summary.to_string());
+    if !command.flags.is_empty() {
+        vec.push(String::new());
+        vec.push("FLAGS".to_string());
+        let width = command.flags.iter().map(|(flag, _)| flag.len()).max().unwrap_or(0);
+        vec.extend(command.flags.iter().map(|(flag, what)| format!("  {:<width$}  {}", flag, what, width = width)));
+    }
+    if !command.examples.is_empty() {
+        vec.push(String::new());
+        vec.push("EXAMPLES".to_string());
+        vec.extend(command.examples.iter().map(|e| format!("  {}", e)));
+    }
+    Ok(vec)
+}
+
+/// Sections of the F1 overlay: the command names, then the keys
+pub fn overlay(keys: &[String]) -> Vec<(String, Vec<String>)> {
+    vec![
+        ("Commands (help (command) for details)".to_string(), COMMANDS.iter().map(|c| c.name.to_string()).collect()),
+        ("Keys".to_string(), keys.to_vec()),
+    ]
+}
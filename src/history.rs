@@ -0,0 +1,70 @@
+use crate::rules::Snapshot;
+use std::collections::VecDeque;
+
+/// Samples kept in memory: one hour at the default two second tick
+pub const CAPACITY: usize = 1800;
+
+/// Growth estimates need some spread in time before they mean anything
+const MIN_SPAN_SECS: f64 = 60.0;
+
+/// Rolling store of the sampler's snapshots, without per-process data to keep it small
+#[derive(Default)]
+pub struct History {
+    pub samples: VecDeque<Snapshot>,
+}
+
+impl History {
+    pub fn push(&mut self, snap: &Snapshot) {
+        let mut snap = snap.clone();
+        snap.processes.clear();
+        if self.samples.len() == CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(snap);
+    }
+
+    /// Least-squares growth of used space on `mount_point` in bytes per second
+    pub fn fs_growth(&self, mount_point: &str) -> Option<f64> {
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .filter_map(|s| {
+                let fs = s.filesystems.iter().find(|f| f.mount_point == mount_point)?;
+                Some((s.time as f64, fs.total.saturating_sub(fs.available) as f64))
+            })
+            .collect();
+        let (first, last) = (points.first()?, points.last()?);
+        if last.0 - first.0 < MIN_SPAN_SECS {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_u = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let covariance: f64 = points.iter().map(|p| (p.0 - mean_t) * (p.1 - mean_u)).sum();
+        let variance: f64 = points.iter().map(|p| (p.0 - mean_t).powi(2)).sum();
+        if variance == 0.0 {
+            return None;
+        }
+        Some(covariance / variance)
+    }
+
+    /// Days until `mount_point` runs out of space, if it is growing
+    pub fn days_until_full(&self, mount_point: &str) -> Option<f64> {
+        let growth = self.fs_growth(mount_point)?;
+        let available = self.samples.back()?.filesystems.iter().find(|f| f.mount_point == mount_point)?.available;
+        if growth <= 0.0 {
+            return None;
+        }
+        Some(available as f64 / growth / 86400.0)
+    }
+
+    /// The soonest projected exhaustion across every filesystem in the latest sample
+    pub fn min_days_until_full(&self) -> Option<f64> {
+        self.samples
+            .back()?
+            .filesystems
+            .iter()
+            .filter_map(|f| self.days_until_full(&f.mount_point))
+            .reduce(f64::min)
+    }
+}
@@ -0,0 +1,436 @@
+use crate::{paths, records::Records};
+use nix::{
+    sys::{
+        signal::{kill, Signal},
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Lines of each job's output kept, the newest ones
+const OUTPUT_LINES: usize = 500;
+
+/// Bytes read back from the end of a background job's log, enough for `OUTPUT_LINES` lines of most output
+const LOG_TAIL: u64 = 64 * 1024;
+
+const USAGE: &str = "usage: ignite [--bg] [--env KEY=VALUE]... [--cwd dir] (cmd) [args]";
+
+/// How a job ended
+#[derive(Clone, Copy)]
+pub enum JobStatus {
+    Exited(i32),
+    Signaled(nix::sys::signal::Signal),
+}
+
+/// What `ignite` starts: `[--bg] [--env KEY=VAL]... [--cwd dir] program [args]`
+pub struct Launch {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Set on top of the environment proclynx runs in
+    pub env: Vec<(String, String)>,
+    pub cwd: Option<String>,
+    /// Left to run without showing its output, which goes to a log file that `jobs <id>` shows.
+    /// Only a job in the foreground writes to a pipe, which breaks once proclynx exits.
+    pub background: bool,
+}
+
+impl Launch {
+    pub fn new(program: &str, args: &[String]) -> Launch {
+        Launch { program: program.to_string(), args: args.to_vec(), env: vec![], cwd: None, background: true }
+    }
+
+    /// Parses the words after `ignite`; options end at the program, so its own arguments may look like them
+    pub fn parse(text: &str) -> Result<Launch, String> {
+        let words = words(text).map_err(|e| format!("ignite: {}", e))?;
+        let mut env: Vec<(String, String)> = vec![];
+        let mut cwd: Option<String> = None;
+        let mut background = false;
+        let mut rest = words.iter();
+        let program = loop {
+            match rest.next().map(|w| w.as_str()) {
+                Some("--bg") => background = true,
+                Some("--env") => match rest.next().and_then(|v| v.split_once('=')).filter(|(key, _)| !key.is_empty()) {
+                    Some((key, value)) => env.push((key.to_string(), value.to_string())),
+                    None => return Err("ignite: --env takes KEY=VALUE".to_string()),
+                },
+                Some("--cwd") => match rest.next() {
+                    Some(dir) if Path::new(dir).is_dir() => cwd = Some(dir.clone()),
+                    Some(dir) => return Err(format!("ignite: --cwd: {} is not a directory", dir)),
+                    None => return Err("ignite: --cwd takes a directory".to_string()),
+                },
+                Some("--") => match rest.next() {
+                    Some(program) => break program.clone(),
+                    None => return Err(USAGE.to_string()),
+                },
+                Some(program) => break program.to_string(),
+                None => return Err(USAGE.to_string()),
+            }
+        };
+        Ok(Launch { program, args: rest.cloned().collect(), env, cwd, background })
+    }
+
+    /// The command line as it could be typed again, quoting the words that need it
+    pub fn describe(&self) -> String {
+        let quote = |word: &String| {
+            if word.is_empty() || word.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"') {
+                format!("'{}'", word.replace('\'', "'\\''"))
+            } else {
+                word.clone()
+            }
+        };
+        std::iter::once(&self.program).chain(&self.args).map(quote).collect::<Vec<String>>().join(" ")
+    }
+}
+
+/// Splits `text` into words at whitespace like a shell: 'single' quotes keep everything, "double"
+/// quotes and a backslash outside quotes keep spaces and the character after the backslash
+pub fn words(text: &str) -> Result<Vec<String>, String> {
+    let mut words: Vec<String> = vec![];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => match chars.next() {
+                // Only what would end or escape the quote needs the backslash; keep it elsewhere
+                Some(next @ ('"' | '\\')) => word.get_or_insert_with(String::new).push(next),
+                Some(next) => word.get_or_insert_with(String::new).extend(['\\', next]),
+                None => return Err("unterminated \" quote".to_string()),
+            },
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            },
+            (None, '\\') => word.get_or_insert_with(String::new).extend(chars.next()),
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("unterminated {} quote", q));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// A process started by `ignite`
+pub struct Job {
+    pub id: usize,
+    pub pid: i32,
+    pub command: String,
+    pub started: Instant,
+    /// Set once the child has been reaped, with how long it ran
+    pub finished: Option<(JobStatus, Duration)>,
+    /// What it printed to stdout and stderr, in the order it arrived
+    output: Vec<String>,
+    /// Lines read from its pipes, until both are closed
+    lines: Option<Receiver<String>>,
+    /// Where a background job's stdout and stderr go instead
+    pub log: Option<PathBuf>,
+}
+
+impl Job {
+    /// Moves the lines printed since the last call into `output`
+    pub fn pump(&mut self) {
+        let Some(lines) = &self.lines else {
+            return;
+        };
+        loop {
+            match lines.try_recv() {
+                Ok(line) => self.output.push(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.lines = None;
+                    break;
+                },
+            }
+        }
+        if self.output.len() > OUTPUT_LINES {
+            self.output.drain(..self.output.len() - OUTPUT_LINES);
+        }
+    }
+
+    /// The newest lines it printed, read back from its log for a background job
+    pub fn printed(&self) -> Vec<String> {
+        match &self.log {
+            Some(log) => tail(log).unwrap_or_else(|e| vec![format!("{}: {}", log.display(), e)]),
+            None => self.output.clone(),
+        }
+    }
+
+    /// Whether it exited and everything it printed has been read
+    pub fn done(&self) -> bool {
+        self.finished.is_some() && self.lines.is_none()
+    }
+
+    /// How long it has been running, or ran
+    pub fn elapsed(&self) -> Duration {
+        self.finished.map(|(_, ran)| ran).unwrap_or_else(|| self.started.elapsed())
+    }
+
+    pub fn status(&self) -> String {
+        match self.finished {
+            None => "running".to_string(),
+            Some((JobStatus::Exited(code), _)) => format!("exited {}", code),
+            Some((JobStatus::Signaled(signal), _)) => format!("killed by {}", signal),
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        matches!(self.finished, Some((JobStatus::Exited(0), _)))
+    }
+
+    pub fn describe(&self) -> String {
+        match self.finished {
+            None => format!("[{}] {} running  {}", self.id, self.pid, self.command),
+            Some((JobStatus::Exited(code), ran)) => format!("[{}] {} exited {} after {:.1}s  {}", self.id, self.pid, code, ran.as_secs_f64(), self.command),
+            Some((JobStatus::Signaled(signal), ran)) => format!("[{}] {} killed by {} after {:.1}s  {}", self.id, self.pid, signal, ran.as_secs_f64(), self.command),
+        }
+    }
+}
+
+/// "42.0s", "3m 05s" or "2h 07m"
+fn elapsed(time: Duration) -> String {
+    let secs = time.as_secs();
+    match secs {
+        0..=59 => format!("{:.1}s", time.as_secs_f64()),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// The last `OUTPUT_LINES` lines of the file at `path`, without reading more than its last `LOG_TAIL` bytes
+fn tail(path: &Path) -> io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let start = file.metadata()?.len().saturating_sub(LOG_TAIL);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes: Vec<u8> = vec![];
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    // Past the start the first line is likely cut off
+    let mut lines: Vec<String> = text.lines().skip(usize::from(start > 0)).map(|line| line.to_string()).collect();
+    lines.drain(..lines.len().saturating_sub(OUTPUT_LINES));
+    Ok(lines)
+}
+
+/// Where background job `id` of this proclynx writes, in the state directory
+fn log_path(id: usize) -> PathBuf {
+    paths::state_dir().join("jobs").join(format!("{}-{}.log", std::process::id(), id))
+}
+
+/// Sends the lines read from `pipe` on a thread of their own, until it closes
+fn forward(pipe: impl Read + Send + 'static, tx: Sender<String>) {
+    thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Children spawned by proclynx, reaped when SIGCHLD says one of them changed state
+pub struct Jobs {
+    pub list: Vec<Job>,
+    sigchld: Arc<AtomicBool>,
+}
+
+impl Default for Jobs {
+    fn default() -> Jobs {
+        let sigchld = Arc::new(AtomicBool::new(false));
+        // Without the handler we still reap, just on every tick instead of on demand
+        if signal_hook::flag::register(signal_hook::consts::SIGCHLD, Arc::clone(&sigchld)).is_err() {
+            sigchld.store(true, Ordering::Relaxed);
+        }
+        Jobs { list: Vec::new(), sigchld }
+    }
+}
+
+impl Jobs {
+    /// Starts `launch` detached from the terminal the TUI is drawing on. A foreground job's output
+    /// is collected for `ignite` to show; a background one writes to a log file, so it keeps running
+    /// undisturbed once proclynx exits, or to nowhere if the log cannot be created.
+    pub fn spawn(&mut self, launch: &Launch) -> io::Result<&Job> {
+        let id = self.list.len() + 1;
+        let mut command = Command::new(&launch.program);
+        command.args(&launch.args).envs(launch.env.iter().map(|(k, v)| (k, v))).stdin(Stdio::null());
+        if let Some(dir) = &launch.cwd {
+            command.current_dir(dir);
+        }
+        let log = launch.background.then(|| log_path(id));
+        match &log {
+            Some(path) => {
+                let file = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| File::create(path));
+                let (stdout, stderr) = match file.and_then(|file| Ok((file.try_clone()?, file))) {
+                    Ok((stdout, stderr)) => (Stdio::from(stdout), Stdio::from(stderr)),
+                    Err(_) => (Stdio::null(), Stdio::null()),
+                };
+                command.stdout(stdout).stderr(stderr);
+            },
+            None => {
+                command.stdout(Stdio::piped()).stderr(Stdio::piped());
+            },
+        }
+        let mut child = command.spawn()?;
+        let lines = match (child.stdout.take(), child.stderr.take()) {
+            (Some(stdout), Some(stderr)) => {
+                let (tx, rx) = mpsc::channel();
+                forward(stdout, tx.clone());
+                forward(stderr, tx);
+                Some(rx)
+            },
+            _ => None,
+        };
+        self.list.push(Job {
+            id,
+            pid: child.id() as i32,
+            command: launch.describe(),
+            started: Instant::now(),
+            finished: None,
+            output: vec![],
+            lines,
+            log: log.filter(|path| path.exists()),
+        });
+        Ok(self.list.last().unwrap())
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.list.iter().find(|job| job.id == id)
+    }
+
+    /// Sends SIGTERM to job `id` if it is still running
+    pub fn kill(&self, id: usize) -> Result<&Job, String> {
+        let job = self.get(id).ok_or_else(|| format!("no job [{}]", id))?;
+        if job.finished.is_some() {
+            return Err(format!("job [{}] already {}", id, job.status()));
+        }
+        kill(Pid::from_raw(job.pid), Signal::SIGTERM).map_err(|e| format!("job [{}] ({}): {}", id, job.pid, e))?;
+        Ok(job)
+    }
+
+    /// One row per job, oldest first
+    pub fn table(&self) -> Records {
+        let mut records = Records::new(&["ID", "PID", "STATUS", "ELAPSED", "COMMAND"]);
+        for job in &self.list {
+            records.push(vec![job.id.to_string(), job.pid.to_string(), job.status(), elapsed(job.elapsed()), job.command.clone()]);
+        }
+        records
+    }
+
+    /// Takes in what every job printed since the last call, keeping only the newest lines of each
+    pub fn pump(&mut self) {
+        for job in &mut self.list {
+            job.pump();
+        }
+    }
+
+    /// Collects the exit status of every job that finished since the last call. Only our own
+    /// jobs are waited for, so children owned by `Command::output` callers are left alone.
+    pub fn reap(&mut self) -> Vec<&Job> {
+        if !self.sigchld.swap(false, Ordering::Relaxed) {
+            return vec![];
+        }
+        let mut finished: Vec<usize> = vec![];
+        for (i, job) in self.list.iter_mut().enumerate().filter(|(_, j)| j.finished.is_none()) {
+            let status = match waitpid(Pid::from_raw(job.pid), Some(WaitPidFlag::WNOHANG)) {
+                Ok(WaitStatus::Exited(_, code)) => JobStatus::Exited(code),
+                Ok(WaitStatus::Signaled(_, signal, _)) => JobStatus::Signaled(signal),
+                // Someone else reaped it; the exit status is lost
+                Err(nix::errno::Errno::ECHILD) => JobStatus::Exited(-1),
+                _ => continue,
+            };
+            job.finished = Some((status, job.started.elapsed()));
+            finished.push(i);
+        }
+        finished.into_iter().map(|i| &self.list[i]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn words_split_at_whitespace_outside_quotes() {
+        assert_eq!(words("  tar  czf 'my home.tgz' \"a b\"  "), Ok(strings(&["tar", "czf", "my home.tgz", "a b"])));
+        assert_eq!(words("a'b c'd"), Ok(strings(&["ab cd"])));
+        assert_eq!(words("'' x"), Ok(strings(&["", "x"])));
+    }
+
+    #[test]
+    fn words_take_escaped_characters() {
+        assert_eq!(words(r"a\ b c\'d"), Ok(strings(&["a b", "c'd"])));
+        assert_eq!(words(r#""say \"hi\" \\ \n""#), Ok(strings(&[r#"say "hi" \ \n"#])));
+        assert_eq!(words(r"'\'"), Ok(strings(&["\\"])));
+    }
+
+    #[test]
+    fn words_refuse_an_unterminated_quote() {
+        assert_eq!(words("echo 'abc"), Err("unterminated ' quote".to_string()));
+        assert_eq!(words("echo \"abc"), Err("unterminated \" quote".to_string()));
+        assert_eq!(words("echo \"abc\\"), Err("unterminated \" quote".to_string()));
+    }
+
+    #[test]
+    fn parse_reads_options_before_the_program() {
+        let launch = Launch::parse("--bg --env A=1 --env B=x=y --cwd / make -j4").unwrap();
+        assert_eq!(launch.program, "make");
+        assert_eq!(launch.args, strings(&["-j4"]));
+        assert_eq!(launch.env, vec![("A".to_string(), "1".to_string()), ("B".to_string(), "x=y".to_string())]);
+        assert_eq!(launch.cwd.as_deref(), Some("/"));
+        assert!(launch.background);
+    }
+
+    #[test]
+    fn parse_leaves_options_after_the_program_to_it() {
+        let launch = Launch::parse("ls --bg --env A=1").unwrap();
+        assert_eq!(launch.program, "ls");
+        assert_eq!(launch.args, strings(&["--bg", "--env", "A=1"]));
+        assert!(launch.env.is_empty());
+        assert!(!launch.background);
+        assert_eq!(Launch::parse("-- --bg").unwrap().program, "--bg");
+    }
+
+    #[test]
+    fn parse_refuses_env_without_a_key_and_value() {
+        for text in ["--env FOO ls", "--env =1 ls", "--env"] {
+            assert_eq!(Launch::parse(text).err(), Some("ignite: --env takes KEY=VALUE".to_string()), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_needs_a_program() {
+        assert_eq!(Launch::parse("").err(), Some(USAGE.to_string()));
+        assert_eq!(Launch::parse("--bg --").err(), Some(USAGE.to_string()));
+        assert!(Launch::parse("--cwd /nonexistent/dir ls").is_err());
+    }
+
+    #[test]
+    fn elapsed_switches_units_at_a_minute_and_an_hour() {
+        assert_eq!(elapsed(Duration::from_millis(59_940)), "59.9s");
+        assert_eq!(elapsed(Duration::from_secs(59)), "59.0s");
+        assert_eq!(elapsed(Duration::from_secs(60)), "1m 00s");
+        assert_eq!(elapsed(Duration::from_secs(3599)), "59m 59s");
+        assert_eq!(elapsed(Duration::from_secs(3600)), "1h 00m");
+        assert_eq!(elapsed(Duration::from_secs(90_061)), "25h 01m");
+    }
+}
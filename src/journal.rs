@@ -0,0 +1,57 @@
+use std::{
+    io::{self, BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+/// Lines of backlog shown before following, same as `journalctl -n` default
+const BACKLOG: &str = "50";
+
+/// A `journalctl --follow` child whose output is collected on a reader thread
+pub struct Journal {
+    child: Child,
+    lines: Receiver<String>,
+}
+
+impl Journal {
+    pub fn follow(unit: Option<&str>) -> io::Result<Journal> {
+        let mut command = Command::new("journalctl");
+        command.args(["--follow", "--no-pager", "--output=short", "--lines", BACKLOG]);
+        if let Some(unit) = unit {
+            command.args(["--unit", unit]);
+        }
+        let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).stdin(Stdio::null()).spawn()?;
+        let (tx, rx) = mpsc::channel();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        let err_tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if err_tx.send(format!("journalctl: {}", line)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Journal { child, lines: rx })
+    }
+
+    /// Entries received since the last call
+    pub fn drain(&self) -> Vec<String> {
+        self.lines.try_iter().collect()
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
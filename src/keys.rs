@@ -0,0 +1,324 @@
+use crate::{ptable::Filter, views::View};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// What a key does outside of typing a command
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Edit,
+    Quit,
+    /// Ends a trace and shows its summary
+    Stop,
+    Alerts,
+    /// Shows the commands and keys over everything else
+    Help,
+    /// Gives the output pane the whole terminal, or puts the layout back
+    Zoom,
+    NextTab,
+    PrevTab,
+    Copy,
+    Goto,
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    SortCpu,
+    SortMem,
+    SortPid,
+    Reverse,
+    /// Sends SIGTERM to the selected process
+    Kill,
+    /// Opens a popup about the selected process
+    Details,
+    /// Folds or opens the selected branch of the process tree
+    Collapse,
+    Expand,
+    /// Starts typing a search through the output
+    Search,
+    /// Starts typing text the process table rows must contain
+    Filter,
+    /// Switches one of the process table's predefined subsets on or off
+    QuickFilter(Filter),
+    NextMatch,
+    PrevMatch,
+    /// Runs the typed command
+    Submit,
+    /// Stops a live view, then leaves editing
+    Cancel,
+    /// Completes the word being typed
+    Complete,
+    /// Brings back earlier command lines into the input box
+    HistoryPrev,
+    HistoryNext,
+    /// Searches the command lines entered before, like Ctrl-R in a shell
+    HistorySearch,
+    /// Shows the command output or one of the dashboards
+    View(View),
+}
+
+/// Name in the `[keys]` section of config.toml, action, default keys
+const ACTIONS: [(&str, Action, &[&str]); 43] = [
+    ("edit", Action::Edit, &["e", "E"]),
+    ("quit", Action::Quit, &["q", "Q"]),
+    ("stop", Action::Stop, &["Esc"]),
+    ("alerts", Action::Alerts, &["a"]),
+    ("help", Action::Help, &["F1", "?"]),
+    ("zoom", Action::Zoom, &["f"]),
+    ("next_tab", Action::NextTab, &["Tab"]),
+    ("prev_tab", Action::PrevTab, &["BackTab"]),
+    ("copy", Action::Copy, &["y"]),
+    ("goto", Action::Goto, &[":"]),
+    // Ahead of down and up, which the same keys trigger where there is no history to go through
+    ("history_prev", Action::HistoryPrev, &["Up"]),
+    ("history_next", Action::HistoryNext, &["Down"]),
+    ("history_search", Action::HistorySearch, &["Ctrl-r"]),
+    ("down", Action::Down, &["Down"]),
+    ("up", Action::Up, &["Up"]),
+    ("page_down", Action::PageDown, &["PageDown"]),
+    ("page_up", Action::PageUp, &["PageUp"]),
+    ("sort_cpu", Action::SortCpu, &["P"]),
+    ("sort_mem", Action::SortMem, &["M"]),
+    ("sort_pid", Action::SortPid, &["N"]),
+    ("reverse", Action::Reverse, &["I"]),
+    ("kill", Action::Kill, &["F9"]),
+    ("details", Action::Details, &["Enter"]),
+    ("collapse", Action::Collapse, &["Left"]),
+    ("expand", Action::Expand, &["Right"]),
+    ("search", Action::Search, &["/"]),
+    ("next_match", Action::NextMatch, &["n"]),
+    ("prev_match", Action::PrevMatch, &["N"]),
+    ("filter", Action::Filter, &["F4"]),
+    ("filter_mine", Action::QuickFilter(Filter::Mine), &["u"]),
+    ("filter_root", Action::QuickFilter(Filter::Root), &["r"]),
+    ("filter_containers", Action::QuickFilter(Filter::Container), &["c"]),
+    ("filter_zombies", Action::QuickFilter(Filter::Zombie), &["z"]),
+    ("filter_gpu", Action::QuickFilter(Filter::Gpu), &["g"]),
+    ("submit", Action::Submit, &["Enter"]),
+    ("cancel", Action::Cancel, &["Esc"]),
+    ("complete", Action::Complete, &["Tab"]),
+    ("view_output", Action::View(View::Output), &["1"]),
+    ("view_processes", Action::View(View::Processes), &["2"]),
+    ("view_system", Action::View(View::System), &["3"]),
+    ("view_network", Action::View(View::Network), &["4"]),
+    ("view_disks", Action::View(View::Disks), &["5"]),
+    ("view_sensors", Action::View(View::Sensors), &["6"]),
+];
+
+/// Actions available while typing a command, where only keys that type nothing can trigger them
+const EDITING: [Action; 10] = [
+    Action::Down,
+    Action::Up,
+    Action::PageDown,
+    Action::PageUp,
+    Action::Submit,
+    Action::Cancel,
+    Action::Complete,
+    Action::HistoryPrev,
+    Action::HistoryNext,
+    Action::HistorySearch,
+];
+
+/// Actions that only make sense while typing
+const TYPING_ONLY: [Action; 6] = [Action::Submit, Action::Cancel, Action::Complete, Action::HistoryPrev, Action::HistoryNext, Action::HistorySearch];
+
+const NAMED_KEYS: [(&str, KeyCode); 15] = [
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Backspace", KeyCode::Backspace),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Delete", KeyCode::Delete),
+    ("Space", KeyCode::Char(' ')),
+];
+
+/// A key with its Ctrl/Alt modifiers
+pub(crate) type Key = (KeyCode, KeyModifiers);
+
+/// One key name or several
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn names(&self) -> Vec<&str> {
+        match self {
+            Keys::One(name) => vec![name.as_str()],
+            Keys::Many(names) => names.iter().map(|n| n.as_str()).collect(),
+        }
+    }
+}
+
+/// Shift is part of the character itself, so `J` is bound as "J" rather than "Shift-j"
+pub(crate) fn normalize(key: KeyEvent) -> Key {
+    let modifiers = match key.code {
+        KeyCode::Char(_) | KeyCode::BackTab => key.modifiers - KeyModifiers::SHIFT,
+        _ => key.modifiers,
+    };
+    (key.code, modifiers)
+}
+
+/// Parses "j", "Down", "F9", "Ctrl-d" or "Alt-Enter"
+pub(crate) fn parse_key(name: &str) -> Result<Key, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    loop {
+        if let Some(after) = rest.strip_prefix("Ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("Alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = after;
+        } else {
+            break;
+        }
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(if modifiers.contains(KeyModifiers::CONTROL) { c.to_ascii_lowercase() } else { c }),
+        _ => match NAMED_KEYS.iter().find(|(n, _)| *n == rest) {
+            Some((_, code)) => *code,
+            None => match rest.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()).filter(|n| (1..=12).contains(n)) {
+                Some(n) => KeyCode::F(n),
+                None => return Err(format!("unknown key '{}'", name)),
+            },
+        },
+    };
+    Ok((code, modifiers))
+}
+
+pub(crate) fn key_name((code, modifiers): Key) -> String {
+    let mut name = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        name.push_str("Ctrl-");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        name.push_str("Alt-");
+    }
+    match code {
+        KeyCode::Char(' ') => name.push_str("Space"),
+        KeyCode::Char(c) => name.push(c),
+        KeyCode::F(n) => name.push_str(&format!("F{}", n)),
+        code => name.push_str(NAMED_KEYS.iter().find(|(_, k)| *k == code).map(|(n, _)| *n).unwrap_or("?")),
+    }
+    name
+}
+
+/// Keys bound to each action: the defaults, with the actions named in `[keys]` rebound
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<Key, Vec<Action>>,
+    keys: Vec<(&'static str, Action, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::new(&BTreeMap::new()).unwrap()
+    }
+}
+
+impl Keymap {
+    pub fn new(config: &BTreeMap<String, Keys>) -> Result<Keymap, String> {
+        if let Some(name) = config.keys().find(|name| !ACTIONS.iter().any(|(n, _, _)| n == name)) {
+            let names: Vec<&str> = ACTIONS.iter().map(|(n, _, _)| *n).collect();
+            return Err(format!("keys: unknown action '{}'; try {}", name, names.join(", ")));
+        }
+        let mut keymap = Keymap { bindings: HashMap::new(), keys: vec![] };
+        for (name, action, defaults) in ACTIONS {
+            let names = match config.get(name) {
+                Some(keys) => keys.names(),
+                None => defaults.to_vec(),
+            };
+            let keys = names.into_iter().map(parse_key).collect::<Result<Vec<_>, String>>().map_err(|e| format!("keys.{}: {}", name, e))?;
+            for key in &keys {
+                keymap.bindings.entry(*key).or_default().push(action);
+            }
+            keymap.keys.push((name, action, keys));
+        }
+        Ok(keymap)
+    }
+
+    /// The action `key` triggers; a key bound to several goes to the first that `applies` accepts
+    pub fn action(&self, key: KeyEvent, editing: bool, applies: impl Fn(Action) -> bool) -> Option<Action> {
+        let key = normalize(key);
+        // While typing, characters are text unless they come with Ctrl or Alt
+        if editing && matches!(key, (KeyCode::Char(_), KeyModifiers::NONE)) {
+            return None;
+        }
+        self.bindings
+            .get(&key)?
+            .iter()
+            .copied()
+            .filter(|action| if editing { EDITING.contains(action) } else { !TYPING_ONLY.contains(action) })
+            .find(|action| applies(*action))
+    }
+
+    /// The keys bound to `action`, as written in config.toml
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keys.iter().find(|(_, a, _)| *a == action).map(|(_, _, keys)| keys.as_slice()).unwrap_or_default();
+        keys.iter().map(|key| key_name(*key)).collect::<Vec<String>>().join("/")
+    }
+
+    /// `action = keys` lines for the `keys` command
+    pub fn list(&self) -> Vec<String> {
+        self.keys.iter().map(|(name, action, _)| format!("{:<17} {}", name, self.describe(*action))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_key_reads_characters_and_named_keys() {
+        assert_eq!(parse_key("j"), Ok((KeyCode::Char('j'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("J"), Ok((KeyCode::Char('J'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("-"), Ok((KeyCode::Char('-'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("PageDown"), Ok((KeyCode::PageDown, KeyModifiers::NONE)));
+        assert_eq!(parse_key("Space"), Ok((KeyCode::Char(' '), KeyModifiers::NONE)));
+        assert_eq!(parse_key("F9"), Ok((KeyCode::F(9), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn parse_key_reads_modifiers() {
+        assert_eq!(parse_key("Ctrl-d"), Ok((KeyCode::Char('d'), KeyModifiers::CONTROL)));
+        // Terminals report Ctrl with the lowercase letter
+        assert_eq!(parse_key("Ctrl-D"), Ok((KeyCode::Char('d'), KeyModifiers::CONTROL)));
+        assert_eq!(parse_key("Alt-Enter"), Ok((KeyCode::Enter, KeyModifiers::ALT)));
+        assert_eq!(parse_key("Ctrl-Alt-x"), Ok((KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT)));
+    }
+
+    #[test]
+    fn parse_key_refuses_unknown_names() {
+        for name in ["", "Ctrl-", "F0", "F13", "Shift-a", "enter", "jk"] {
+            assert_eq!(parse_key(name), Err(format!("unknown key '{}'", name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn quick_filters_can_be_rebound() {
+        let config = BTreeMap::from([("filter_zombies".to_string(), Keys::One("Z".to_string()))]);
+        let keymap = Keymap::new(&config).unwrap();
+        assert!(keymap.action(KeyEvent::from(KeyCode::Char('Z')), false, |_| true) == Some(Action::QuickFilter(Filter::Zombie)));
+        assert!(keymap.action(KeyEvent::from(KeyCode::Char('z')), false, |_| true).is_none());
+        assert!(keymap.action(KeyEvent::from(KeyCode::Char('g')), false, |_| true) == Some(Action::QuickFilter(Filter::Gpu)));
+    }
+
+    #[test]
+    fn key_name_reads_back_as_the_same_key() {
+        for name in ["j", "Ctrl-d", "Alt-Enter", "F12", "Space", "BackTab"] {
+            assert_eq!(key_name(parse_key(name).unwrap()), name);
+        }
+    }
+}
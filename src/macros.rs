@@ -0,0 +1,143 @@
+use crate::{keys, paths};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    fs,
+};
+
+/// Named key sequences, stored vim-style as text (`ptable<Enter><Down><Down>`) in macros.yaml
+#[derive(Default)]
+pub struct Macros {
+    saved: BTreeMap<String, String>,
+    recording: Option<(String, Vec<KeyEvent>)>,
+    /// Keys of a macro being played back, consumed before any terminal input
+    pub pending: VecDeque<KeyEvent>,
+}
+
+/// Plain characters as themselves, other keys by their keymap name (`<Ctrl-d>`, `<F5>`)
+fn encode(keys: &[KeyEvent]) -> String {
+    let mut text = String::new();
+    for key in keys {
+        match keys::normalize(*key) {
+            (KeyCode::Char('<'), KeyModifiers::NONE) => text.push_str("<lt>"),
+            (KeyCode::Char(c), KeyModifiers::NONE) => text.push(c),
+            key => {
+                let name = keys::key_name(key);
+                // Keys without a name, such as Insert, would not read back as themselves
+                if keys::parse_key(&name) == Ok(key) {
+                    text.push_str(&format!("<{}>", name));
+                }
+            },
+        }
+    }
+    text
+}
+
+fn decode(text: &str) -> Vec<KeyEvent> {
+    let mut keys: Vec<KeyEvent> = vec![];
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                let key = match &rest[1..end] {
+                    "lt" => Ok((KeyCode::Char('<'), KeyModifiers::NONE)),
+                    name => keys::parse_key(name),
+                };
+                if let Ok((code, modifiers)) = key {
+                    keys.push(KeyEvent::new(code, modifiers));
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        keys.push(KeyEvent::from(KeyCode::Char(c)));
+        rest = &rest[c.len_utf8()..];
+    }
+    keys
+}
+
+impl Macros {
+    pub fn load() -> Macros {
+        let saved = fs::read_to_string(paths::config_dir().join("macros.yaml"))
+            .ok()
+            .and_then(|text| serde_yaml::from_str(&text).ok())
+            .unwrap_or_default();
+        Macros { saved, ..Macros::default() }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let dir = paths::config_dir();
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("macros.yaml"), serde_yaml::to_string(&self.saved)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, key: KeyEvent) {
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(key);
+        }
+    }
+
+    pub fn start(&mut self, name: &str) -> Result<(), String> {
+        if let Some((recording, _)) = &self.recording {
+            return Err(format!("already recording '{}'", recording));
+        }
+        self.recording = Some((name.to_string(), vec![]));
+        Ok(())
+    }
+
+    /// Ends the recording and saves it, minus the keys that typed the `macro stop` line itself
+    pub fn stop(&mut self) -> Result<String, Box<dyn Error>> {
+        let (name, mut keys) = self.recording.take().ok_or("not recording")?;
+        keys.pop();
+        while keys.last().map(|k| k.code != KeyCode::Enter).unwrap_or(false) {
+            keys.pop();
+        }
+        let text = encode(&keys);
+        self.saved.insert(name.clone(), text.clone());
+        self.save()?;
+        Ok(format!("Saved macro '{}': {}", name, text))
+    }
+
+    pub fn play(&mut self, name: &str) -> Result<(), String> {
+        // A macro that plays itself would never finish
+        if !self.pending.is_empty() {
+            return Err("cannot start a macro from inside another macro".to_string());
+        }
+        let text = self.saved.get(name).ok_or(format!("no macro named '{}'", name))?;
+        self.pending.extend(decode(text));
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        self.saved.iter().map(|(name, text)| format!("{:<20} {}", name, text)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_names_keys_the_way_the_keymap_does() {
+        let keys = [
+            KeyEvent::from(KeyCode::Char('p')),
+            KeyEvent::from(KeyCode::Char('<')),
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+            KeyEvent::from(KeyCode::F(5)),
+            KeyEvent::from(KeyCode::PageDown),
+            KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT),
+            KeyEvent::from(KeyCode::Insert),
+        ];
+        assert_eq!(encode(&keys), "p<lt><Ctrl-d><F5><PageDown><BackTab><Alt-Enter>");
+    }
+
+    #[test]
+    fn decode_reads_back_what_encode_wrote() {
+        let text = "ps <lt>x<Ctrl-d><F5><PageUp><BackTab><Alt-Enter><nope>";
+        assert_eq!(encode(&decode(text)), "ps <lt>x<Ctrl-d><F5><PageUp><BackTab><Alt-Enter><lt>nope>");
+        assert_eq!(decode("<Ctrl-d>"), vec![KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL)]);
+    }
+}
@@ -1,31 +1,180 @@
 // https://github.com/fdehau/tui-rs/blob/master/examples/user_input.rs
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{error::Error, io};
+use clap::Parser;
+use std::{collections::VecDeque, error::Error, io::{self, Write}, time::{Duration, Instant}};
 use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    backend::{Backend, CrosstermBackend, TestBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs},
     Frame, Terminal,
 };
 use sysinfo::{ComponentExt, System, SystemExt, CpuExt, DiskExt};
 use unicode_width::UnicodeWidthStr;
 use nix::sys::signal::{kill, Signal};
 use nix::unistd::Pid;
+use nix::sys::statvfs::statvfs;
 use std::str;
-use std::process::Command;
+use std::fs;
 use psutil::process::Process;
-use sysinfo::NetworkExt;
 use pretty_bytes::converter::convert;
+use keys::Action;
+use records::Records;
+use views::View;
 
+mod alerts;
+mod cgroup;
+mod chain;
+mod chart;
+mod cli;
+mod clipboard;
+mod complete;
+mod config;
+mod control;
+mod dns;
+mod docker;
+mod dump;
+mod export;
+mod gpu;
+mod gauges;
+mod graph;
+mod guard;
+mod hardware;
+mod help;
+mod history;
+mod jobs;
+mod journal;
+mod keys;
+mod nethogs;
+mod notify;
+mod macros;
+mod mqtt;
+mod network;
+mod paging;
+mod paths;
+mod perf;
+mod pressure;
+mod ping;
+mod pipe;
+mod popup;
+#[cfg(feature = "kubernetes")]
+mod pods;
+mod portwatch;
+mod procio;
+mod pstree;
+mod ptable;
+mod record;
+mod recall;
+mod records;
+mod remote;
+mod report;
+mod rest;
+mod rules;
+mod scrollbar;
+mod search;
+mod sched;
+mod services;
+mod snapshots;
+#[cfg(feature = "ebpf")]
+mod snoop;
+mod sockets;
+mod spawn;
+mod storage;
+mod theme;
+mod thermals;
+mod toasts;
+mod trace;
+mod views;
+mod vmstat;
+mod watchdog;
+mod webhook;
+mod who;
+
+/// Views that keep appending to the output pane on every sampler tick
+enum Live {
+    Vmstat(vmstat::Vmstat),
+    Journal(journal::Journal),
+    /// Redrawn in place from the paging tracker and history
+    Paging,
+    /// Run queue latency of one process, redrawn in place
+    Schedlat(i32),
+    /// Per-interface throughput, redrawn in place
+    Network,
+    Nethogs(nethogs::Nethogs),
+    Ping(ping::Ping),
+    /// Temperature history, redrawn in place
+    Thermals,
+    /// Chart drawn from the history store, with its text form kept in the output
+    Graph(graph::Graph),
+    /// Any command re-run on its own timer
+    Watch(Watch),
+    /// Syscalls of a process under strace, summarised when detached
+    Trace(trace::Trace),
+    /// Hardware counters of a process, reported once perf finishes
+    PerfStat(perf::PerfStat),
+    /// What a job started by `ignite` prints, streamed until it exits
+    Ignite(usize),
+    /// Executions or file opens streamed from eBPF
+    #[cfg(feature = "ebpf")]
+    Snoop(snoop::Snoop),
+}
+
+/// A command line `watch` re-runs every `interval`
+struct Watch {
+    command: String,
+    interval: Duration,
+    last: Instant,
+}
+
+/// Drawn above the records of a command that shows them that way
+enum Bars {
+    /// RAM and swap as gauges over their byte counts
+    Memory(Vec<gauges::Usage>),
+    /// A bar of used space per filesystem
+    Filesystems(Vec<gauges::Usage>),
+}
+
+/// What `clear` took off the output pane, for `unclear` to put back
+struct Cleared {
+    output: Vec<String>,
+    records: Option<Records>,
+    bars: Option<Bars>,
+    table: Option<ptable::ProcessTable>,
+    tree: Option<pstree::ProcessTree>,
+}
+
+/// Cleared outputs `unclear` can go back through
+const CLEARED_KEPT: usize = 10;
+
+/// Lines kept in the output pane while following a live log
+const FOLLOW_LINES: usize = 500;
+
+/// Probes shown in the ping sparkline
+const PING_WIDTH: usize = 100;
+
+/// Characters across each chart of a graph's text form
+const GRAPH_WIDTH: usize = 60;
+
+/// Latest alert log entries kept in sight below the output
+const ALERT_PANE_LINES: usize = 5;
+
+#[derive(Clone, Copy, PartialEq)]
 enum InputMode {
     Normal,
     Editing,
+    /// Typing a PID or row number to jump to in the process table
+    Goto,
+    /// Typing what to search the output for
+    Search,
+    /// Typing text the rows of the process table must contain
+    Filter,
+    /// Typing what to look for in the command lines entered before
+    Recall,
 }
 
 /// App holds the state of the application
@@ -34,8 +183,114 @@ struct App {
     input: String,
     /// Current input mode
     input_mode: InputMode,
+    /// Command lines entered, oldest first
     messages: Vec<String>,
+    /// Position of Up/Down and Ctrl-R in `messages`
+    recall: recall::Recall,
     output: Vec<String>,
+    /// Process tree printed by `pstree`, shown in place of the output lines
+    tree: Option<pstree::ProcessTree>,
+    /// First line of the output pane after scrolling it with the mouse wheel, `None` to keep
+    /// the search match or the newest line of a live view in sight
+    scroll: Option<usize>,
+    /// First line of the output pane as last drawn, where the wheel scrolls from
+    output_top: usize,
+    /// Alert/autokill rules loaded with `rules load`
+    rules: Vec<rules::Rule>,
+    /// Most recent sample taken by the background sampler
+    snapshot: Option<rules::Snapshot>,
+    /// Rule evaluation state, the firing banner and the alert log
+    alerts: alerts::Alerts,
+    /// Set when the last command failed, for `&&`
+    failed: bool,
+    /// Commands left of a line, waiting for the `ignite` job before them to exit
+    chained: Option<(usize, Vec<chain::Step>)>,
+    /// Background events shown briefly over the output, whatever view is up
+    toasts: toasts::Toasts,
+    /// Last status string written to the terminal/tmux title
+    title: String,
+    /// Live view currently feeding the output pane, replaced by the next command
+    live: Option<Live>,
+    /// Per-process disk I/O rates, refreshed by the sampler
+    io: procio::IoTracker,
+    /// System-wide and per-process swap/major fault rates, refreshed by the sampler
+    paging: paging::PagingTracker,
+    /// Per-process run queue latency, refreshed by the sampler
+    sched: sched::SchedTracker,
+    /// Per-interface throughput, refreshed by the sampler
+    net: network::NetTracker,
+    /// Rolling store of past samples
+    history: history::History,
+    /// Candidates offered by the last `pick`
+    picks: Vec<i32>,
+    /// Process chosen with `pick <n>`, the target of a bare `kill`
+    selected: Option<i32>,
+    /// Recorded key sequences and the playback queue
+    macros: macros::Macros,
+    /// Settings from config.toml
+    config: config::Config,
+    /// Process table shown instead of the text output, if the last command produced one
+    table: Option<ptable::ProcessTable>,
+    /// Columns and rows shown as a table instead of the text output, if the last command produced them
+    records: Option<Records>,
+    /// Processes started with `ignite`
+    jobs: jobs::Jobs,
+    /// Processes watched with `watchdog`
+    watchdogs: watchdog::Watchdogs,
+    /// Resource limits enforced with `guard`
+    guards: guard::Guards,
+    webhooks: webhook::Webhooks,
+    /// Patterns watched with `notify-on-spawn`
+    spawns: spawn::SpawnWatcher,
+    /// Ports watched with `portwatch`
+    ports: portwatch::PortWatcher,
+    /// Config alarms over their thresholds in the latest sample
+    warnings: Vec<String>,
+    /// Whether the alert log pane is shown below the output, toggled with `a`
+    alert_pane: bool,
+    /// HTTP API started with `serve`
+    server: Option<rest::Server>,
+    /// Socket `proclynxctl` sends commands through
+    control: Option<control::Control>,
+    /// Hosts added with `connect`, each shown in its own tab
+    hosts: Vec<remote::Remote>,
+    /// Tab shown: 0 is this host, then one per connected host and the overview last
+    tab: usize,
+    /// Running as `--agent` with no terminal of its own
+    headless: bool,
+    /// `| grep`/`head`/`tail` stages of the last command, reapplied while a live view updates
+    pipe: Vec<pipe::Stage>,
+    /// Active `record start` output
+    recorder: Option<record::Recorder>,
+    /// Metrics publisher started from config.toml or with `mqtt start`
+    mqtt: Option<mqtt::Publisher>,
+    /// Holds on to what `copy` and `y` put on the clipboard
+    clipboard: clipboard::Keeper,
+    /// Command-line flags, which win over config.toml
+    cli: cli::Cli,
+    /// Colours of the UI, from the theme in config.toml and theme.toml
+    theme: theme::Theme,
+    /// Keys bound to each action, from [keys] in config.toml
+    keymap: keys::Keymap,
+    /// Dashboard tab shown instead of the command output, switched with the number keys
+    views: views::Views,
+    /// Host, uptime, load, memory, CPU and logged-in users from the latest sample, shown in the
+    /// status bar while this host's tab is open
+    status: String,
+    /// Shown over everything else until Esc
+    popup: Option<popup::Popup>,
+    /// Whether the output pane takes the whole terminal, for reading wide tables
+    zoom: bool,
+    /// Command line run when the question in the popup is answered with y
+    confirm: Option<String>,
+    /// Outputs taken off the pane by `clear`, newest last
+    cleared: VecDeque<Cleared>,
+    /// Gauges shown above the records of `memory` and `df`
+    bars: Option<Bars>,
+    /// Matches highlighted in the output until Esc or the next command
+    search: Option<search::Search>,
+    /// Candidates listed above the input after a Tab that had more than one
+    completions: Vec<String>,
 }
 
 impl Default for App {
@@ -44,12 +299,80 @@ impl Default for App {
             input: String::new(),
             input_mode: InputMode::Normal,
             messages: Vec::new(),
+            recall: recall::Recall::default(),
             output: Vec::new(),
+            tree: None,
+            scroll: None,
+            output_top: 0,
+            rules: Vec::new(),
+            snapshot: None,
+            alerts: alerts::Alerts::default(),
+            failed: false,
+            chained: None,
+            toasts: toasts::Toasts::default(),
+            title: String::new(),
+            live: None,
+            io: procio::IoTracker::default(),
+            paging: paging::PagingTracker::default(),
+            sched: sched::SchedTracker::default(),
+            net: network::NetTracker::default(),
+            history: history::History::default(),
+            picks: Vec::new(),
+            selected: None,
+            macros: macros::Macros::load(),
+            config: config::Config::default(),
+            table: None,
+            records: None,
+            jobs: jobs::Jobs::default(),
+            watchdogs: watchdog::Watchdogs::default(),
+            guards: guard::Guards::default(),
+            webhooks: webhook::Webhooks::default(),
+            spawns: spawn::SpawnWatcher::default(),
+            ports: portwatch::PortWatcher::default(),
+            warnings: vec![],
+            alert_pane: true,
+            pipe: vec![],
+            server: None,
+            control: None,
+            hosts: vec![],
+            tab: 0,
+            headless: false,
+            recorder: None,
+            mqtt: None,
+            clipboard: clipboard::Keeper::default(),
+            cli: cli::Cli::default(),
+            theme: theme::Theme::default(),
+            keymap: keys::Keymap::default(),
+            views: views::Views::default(),
+            status: String::new(),
+            popup: None,
+            zoom: false,
+            confirm: None,
+            cleared: VecDeque::new(),
+            bars: None,
+            search: None,
+            completions: vec![],
         }
     }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = cli::Cli::parse();
+    if let Some(path) = &cli.config {
+        config::set_path(path.clone());
+    }
+    if cli.agent {
+        return run_agent(cli);
+    }
+    if let Some(cli::Command::Exec { json, csv, line }) = &cli.command {
+        let format = match (json, csv) {
+            (true, _) => cli::Format::Json,
+            (_, true) => cli::Format::Csv,
+            _ => cli::Format::Text,
+        };
+        let line = line.join(" ");
+        return run_exec(cli, &line, format);
+    }
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -58,7 +381,24 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let app = App::default();
+    let mut app = App { cli, ..App::default() };
+    match config::Config::load() {
+        Ok(config) => app.config = config,
+        Err(e) => app.output.push(format!("Error loading {}: {}", config::path().display(), e)),
+    }
+    app.cli.apply(&mut app.config);
+    apply_ui_settings(&mut app);
+    app.messages = recall::load(app.config.history_size);
+    if let Some(config) = app.config.mqtt.clone() {
+        match mqtt::Publisher::start(&config, &System::new().host_name().unwrap_or_default()) {
+            Ok(publisher) => app.mqtt = Some(publisher),
+            Err(e) => app.output.push(format!("mqtt: {}", e)),
+        }
+    }
+    match control::Control::start() {
+        Ok(control) => app.control = Some(control),
+        Err(e) => app.output.push(format!("Control socket disabled, proclynxctl will not work: {}", e)),
+    }
     let res = run_app(&mut terminal, app);
 
     // restore terminal
@@ -69,6 +409,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         DisableMouseCapture
     )?;
     terminal.show_cursor()?;
+    // tmux shows the host name as the pane title by default
+    set_pane_title(&System::new().host_name().unwrap_or_default());
 
     if let Err(err) = res {
         println!("{:?}", err)
@@ -77,282 +419,2177 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Serves command lines read from stdin for `connect` on another host, answering each with its
+/// output followed by `remote::END`
+fn run_agent(cli: cli::Cli) -> Result<(), Box<dyn Error>> {
+    let mut app = App { headless: true, cli, ..App::default() };
+    if let Ok(config) = config::Config::load() {
+        app.config = config;
+    }
+    app.cli.apply(&mut app.config);
+    // Whoever connected gets the output; the remote desktop is not theirs
+    app.config.notify.desktop = false;
+    let mut terminal = Terminal::new(TestBackend::new(80, 24))?;
+    let mut sys = System::new_all();
+    sample(&mut app, &mut sys);
+    let mut last_tick = Instant::now();
+    let mut stdout = io::stdout();
+    for line in io::stdin().lines() {
+        let line = line?;
+        if last_tick.elapsed() >= app.config.refresh() {
+            sample(&mut app, &mut sys);
+            last_tick = Instant::now();
+        }
+        if line == remote::METRICS {
+            let metrics = app.snapshot.as_ref().map(remote::HostMetrics::from_snapshot).unwrap_or_default();
+            writeln!(stdout, "{}", serde_json::to_string(&metrics)?)?;
+        } else if !line.trim().is_empty() {
+            for output in run_detached(&mut terminal, &mut app, &mut sys, &line)? {
+                writeln!(stdout, "{}", output)?;
+            }
+        }
+        writeln!(stdout, "{}", remote::END)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// Wait between the two samples `exec` takes, so CPU and I/O rates have something to measure against
+const EXEC_WARMUP: Duration = Duration::from_millis(500);
+
+/// `proclynx exec <command>`: runs one command line without the TUI and prints the output, or the
+/// process table rows, to stdout
+fn run_exec(cli: cli::Cli, line: &str, format: cli::Format) -> Result<(), Box<dyn Error>> {
+    let mut app = App { headless: true, cli, ..App::default() };
+    app.config = config::Config::load().map_err(|e| format!("{}: {}", config::path().display(), e))?;
+    app.cli.apply(&mut app.config);
+    apply_ui_settings(&mut app);
+    app.config.notify.desktop = false;
+    if line.split_whitespace().next() == Some("edit") {
+        return Err("edit needs the terminal; run it in the proclynx window".into());
+    }
+    let mut terminal = Terminal::new(TestBackend::new(80, 24))?;
+    let mut sys = System::new_all();
+    sample(&mut app, &mut sys);
+    std::thread::sleep(EXEC_WARMUP);
+    sample(&mut app, &mut sys);
+    // Events from the samples are not part of the command's output
+    app.output.clear();
+    run_command(&mut terminal, &mut app, &mut sys, line)?;
+    let records = match (&app.table, app.records.take()) {
+        (Some(table), _) => table.records(),
+        (None, Some(records)) => records,
+        (None, None) => Records::from_lines(&app.output),
+    };
+    let text = match (format, &app.table) {
+        // Numbers stay numbers
+        (cli::Format::Json, Some(table)) => serde_json::to_string_pretty(&table.visible())?,
+        (cli::Format::Json, None) => serde_json::to_string_pretty(&records.to_json())?,
+        (cli::Format::Csv, _) => records.to_csv().trim_end().to_string(),
+        (cli::Format::Text, Some(table)) => table.lines().join("\n"),
+        (cli::Format::Text, None) => app.output.join("\n"),
+    };
+    // Piped into `head`, the reader may be gone before everything is written
+    match writeln!(io::stdout(), "{}", text) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Longest a `proclynxctl` command waits for the UI loop to pick it up
+const CONTROL_POLL: Duration = Duration::from_millis(100);
+
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
-    let mut flag: bool = false;
-    let mut num: i32 = 0;
     let mut sys = System::new_all();
-    let mut arg = String::new();
-    let mut parts: Vec<String>;
-    let mut history: Vec<String> = vec![];
+    sample(&mut app, &mut sys);
+    let notices = std::mem::take(&mut app.output);
+    for line in app.config.startup.iter().chain(&app.cli.commands).cloned().collect::<Vec<String>>() {
+        run_command(terminal, &mut app, &mut sys, &line)?;
+    }
+    // Problems loading the config stay on screen above the startup output
+    app.output.splice(0..0, notices);
+    let mut last_tick = Instant::now();
     loop {
-        terminal.draw(|f| ui(f, &app))?;
-        if let Event::Key(key) = event::read()? {
-            match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('e') | KeyCode::Char('E') => {
-                        app.input_mode = InputMode::Editing;
-                    }
-                    KeyCode::Char('q') | KeyCode::Char('Q') => {
-                        return Ok(());
+        app.jobs.pump();
+        let mut finished: Vec<String> = vec![];
+        for job in app.jobs.reap() {
+            let long = job.finished.map(|(_, ran)| ran.as_secs() >= app.config.notify.long_job_secs).unwrap_or(false);
+            if long && app.config.notify.desktop {
+                notify::desktop("proclynx job finished", &job.describe(), false);
+            }
+            finished.push(job.describe());
+        }
+        for line in &finished {
+            toast(&mut app, line);
+        }
+        match app.live {
+            Some(Live::Ignite(id)) => show_job(&mut app, id),
+            _ if app.table.is_none() && app.records.is_none() => app.output.extend(finished),
+            _ => {},
+        }
+        // The rest of a command line waits for the job it started
+        if let Some((id, steps)) = app.chained.take() {
+            match app.jobs.get(id) {
+                Some(job) if !job.done() => app.chained = Some((id, steps)),
+                Some(job) => {
+                    app.failed = !job.succeeded();
+                    run_steps(terminal, &mut app, &mut sys, steps, true)?;
+                },
+                None => {},
+            }
+        }
+        if last_tick.elapsed() >= app.config.refresh() {
+            sample(&mut app, &mut sys);
+            last_tick = Instant::now();
+        }
+        refresh_watch(terminal, &mut app, &mut sys)?;
+        update_hosts(&mut app);
+        let requests = app.control.as_ref().map(|control| control.requests()).unwrap_or_default();
+        for (line, stream) in requests {
+            let lines = run_detached(terminal, &mut app, &mut sys, &line)?;
+            control::reply(stream, &lines);
+        }
+        app.toasts.expire(Duration::from_secs(app.config.notify.toast_secs));
+        terminal.draw(|f| ui(f, &mut app))?;
+        let mut timeout = app.config.refresh().saturating_sub(last_tick.elapsed());
+        if let Some(Live::Watch(watch)) = &app.live {
+            timeout = timeout.min(watch.interval.saturating_sub(watch.last.elapsed()));
+        }
+        if app.control.is_some() {
+            timeout = timeout.min(CONTROL_POLL);
+        }
+        if let Some(key) = next_key(&mut app, timeout)? {
+            // A question in the popup takes the next key: y goes ahead, anything else cancels
+            if let Some(line) = app.confirm.take() {
+                app.popup = None;
+                if key.code == KeyCode::Char('y') {
+                    run_command(terminal, &mut app, &mut sys, &line)?;
+                }
+                continue;
+            }
+            let table = active_table(&mut app).is_some();
+            let tree = app.tree.is_some() && app.views.current == View::Output && app.tab == 0;
+            let tabs = !app.hosts.is_empty();
+            let editing = app.input_mode != InputMode::Normal;
+            let action = app.keymap.action(key, editing, |action| match action {
+                // This host, each connected host, then the overview
+                Action::NextTab | Action::PrevTab => tabs,
+                Action::Copy | Action::Goto | Action::SortCpu | Action::SortMem | Action::SortPid | Action::Reverse | Action::Filter | Action::QuickFilter(_) => table,
+                Action::Kill | Action::Details => table || tree,
+                Action::Collapse | Action::Expand => tree,
+                Action::Search => !table && app.tab == 0,
+                Action::NextMatch | Action::PrevMatch => app.search.is_some(),
+                Action::HistoryPrev | Action::HistoryNext => app.input_mode == InputMode::Editing,
+                Action::HistorySearch => matches!(app.input_mode, InputMode::Editing | InputMode::Recall),
+                _ => true,
+            });
+            if action != Some(Action::Complete) && key.code != KeyCode::Backspace && !matches!(key.code, KeyCode::Char(_)) {
+                app.completions.clear();
+            }
+            // Esc closes an open popup, then clears a search, then goes back from scrolling, before it stops anything
+            if action == Some(Action::Stop) && (app.popup.take().is_some() || app.search.take().is_some() || app.scroll.take().is_some()) {
+                continue;
+            }
+            match (app.input_mode, action) {
+                (_, Some(action @ (Action::Down | Action::Up | Action::PageDown | Action::PageUp))) if tree => {
+                    if let Some(tree) = &mut app.tree {
+                        match action {
+                            Action::Down => tree.scroll(1),
+                            Action::Up => tree.scroll(-1),
+                            _ => tree.page(action == Action::PageDown),
+                        }
                     }
-                    _ => {}
-                },
-                InputMode::Editing => match key.code {
-                    KeyCode::Down => {
-                        if flag {
-                            if !(app.output.is_empty()) && history.len() <= (num-45).try_into().unwrap() {
-                                history.push(app.output.remove(1)); 
-                            }                            
+                },
+                (_, Some(Action::Down)) => scroll_table(&mut app, |table| table.scroll(1)),
+                (_, Some(Action::Up)) => scroll_table(&mut app, |table| table.scroll(-1)),
+                (_, Some(Action::PageDown)) => scroll_table(&mut app, |table| table.page(true)),
+                (_, Some(Action::PageUp)) => scroll_table(&mut app, |table| table.page(false)),
+                (InputMode::Normal, Some(action)) => match action {
+                    Action::Edit => app.input_mode = InputMode::Editing,
+                    Action::Quit => return Ok(()),
+                    Action::Stop => {
+                        if let Some(Live::Trace(mut trace)) = app.live.take() {
+                            app.output = trace.summary();
+                            app.records = None;
                         }
                     },
-                    KeyCode::Up => {
-
-                        if flag {
-                            if !(history.is_empty()) {
-                                app.output.insert(1, history.pop().unwrap());
-                            }                            
+                    Action::Alerts => app.alert_pane = !app.alert_pane,
+                    Action::Zoom => app.zoom = !app.zoom,
+                    Action::Help => {
+                        app.popup = Some(popup::Popup { title: "Help".to_string(), body: popup::Body::Columns(help::overlay(&app.keymap.list())) });
+                    },
+                    Action::NextTab => {
+                        app.tab = (app.tab + 1) % (app.hosts.len() + 2);
+                        app.views.current = View::Output;
+                    },
+                    Action::PrevTab => {
+                        app.tab = (app.tab + app.hosts.len() + 1) % (app.hosts.len() + 2);
+                        app.views.current = View::Output;
+                    },
+                    Action::Copy => {
+                        if let Some(pid) = active_table(&mut app).and_then(|t| t.selected_row()).map(|row| row.pid) {
+                            let message = match app.clipboard.write(&pid.to_string()) {
+                                Ok(_) => format!("Copied PID {} to the clipboard", pid),
+                                Err(e) => format!("Error writing clipboard: {}", e),
+                            };
+                            toast(&mut app, &message);
+                        }
+                    },
+                    Action::Goto => {
+                        app.input.clear();
+                        app.input_mode = InputMode::Goto;
+                    },
+                    Action::SortCpu => sort_table(&mut app, ptable::SortColumn::Cpu, true),
+                    Action::SortMem => sort_table(&mut app, ptable::SortColumn::Mem, true),
+                    Action::SortPid => sort_table(&mut app, ptable::SortColumn::Pid, false),
+                    Action::Reverse => {
+                        if let Some(table) = active_table(&mut app) {
+                            table.sort_by(table.sort, !table.descending);
+                        }
+                    },
+                    Action::Kill if tree => kill_tree_selected(&mut app),
+                    Action::Kill => kill_selected(&mut app),
+                    Action::Collapse | Action::Expand => {
+                        if let Some(tree) = &mut app.tree {
+                            if action == Action::Collapse { tree.collapse() } else { tree.expand() }
+                        }
+                    },
+                    Action::Details if tree => {
+                        if let Some((pid, name)) = app.tree.as_ref().and_then(|tree| tree.selected_pid().map(|pid| (pid, tree.name(pid)))) {
+                            app.popup = Some(popup::Popup { title: format!("{} ({})", name, pid), body: popup::Body::Text(get_process_details(&app, pid as i32)) });
+                        }
+                    },
+                    Action::Details => {
+                        if let Some(row) = active_table(&mut app).and_then(|t| t.selected_row()) {
+                            let (pid, name) = (row.pid as i32, row.name.clone());
+                            app.popup = Some(popup::Popup { title: format!("{} ({})", name, pid), body: popup::Body::Text(get_process_details(&app, pid)) });
+                        }
+                    },
+                    Action::Search => {
+                        app.input.clear();
+                        app.search = None;
+                        app.input_mode = InputMode::Search;
+                    },
+                    Action::Filter => {
+                        app.input = active_table(&mut app).map(|table| table.query.clone()).unwrap_or_default();
+                        app.input_mode = InputMode::Filter;
+                    },
+                    Action::NextMatch | Action::PrevMatch => {
+                        let lines = search_lines(&app);
+                        if let Some(search) = &mut app.search {
+                            search.step(&lines, action == Action::NextMatch);
+                            app.scroll = None;
                         }
                     },
-                    KeyCode::Enter => {
-                        parts = app.input.split_whitespace().map(|s| s.to_string()).collect();
-                        app.output.clear();
-                        app.messages.push(app.input.drain(..).collect());
-                        match parts[0].as_str() {
-                            "uname" => {
-                                flag = false;
-                                app.output.push(format!("{}", sys.kernel_version().unwrap()))
-                            },
-                            "release" => {
-                                flag = false;
-                                app.output.push(format!("{}", sys.os_version().unwrap()))
-                            },
-                            "hostname" => {
-                                flag = false;
-                                app.output.push(format!("{}", sys.host_name().unwrap()))
-                            },
-                            "sysinfo" => {
-                                flag = false;
-                                app.output = get_system_information(&mut sys);
-                            },
-                            "sensors" => {
-                                flag = false;
-                                app.output = get_components_information(&mut sys);
-                            },
-                            "df" => {
-                                flag = false;
-                                if parts.len() == 2 {
-                                    arg = parts[1][1..].to_string();
-                                }
-                                app.output = get_disks_information(&mut sys, arg.clone());
-                            },
-                            "hddtemp" => {
-                                flag = false;
-                                if parts.len() == 2 {
-                                    arg = parts[1][1..].to_string();
-                                }
-                                app.output = get_hddtemp(&mut sys, arg.clone());
-                            },
-                            "lscpu" => {
-                                flag = false;
-                                app.output = get_cpu_information(&mut sys);
-                            },
-                            "gputemp" => {
-                                flag = false;
-                                if parts.len() == 2 {
-                                    arg = parts[1][1..].to_string();
-                                }
-                                app.output = get_gputemp(&mut sys, arg.clone());
-                            },
-                            "kill" => {
-                                flag = false;
-                                if parts.len() == 2 {
-                                    if parts[1].parse::<i32>().is_ok() {
-                                        let pid = parts[1].parse::<i32>().unwrap();
-                                        kill_by_pid(&mut app, pid);
-                                    }
-                                    else {
-                                        kill_by_name(&mut app, parts[1].clone());
-                                    }
-                                }
-                            },
-                            "ignite" => {
-                                flag = false;
-                                if parts.len() == 2 {
-                                    Command::new(parts[1].as_str()).output()?;    
-                                }
-                            },
-                            "ptable" => {
-                                num = printptable(&mut app);
-                                flag = true;
-                            },
-                            "clear" => {
-                                flag = false;
-                                app.output.clear();
-                            },
-                            "help"=> {
-                                app.output.push(format!("COMMANDS .\n"));
-                                app.output.push(format!("find (pid) --> retrievs the info of process with (pid)"));
-                                app.output.push(format!("ignite --> start new process"));
-                                app.output.push(format!("ptable --> prints proces table"));
-                                app.output.push(format!("desc --> sort process table descendingly"));
-                                app.output.push(format!("sysinfo --> retrieves system info"));
-                                app.output.push(format!("kill (pid/name)--> kill process with (pid/name)"));
-                                app.output.push(format!("uname --> prints the kernel version"));
-                                app.output.push(format!("uname --> prints the kernel version"));
-                                app.output.push(format!("release --> prints the OS version"));
-                                app.output.push(format!("release --> prints the OS version"));
-                                app.output.push(format!("hostname --> prints the hostname"));
-                                app.output.push(format!("sensors --> prints the labels of various components with their associated temperatures"));
-                                app.output.push(format!("df --> prints the disk filesystem information"));
-                                app.output.push(format!("hddtemp --> prints the temperature of the internal HDD/SSD"));
-                                app.output.push(format!("lscpu --> lists the processor information"));
-                                app.output.push(format!("gputemp --> prints the temperature of the GPU"));
-                                app.output.push(format!("network --> prints information pertaining to network utilization"));
-                                app.output.push(format!("memory --> prints information pertaining to memory utilization"));
-                            },
-                            "find" => {
-                                if parts.len() == 2 {
-                                    let pid = parts[1].parse::<i32>().unwrap();
-                                    if let Some(process) = findbypid(pid) {
-                                        app.output.push(format!("Process with PID {} found!: {:?}", pid, process.name().unwrap()));
-                                        let mut p = process;
-                                        app.output.push(format!("{:<30} {:<30} {:<30} {:<30}", "PID","%CPU", "%MEM", "COMMAND"));
-                                        match p.cmdline() {
-                                            Ok(None) => {},
-                                            _=> {app.output.push(format!("{:<30} {:<30} {:<30} {:<30}", p.pid(), p.cpu_percent().unwrap(), p.memory_percent().unwrap(), p.cmdline().unwrap().expect("Oops something went wrong!").to_string()));},
-                                        }
-                                        // app.output.push(format!("Process with PID {} found!: {:?}", pid, process.cpu_percent().unwrap()));
-                                    } else {
-                                        app.output.push(format!("Process not found with PID {}", pid));
-                                    }
-                                }
-                            },
-                            "network" =>{
-                                networkuti(&mut app);
-                            },
-                            "memory" => {
-                                memutil(&mut app)
-                            },
-                            "desc" =>{
-                                desc(&mut app);
-                            },
-                            _ => {app.output.push(format!("command not found"))},
+                    Action::QuickFilter(filter) => {
+                        if let Some(table) = active_table(&mut app) {
+                            table.toggle(filter);
                         }
-                        
+                    },
+                    Action::View(view) => {
+                        app.views.current = view;
+                        refresh_view(&mut app, &mut sys);
+                    },
+                    _ => {},
+                },
+                (InputMode::Goto, Some(Action::Submit)) => {
+                    let target = app.input.trim().to_string();
+                    let found = active_table(&mut app).map(|table| table.goto(&target)).unwrap_or(true);
+                    // Leave an unmatched target in place so it can be corrected
+                    if found {
+                        app.input.clear();
+                        app.input_mode = InputMode::Normal;
+                    }
+                },
+                (InputMode::Goto, Some(Action::Cancel)) => {
+                    app.input.clear();
+                    app.input_mode = InputMode::Normal;
+                },
+                (InputMode::Search, Some(Action::Submit)) => {
+                    if app.input.is_empty() {
+                        app.search = None;
+                    }
+                    app.input.clear();
+                    app.input_mode = InputMode::Normal;
+                },
+                (InputMode::Search, Some(Action::Cancel)) => {
+                    app.search = None;
+                    app.input.clear();
+                    app.input_mode = InputMode::Normal;
+                },
+                (InputMode::Filter, Some(Action::Submit)) => {
+                    app.input.clear();
+                    app.input_mode = InputMode::Normal;
+                },
+                (InputMode::Filter, Some(Action::Cancel)) => {
+                    if let Some(table) = active_table(&mut app) {
+                        table.set_query("");
+                    }
+                    app.input.clear();
+                    app.input_mode = InputMode::Normal;
+                },
+                (InputMode::Editing, Some(Action::HistoryPrev)) => {
+                    if let Some(line) = app.recall.older(&app.messages, &app.input) {
+                        app.input = line;
+                    }
+                },
+                (InputMode::Editing, Some(Action::HistoryNext)) => {
+                    if let Some(line) = app.recall.newer(&app.messages) {
+                        app.input = line;
+                    }
+                },
+                (InputMode::Editing, Some(Action::HistorySearch)) => {
+                    app.recall.start_search(&app.input);
+                    app.input.clear();
+                    app.input_mode = InputMode::Recall;
+                },
+                (InputMode::Recall, Some(Action::HistorySearch)) => app.recall.search(&app.messages, &app.input, true),
+                (InputMode::Recall, Some(action @ (Action::Submit | Action::Cancel))) => {
+                    app.input = app.recall.finish_search(&app.messages, action == Action::Submit);
+                    app.input_mode = InputMode::Editing;
+                },
+                (InputMode::Editing, Some(Action::Submit)) => {
+                    let line: String = app.input.drain(..).collect();
+                    app.recall.reset();
+                    if line.trim().is_empty() {
+                        continue;
                     }
-                    KeyCode::Char(c) => {
-                        app.input.push(c);
+                    recall::push(&mut app.messages, &line, app.config.history_size);
+                    if let Err(e) = recall::save(&app.messages) {
+                        app.alerts.note(&format!("history: writing {} failed: {}", recall::path().display(), e));
                     }
-                    KeyCode::Backspace => {
-                        app.input.pop();
+                    // The output of what was typed is what matters now
+                    app.views.current = View::Output;
+                    run_command(terminal, &mut app, &mut sys, &line)?;
+                },
+                (InputMode::Editing, Some(Action::Complete)) => {
+                    let completion = complete::complete(&app.input, app.snapshot.as_ref(), &app.config.aliases);
+                    app.completions.clear();
+                    match completion.candidates.as_slice() {
+                        [] => {},
+                        [only] => {
+                            app.input.truncate(completion.start);
+                            app.input.push_str(only);
+                            app.input.push(' ');
+                        },
+                        many => {
+                            app.input.truncate(completion.start);
+                            app.input.push_str(&complete::common_prefix(many));
+                            app.completions = many.to_vec();
+                        },
                     }
-                    KeyCode::Esc => {
+                },
+                (InputMode::Editing, Some(Action::Cancel)) => {
+                    // The first cancel stops a running live view, the next one leaves editing
+                    let stopped = app.live.take().is_some();
+                    if !stopped {
                         app.input_mode = InputMode::Normal;
                     }
-                    _ => {}
+                },
+                (_, _) => {
+                    match key.code {
+                        KeyCode::Char(c) => app.input.push(c),
+                        KeyCode::Backspace => {
+                            app.input.pop();
+                        },
+                        _ => {},
+                    }
+                    // Shown completions narrow as the word is typed
+                    if !app.completions.is_empty() {
+                        app.completions = complete::complete(&app.input, app.snapshot.as_ref(), &app.config.aliases).candidates;
+                    }
+                    // Searching jumps to the first match and filtering narrows the table as the text is typed
+                    if app.input_mode == InputMode::Search {
+                        let mut search = search::Search::new(&app.input);
+                        search.step(&search_lines(&app), true);
+                        app.search = Some(search);
+                        app.scroll = None;
+                    }
+                    if app.input_mode == InputMode::Recall {
+                        app.recall.search(&app.messages, &app.input, false);
+                    }
+                    if app.input_mode == InputMode::Filter {
+                        let query = app.input.clone();
+                        if let Some(table) = active_table(&mut app) {
+                            table.set_query(&query);
+                        }
+                    }
                 },
             }
         }
     }
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+/// The lines `/` searches: the rows of the records shown, or the output
+fn search_lines(app: &App) -> Vec<String> {
+    match &app.records {
+        Some(records) => records.row_lines(),
+        None => app.output.clone(),
+    }
+}
+
+/// The process table the keys act on: the Processes view's, or the one a command printed
+fn active_table(app: &mut App) -> Option<&mut ptable::ProcessTable> {
+    match app.views.current {
+        View::Processes => app.views.processes.as_mut(),
+        View::Output => app.table.as_mut(),
+        _ => None,
+    }
+}
+
+fn scroll_table(app: &mut App, scroll: impl FnOnce(&mut ptable::ProcessTable)) {
+    if let Some(table) = active_table(app) {
+        scroll(table);
+    }
+}
+
+fn sort_table(app: &mut App, sort: ptable::SortColumn, descending: bool) {
+    if let Some(table) = active_table(app) {
+        table.sort_by(sort, descending);
+    }
+}
+
+/// SIGTERM to the selected row's process, reported in the alert log since the table stays on screen
+fn kill_selected(app: &mut App) {
+    let Some(row) = active_table(app).and_then(|t| t.selected_row()) else {
+        return;
+    };
+    let (pid, name) = (row.pid as i32, row.name.clone());
+    kill_selected_pid(app, pid, name);
+}
+
+fn kill_selected_pid(app: &mut App, pid: i32, name: String) {
+    if app.cli.readonly {
+        app.alerts.note("kill: not allowed in read-only mode");
+        return;
+    }
+    match kill(Pid::from_raw(pid), Signal::SIGTERM) {
+        Ok(_) => app.alerts.note(&format!("kill: sent SIGTERM to {} ({})", pid, name)),
+        Err(e) => app.alerts.note(&format!("kill: {} ({}): {}", pid, name, e)),
+    }
+}
+/// Commands that signal, start or stop processes or services, refused under `--readonly`
+fn modifies_system(parts: &[String]) -> bool {
+    match parts[0].as_str() {
+        "kill" | "ignite" | "killjob" | "service" => true,
+        "pstree" => parts.len() > 1,
+        "docker" => parts.len() > 1,
+        "guard" => !matches!(parts.get(1).map(|s| s.as_str()), None | Some("list" | "log" | "remove")),
+        "watchdog" => parts.iter().any(|p| p == "--restart"),
+        _ => false,
+    }
+}
+
+/// Whether running `parts` again every few seconds would do more than show something: what
+/// `modifies_system` covers, and commands that write files or settings or connect somewhere
+fn has_side_effects(parts: &[String]) -> bool {
+    modifies_system(parts) || match parts[0].as_str() {
+        "serve" | "connect" | "disconnect" | "record" | "export" | "report" | "dump" | "edit" | "macro" | "alias" | "unalias" | "copy" | "pick" | "clear" | "unclear" | "watch" => true,
+        // Listing what is set up is fine, setting something up is not
+        "mqtt" | "theme" | "config" | "rules" | "snapshot" | "alert" | "guard" | "watchdog" | "notify-on-spawn" | "portwatch" => !matches!(parts.get(1).map(|s| s.as_str()), None | Some("list" | "log" | "check")),
+        _ => false,
+    }
+}
+
+/// Runs a command line typed at the prompt, replacing whatever the output pane showed. Commands
+/// joined by `;` run one after the other and those after `&&` only if the one before did not fail;
+/// the text they print is kept one after the other too.
+fn run_command<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, sys: &mut System, line: &str) -> io::Result<()> {
+    app.chained = None;
+    let recalled = match recall::expand(&app.messages, line) {
+        Ok(recalled) => recalled,
+        Err(e) => {
+            (app.table, app.records, app.tree, app.live) = (None, None, None, None);
+            app.output.clear();
+            fail(app, e);
+            return Ok(());
+        },
+    };
+    // Put in the history as what it stands for, the same as a line typed out in full
+    if recalled != line {
+        recall::push(&mut app.messages, &recalled, app.config.history_size);
+        if let Err(e) = recall::save(&app.messages) {
+            app.alerts.note(&format!("history: writing {} failed: {}", recall::path().display(), e));
+        }
+    }
+    let mut steps = expand_aliases(&app.config, chain::split(&recalled));
+    match steps.len() {
+        0 => Ok(()),
+        1 => run_step(terminal, app, sys, &steps.remove(0).line),
+        _ => run_steps(terminal, app, sys, steps, false),
+    }
+}
+
+/// Expands the alias each step starts with, splitting the expansion again since it may hold `;` or
+/// `&&` of its own
+fn expand_aliases(config: &config::Config, steps: Vec<chain::Step>) -> Vec<chain::Step> {
+    let mut expanded: Vec<chain::Step> = vec![];
+    for step in steps {
+        let start = expanded.len();
+        expanded.extend(chain::split(&config.expand_alias(&step.line)));
+        // Joined to the step before the way the alias was
+        if let Some(first) = expanded.get_mut(start) {
+            first.after_success = step.after_success;
+        }
+    }
+    expanded
+}
+
+/// Runs `steps` in order, after one that already ran when `continued`. An `ignite` still running
+/// holds back the ones after it until it exits, so they can depend on its exit status.
+fn run_steps<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, sys: &mut System, steps: Vec<chain::Step>, continued: bool) -> io::Result<()> {
+    let plain = |app: &App| app.table.is_none() && app.records.is_none() && app.tree.is_none() && app.live.is_none();
+    let mut earlier: Vec<String> = vec![];
+    let mut steps = steps.into_iter().peekable();
+    let mut first = !continued;
+    while let Some(step) = steps.next() {
+        // Skipped like a shell does, staying failed for the `&&` after it; a `;` runs again
+        if step.after_success && app.failed {
+            continue;
+        }
+        if !first && plain(app) {
+            earlier.append(&mut app.output);
+        }
+        first = false;
+        run_step(terminal, app, sys, &step.line)?;
+        if let (Some(Live::Ignite(id)), Some(_)) = (&app.live, steps.peek()) {
+            app.chained = Some((*id, steps.collect()));
+            break;
+        }
+    }
+    if plain(app) {
+        app.output.splice(0..0, earlier);
+    }
+    Ok(())
+}
+
+/// Runs one command of a line
+fn run_step<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, sys: &mut System, line: &str) -> io::Result<()> {
+    app.failed = false;
+    // Clearing what is still updating takes a y first
+    if app.tab == 0 && line.trim() == "clear" && app.live.is_some() {
+        app.popup = Some(popup::Popup {
+            title: "clear".to_string(),
+            body: popup::Body::Text(vec!["The output is still updating. Stop it and clear it? (y/n)".to_string()]),
+        });
+        app.confirm = Some("clear -f".to_string());
+        return Ok(());
+    }
+    // Kept for `copy`
+    let previous = std::mem::take(&mut app.output);
+    let previous_table = app.table.take();
+    let previous_records = app.records.take();
+    let previous_tree = app.tree.take();
+    let previous_bars = app.bars.take();
+    app.live = None;
+    app.search = None;
+    app.scroll = None;
+    let first = line.split_whitespace().next();
+    let local = matches!(first, Some("connect" | "disconnect" | "hosts" | "help" | "clear" | "unclear" | "edit" | "macro" | "watch" | "alias" | "unalias" | "history"));
+    if let Some(host) = app.tab.checked_sub(1).and_then(|i| app.hosts.get_mut(i)) {
+        if !local {
+            // Checked here too, not only by the agent, in case it predates `--readonly`
+            let command = chain::unquoted(line, '|').map_or(line, |at| &line[..at]);
+            let parts: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+            if app.cli.readonly && !parts.is_empty() && modifies_system(&parts) {
+                host.output = vec![format!("{}: not allowed in read-only mode", parts[0])];
+                app.failed = true;
+            } else {
+                host.run(line);
+            }
+            return Ok(());
+        }
+    }
+    // The rest shows on this host's tab; `disconnect` still needs the tab it was typed on
+    if first != Some("disconnect") {
+        app.tab = 0;
+    }
+    // `watch` keeps the pipe as part of the command it re-runs, `alias` as part of the expansion
+    let (command, stages) = match chain::unquoted(line, '|').map(|at| line.split_at(at)) {
+        Some((command, rest)) if !matches!(first, Some("watch" | "alias")) => (command, rest[1..].split('|').map(pipe::Stage::parse).collect()),
+        _ => (line, Ok(vec![])),
+    };
+    app.pipe = match stages {
+        Ok(stages) => stages,
+        Err(e) => {
+            fail(app, e);
+            return Ok(());
+        },
+    };
+    let parts: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+    // Left by a line such as `| head`
+    if parts.is_empty() {
+        fail(app, "usage: <command> [args] [| filter]; help lists the commands".to_string());
+        return Ok(());
+    }
+    if app.cli.readonly && modifies_system(&parts) {
+        fail(app, format!("{}: not allowed in read-only mode", parts[0]));
+        return Ok(());
+    }
+    match parts[0].as_str() {
+        "uname" => {
+            app.output.push(format!("{}", sys.kernel_version().unwrap()))
+        },
+        "release" => {
+            app.output.push(format!("{}", sys.os_version().unwrap()))
+        },
+        "hostname" => {
+            app.output.push(format!("{}", sys.host_name().unwrap()))
+        },
+        "sysinfo" => {
+            show(app, get_system_information(sys));
+        },
+        "sensors" => {
+            show(app, get_components_information(sys));
+        },
+        "df" => {
+            match parse_df_options(&parts[1..]) {
+                Ok(options) => {
+                    show(app, get_disks_information(sys, &options, &app.history));
+                    if !options.inodes {
+                        app.bars = app.snapshot.as_ref().map(|snap| Bars::Filesystems(gauges::filesystems(snap)));
+                    }
+                },
+                Err(e) => fail(app, e),
+            }
+        },
+        "lsblk" => {
+            match storage::get_block_devices() {
+                Ok(records) => show(app, records),
+                Err(e) => fail(app, e),
+            }
+        },
+        "thermals" => {
+            show_live(app, thermals::get_thermals(&app.history));
+            app.live = Some(Live::Thermals);
+        },
+        "graph" => {
+            match graph::Graph::parse(&parts[1..]) {
+                Ok(graph) => {
+                    app.output = graph::lines(&graph, &app.history, GRAPH_WIDTH);
+                    app.live = Some(Live::Graph(graph));
+                },
+                Err(e) => fail(app, e),
+            }
+        },
+        "hddtemp" => match parts.get(1).map(|s| s.as_str()) {
+            None => app.output = get_hddtemp(sys, String::new()),
+            Some(flag @ ("-max" | "-crit")) if parts.len() == 2 => app.output = get_hddtemp(sys, flag[1..].to_string()),
+            _ => fail(app, "usage: hddtemp [-max|-crit]".to_string()),
+        },
+        "lscpu" => {
+            show(app, get_cpu_information(sys));
+        },
+        "gputemp" => match parts.get(1).map(|s| s.as_str()) {
+            None => app.output = get_gputemp(sys, String::new()),
+            Some("-max") if parts.len() == 2 => app.output = get_gputemp(sys, "max".to_string()),
+            _ => fail(app, "usage: gputemp [-max]".to_string()),
+        },
+        "docker" => {
+            match (parts.get(1).map(|s| s.as_str()), parts.get(2)) {
+                (None, _) => match docker::get_containers() {
+                    Ok(records) if records.rows.is_empty() => app.output.push("No running containers".to_string()),
+                    Ok(records) => show(app, records),
+                    Err(e) => fail(app, e),
+                },
+                (Some(action @ ("stop" | "kill")), Some(target)) => app.output.push(docker::signal_container(action, target)),
+                _ => fail(app, "usage: docker [stop|kill <container>]".to_string()),
+            }
+        },
+        "pods" => {
+            #[cfg(feature = "kubernetes")]
+            {
+                match pods::get_pods(parts.get(1).map(|s| s.as_str())) {
+                    Ok((records, _)) if records.rows.is_empty() => app.output.push("No pods".to_string()),
+                    Ok((records, usage)) => {
+                        show(app, records);
+                        if !usage {
+                            toast(app, "pods: no usage; metrics-server (metrics.k8s.io) is not reachable");
+                        }
+                    },
+                    Err(e) => fail(app, e),
+                }
+            }
+            #[cfg(not(feature = "kubernetes"))]
+            app.output.push("pods: built without Kubernetes support; rebuild with --features kubernetes".to_string());
+        },
+        "copy" => {
+            let lines = match previous_table {
+                Some(table) => table.lines(),
+                None => previous,
+            };
+            let text = match parts.get(1).map(|n| n.parse::<usize>()) {
+                None => Some(lines.join("\n")),
+                Some(Ok(n)) => lines.get(n.wrapping_sub(1)).cloned(),
+                Some(Err(_)) => None,
+            };
+            match text {
+                Some(text) if !text.is_empty() => match app.clipboard.write(&text) {
+                    Ok(_) => app.output.push(format!("Copied {} lines to the clipboard", text.lines().count())),
+                    Err(e) => fail(app, format!("Error writing clipboard: {}", e)),
+                },
+                Some(_) => fail(app, "copy: no output to copy".to_string()),
+                None => fail(app, format!("usage: copy [line number 1-{}]", lines.len())),
+            }
+        },
+        "gpu" => {
+            let records = gpu::get_gpu_information();
+            if records.rows.is_empty() {
+                app.output.push("No GPU with readable metrics found".to_string());
+            } else {
+                show(app, records);
+            }
+        },
+        "kill" => {
+            if parts.len() == 1 {
+                match app.selected.take() {
+                    Some(pid) => kill_by_pid(app, pid),
+                    None => app.output.push("kill: no PID given and nothing selected".to_string()),
+                }
+            }
+            if parts.len() == 2 {
+                if let Some(port) = parts[1].strip_prefix(':') {
+                    kill_by_port(app, port);
+                }
+                else if parts[1].parse::<i32>().is_ok() {
+                    let pid = parts[1].parse::<i32>().unwrap();
+                    kill_by_pid(app, pid);
+                }
+                else {
+                    kill_by_name(app, parts[1].clone());
+                }
+            }
+        },
+        // What a job prints keeps coming after the pipe ran, so it is never filtered
+        "ignite" if !app.pipe.is_empty() => fail(app, "ignite: its output cannot be piped; quote the pipe to run it in a shell: ignite sh -c 'cmd | grep text'".to_string()),
+        "ignite" => {
+            // Split again by `jobs::words`, which knows about quotes
+            match jobs::Launch::parse(command.trim_start().strip_prefix("ignite").unwrap_or_default()) {
+                Ok(launch) => match app.jobs.spawn(&launch) {
+                    Ok(job) if launch.background => match &job.log {
+                        Some(log) => app.output.push(format!("[{}] {}  {}  (in the background, printing to {}; jobs {} shows it)", job.id, job.pid, job.command, log.display(), job.id)),
+                        None => app.output.push(format!("[{}] {}  {}  (in the background; its output is discarded)", job.id, job.pid, job.command)),
+                    },
+                    Ok(job) => {
+                        app.output.push(format!("[{}] {}  {}", job.id, job.pid, job.command));
+                        app.live = Some(Live::Ignite(job.id));
+                    },
+                    Err(e) => fail(app, format!("Error starting {}: {}", launch.program, e)),
+                },
+                Err(e) => fail(app, e),
+            }
+        },
+        "history" => show(app, recall::table(&app.messages)),
+        "jobs" => match parts.get(1).map(|id| id.parse::<usize>().ok().filter(|id| app.jobs.get(*id).is_some())) {
+            None if app.jobs.list.is_empty() => app.output.push("No jobs; start one with ignite".to_string()),
+            None => show(app, app.jobs.table()),
+            Some(Some(_)) if !app.pipe.is_empty() => fail(app, "jobs: the output of one job cannot be piped; pipe jobs or open the log file".to_string()),
+            Some(Some(id)) => {
+                show_job(app, id);
+                if app.jobs.get(id).map(|job| !job.done()).unwrap_or(false) {
+                    app.live = Some(Live::Ignite(id));
+                }
+            },
+            Some(None) => fail(app, format!("jobs: no job [{}]", parts[1])),
+        },
+        "killjob" => match parts.get(1).and_then(|id| id.parse::<usize>().ok()) {
+            Some(id) => match app.jobs.kill(id) {
+                Ok(job) => app.output.push(format!("Sent SIGTERM to [{}] {}  {}", job.id, job.pid, job.command)),
+                Err(e) => fail(app, format!("killjob: {}", e)),
+            },
+            None => fail(app, "usage: killjob <id>".to_string()),
+        },
+        "watch" => {
+            watch_command(terminal, app, sys, &parts)?;
+        },
+        "snapshot" => {
+            snapshot_command(app, &parts);
+        },
+        "record" => {
+            record_command(app, &parts);
+        },
+        "theme" => {
+            theme_command(app, &parts);
+        },
+        "keys" => {
+            app.output = app.keymap.list();
+        },
+        "config" => {
+            match parts.get(1).map(|s| s.as_str()) {
+                Some("reload") => reload_config(app),
+                None => {
+                    app.output.push(format!("Settings from {}:", config::path().display()));
+                    app.output.push(format!("refresh every {} ms, ptable sorted by {}", app.config.refresh_ms, app.config.sort));
+                    app.output.push(format!("{} command lines of history kept in {}", app.config.history_size, recall::path().display()));
+                    for line in &app.config.startup {
+                        app.output.push(format!("startup: {}", line));
+                    }
+                    for (name, expansion) in &app.config.aliases {
+                        app.output.push(format!("alias {} = {}", name, expansion));
+                    }
+                },
+                _ => fail(app, "usage: config [reload]".to_string()),
+            }
+        },
+        "alias" => alias_command(app, command),
+        "unalias" => match parts.get(1) {
+            Some(name) => match app.config.set_alias(name, None) {
+                Ok(()) => app.output.push(format!("Removed alias {}", name)),
+                Err(e) => fail(app, format!("unalias: {}", e)),
+            },
+            None => fail(app, "usage: unalias <name>".to_string()),
+        },
+        "mqtt" => {
+            mqtt_command(app, sys, &parts);
+        },
+        "serve" => {
+            serve_command(app, &parts);
+        },
+        "connect" => {
+            match parts.get(1) {
+                Some(target) if app.hosts.iter().any(|h| &h.target == target) => app.output.push(format!("connect: already connected to {}", target)),
+                Some(target) => {
+                    let program = parts.get(2).map(|p| p.as_str()).unwrap_or("proclynx");
+                    match remote::Remote::connect(target, program, app.cli.readonly) {
+                        Ok(host) => {
+                            app.hosts.push(host);
+                            app.tab = app.hosts.len();
+                        },
+                        Err(e) => fail(app, format!("Error starting ssh: {}", e)),
+                    }
+                },
+                None => fail(app, "usage: connect <user@host> [remote proclynx path]".to_string()),
+            }
+        },
+        "disconnect" => {
+            let index = match parts.get(1) {
+                Some(target) => app.hosts.iter().position(|h| &h.target == target),
+                None => app.tab.checked_sub(1).filter(|i| *i < app.hosts.len()),
+            };
+            match index {
+                Some(i) => {
+                    let host = app.hosts.remove(i);
+                    app.output.push(format!("Disconnected from {}", host.target));
+                    app.tab = 0;
+                },
+                None if parts.len() > 1 => fail(app, format!("disconnect: not connected to {}", parts[1])),
+                None => fail(app, "usage: disconnect [user@host], or switch to the host's tab first".to_string()),
+            }
+        },
+        "hosts" => {
+            if app.hosts.is_empty() {
+                app.output.push("No hosts connected".to_string());
+            }
+            for (i, host) in app.hosts.iter().enumerate() {
+                app.output.push(format!("[{}] {}", i + 1, host.target));
+            }
+        },
+        "guard" => {
+            guard_command(app, &parts);
+        },
+        "watchdog" => {
+            watchdog_command(app, &parts);
+        },
+        "notify-on-spawn" => {
+            spawn_command(app, &parts);
+        },
+        "ptable" => {
+            match ptable::parse_options(&parts[1..], app.config.sort_column()) {
+                Ok((sort, descending)) => {
+                    let mut table = ptable::ProcessTable::collect(&app.io, &app.sched, sort, descending);
+                    table.probe(app.config.colors_by_container(), false);
+                    app.table = Some(table);
+                },
+                Err(e) => fail(app, e),
+            }
+        },
+        "pstree" => {
+            pstree_command(app, sys, &parts, previous_tree);
+        },
+        "clear" => {
+            clear(app, Cleared { output: previous, records: previous_records, bars: previous_bars, table: previous_table, tree: previous_tree });
+        },
+        "unclear" => match app.cleared.pop_back() {
+            Some(cleared) => {
+                app.output = cleared.output;
+                app.records = cleared.records;
+                app.bars = cleared.bars;
+                app.table = cleared.table;
+                app.tree = cleared.tree;
+            },
+            None => app.output.push("unclear: nothing was cleared".to_string()),
+        },
+        "help" => match parts.get(1) {
+            Some(name) => match help::command(name) {
+                Ok(lines) => app.output = lines,
+                Err(e) => fail(app, e),
+            },
+            None => app.output = help::lines(&app.keymap.list()),
+        },
+        "find" => match parts.get(1).map(|p| p.parse::<i32>()) {
+            Some(Ok(pid)) if parts.len() == 2 => find_process(app, pid),
+            Some(Err(_)) => fail(app, format!("find: invalid PID '{}'", parts[1])),
+            _ => fail(app, "usage: find <pid>".to_string()),
+        },
+        "dump" => {
+            match (parts.get(1).and_then(|p| p.parse::<i32>().ok()), parts.get(2)) {
+                (Some(pid), Some(path)) => {
+                    let metrics = dump::Metrics { snapshot: app.snapshot.as_ref(), io: &app.io, paging: &app.paging, sched: &app.sched };
+                    match dump::dump(pid, path, &metrics) {
+                        Ok(size) => app.output.push(format!("Wrote {} bytes about PID {} to {}", size, pid, path)),
+                        Err(e) => fail(app, format!("Error dumping process: {}", e)),
+                    }
+                },
+                _ => fail(app, "usage: dump <pid> <path>".to_string()),
+            }
+        },
+        "edit" => {
+            edit_command(terminal, app, &parts)?;
+        },
+        "export" => {
+            export_command(app, sys, &parts);
+        },
+        "report" => {
+            report_command(app, sys, &parts);
+        },
+        "pick" => {
+            pick(app, parts.get(1));
+        },
+        "network" =>{
+            if parts.get(1).map(|a| a.as_str()) == Some("live") {
+                show_live(app, network::get_throughput(&app.net));
+                app.live = Some(Live::Network);
+            } else {
+                app.output = network::get_network_information(&app.net);
+            }
+        },
+        "memory" => {
+            let usages = gauges::memory();
+            show(app, memutil(&usages));
+            app.bars = Some(Bars::Memory(usages));
+        },
+        "desc" =>{
+            let mut table = ptable::ProcessTable::collect(&app.io, &app.sched, ptable::SortColumn::Pid, true);
+            table.probe(app.config.colors_by_container(), false);
+            app.table = Some(table);
+        },
+        "vmstat" => {
+            let count = parts.get(1).and_then(|c| c.parse::<usize>().ok()).unwrap_or(5);
+            app.output.push(vmstat::Vmstat::header());
+            app.live = Some(Live::Vmstat(vmstat::Vmstat::start(count)));
+        },
+        "pressure" => {
+            show(app, pressure::get_pressure_information());
+        },
+        "paging" => {
+            app.output = paging::get_paging_information(&app.paging, &app.history);
+            app.live = Some(Live::Paging);
+        },
+        "schedlat" => {
+            match parts.get(1).and_then(|p| p.parse::<i32>().ok()) {
+                Some(pid) => {
+                    app.output = sched::get_latency_information(&app.sched, pid);
+                    app.live = Some(Live::Schedlat(pid));
+                },
+                None => fail(app, "usage: schedlat <pid>".to_string()),
+            }
+        },
+        "journal" => {
+            match journal::Journal::follow(parts.get(1).map(|u| u.as_str())) {
+                Ok(journal) => app.live = Some(Live::Journal(journal)),
+                Err(e) => fail(app, format!("Error starting journalctl: {}", e)),
+            }
+        },
+        "lsmod" => {
+            match hardware::get_modules(parts.get(1).map(|f| f.as_str())) {
+                Ok(records) => show(app, records),
+                Err(e) => fail(app, e),
+            }
+        },
+        "lspci" => {
+            show(app, hardware::get_pci_devices());
+        },
+        "lsusb" => {
+            let records = hardware::get_usb_devices();
+            if records.rows.is_empty() {
+                app.output.push("No USB devices found".to_string());
+            } else {
+                show(app, records);
+            }
+        },
+        "who" => {
+            let records = who::get_logged_in_users();
+            if records.rows.is_empty() {
+                app.output.push("No users logged in".to_string());
+            } else {
+                show(app, records);
+            }
+        },
+        "ports" => {
+            show(app, sockets::get_listening_ports());
+        },
+        "portwatch" => {
+            portwatch_command(app, &parts);
+        },
+        "route" => {
+            let records = network::get_routes();
+            if records.rows.is_empty() {
+                app.output.push("No routes".to_string());
+            } else {
+                if !records.rows[0][0].starts_with("default") {
+                    toast(app, "route: no default gateway");
+                }
+                show(app, records);
+            }
+        },
+        "trace" => {
+            match parts.get(1).map(|p| p.parse::<i32>()) {
+                Some(Ok(pid)) => match trace::Trace::start(pid) {
+                    Ok(mut trace) => {
+                        show(app, trace.update());
+                        app.live = Some(Live::Trace(trace));
+                    },
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => fail(app, "trace: strace is not installed".to_string()),
+                    Err(e) => fail(app, format!("Error running strace: {}", e)),
+                },
+                _ => fail(app, "usage: trace <pid>".to_string()),
+            }
+        },
+        "execsnoop" | "opensnoop" => {
+            #[cfg(feature = "ebpf")]
+            {
+                let kind = if parts[0] == "execsnoop" { snoop::Kind::Exec } else { snoop::Kind::Open };
+                let snoop = snoop::Snoop::start(kind);
+                app.output = snoop.drain();
+                app.live = Some(Live::Snoop(snoop));
+            }
+            #[cfg(not(feature = "ebpf"))]
+            app.output.push(format!("{}: built without eBPF support; rebuild with --features ebpf", parts[0]));
+        },
+        "perfstat" => {
+            let seconds = match parts.get(2).map(|s| s.parse::<u64>()) {
+                Some(Ok(seconds)) if seconds > 0 => Some(seconds),
+                Some(_) => None,
+                None => Some(perf::DEFAULT_SECONDS),
+            };
+            match (parts.get(1).map(|p| p.parse::<i32>()), seconds) {
+                (Some(Ok(pid)), Some(seconds)) => match perf::PerfStat::start(pid, seconds) {
+                    Ok(mut perf) => {
+                        app.output = perf.update();
+                        app.live = Some(Live::PerfStat(perf));
+                    },
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => fail(app, "perfstat: perf is not installed".to_string()),
+                    Err(e) => fail(app, format!("Error running perf: {}", e)),
+                },
+                _ => fail(app, "usage: perfstat <pid> [seconds]".to_string()),
+            }
+        },
+        "ping" => {
+            match parts.get(1) {
+                Some(host) => {
+                    let mut ping = ping::Ping::start(host);
+                    app.output = ping.update(PING_WIDTH);
+                    app.live = Some(Live::Ping(ping));
+                },
+                None => fail(app, "usage: ping <host>".to_string()),
+            }
+        },
+        "dig" => {
+            match parts.get(1) {
+                Some(name) => match dns::dig(name) {
+                    Ok(records) => show(app, records),
+                    Err(e) => fail(app, e),
+                },
+                None => fail(app, "usage: dig <name>".to_string()),
+            }
+        },
+        "arp" => {
+            let records = network::get_neighbours();
+            if records.rows.is_empty() {
+                app.output.push("No neighbour entries".to_string());
+            } else {
+                show(app, records);
+            }
+        },
+        "wifi" => {
+            app.output = network::get_wifi_information();
+        },
+        "nethogs" => {
+            match nethogs::Nethogs::start() {
+                Ok(nethogs) => {
+                    app.output.push("Measuring...".to_string());
+                    app.live = Some(Live::Nethogs(nethogs));
+                },
+                Err(e) => fail(app, format!("Error running ss: {}", e)),
+            }
+        },
+        "connections" | "ss" => {
+            show(app, sockets::get_connections(parts.get(1).map(|f| f.as_str())));
+        },
+        "services" => {
+            match services::get_services() {
+                Ok(records) => show(app, records),
+                Err(e) => fail(app, e),
+            }
+        },
+        "service" => {
+            if parts.len() == 3 {
+                app.output = services::control_service(&parts[1], &parts[2]);
+            } else {
+                fail(app, "usage: service start|stop|restart <unit>".to_string());
+            }
+        },
+        "macro" => {
+            macro_command(app, &parts);
+        },
+        "alert" => {
+            alert_command(app, &parts);
+        },
+        "alerts" => {
+            let records = app.alerts.get_history();
+            if records.rows.is_empty() {
+                app.output.push("No alerts have fired".to_string());
+            } else {
+                show(app, records);
+            }
+        },
+        "rules" => {
+            rules_command(app, sys, &parts);
+        },
+        _ => fail(app, "command not found".to_string()),
+    }
+    apply_pipe(app);
+    Ok(())
+}
+
+/// Reports why a command did not do what was asked; `&&` stops a chain after it
+fn fail(app: &mut App, message: String) {
+    app.output.push(message);
+    app.failed = true;
+}
+
+/// Shows what job `id` printed so far under its command line, ending the live view with its
+/// exit status once it is done
+fn show_job(app: &mut App, id: usize) {
+    let Some(job) = app.jobs.get(id) else {
+        return;
+    };
+    app.output = vec![format!("[{}] {}  {}", job.id, job.pid, job.command)];
+    app.output.extend(job.printed());
+    if job.done() {
+        app.output.push(job.describe());
+        app.live = None;
+    }
+}
+
+/// Shows a command's records as a table, keeping their text for `copy`, `watch` and `proclynxctl`
+fn show(app: &mut App, records: Records) {
+    app.output = records.lines();
+    app.records = Some(records);
+}
+
+/// Shows the records of a live view, or the reason it has none in their place
+fn show_live(app: &mut App, records: Result<Records, String>) {
+    match records {
+        Ok(records) => show(app, records),
+        Err(reason) => {
+            app.records = None;
+            app.output = vec![reason];
+        },
+    }
+}
+
+/// Runs a command for `proclynxctl` and returns its output as text, leaving what the UI shows untouched
+fn run_detached<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, sys: &mut System, line: &str) -> io::Result<Vec<String>> {
+    if line.split_whitespace().next() == Some("edit") {
+        return Ok(vec!["edit needs the terminal; run it in the proclynx window".to_string()]);
+    }
+    let output = std::mem::take(&mut app.output);
+    let live = app.live.take();
+    let table = app.table.take();
+    let records = app.records.take();
+    let bars = app.bars.take();
+    let pipe = std::mem::take(&mut app.pipe);
+    // Run here even when a remote host's tab is open
+    let tab = std::mem::replace(&mut app.tab, 0);
+    run_command(terminal, app, sys, line)?;
+    // A live view started this way only reports its first screen
+    let lines = match app.table.take() {
+        Some(table) => table.lines(),
+        None => std::mem::take(&mut app.output),
+    };
+    app.output = output;
+    app.live = live;
+    app.table = table;
+    app.records = records;
+    app.bars = bars;
+    app.pipe = pipe;
+    // A host disconnected meanwhile may have taken the tab with it
+    app.tab = if app.hosts.is_empty() { 0 } else { tab.min(app.hosts.len() + 1) };
+    Ok(lines)
+}
+
+/// Filters the output lines, or the rows of the process table or records, through the command's pipe stages
+fn apply_pipe(app: &mut App) {
+    if app.pipe.is_empty() {
+        return;
+    }
+    if let Some(table) = &mut app.table {
+        table.rows = pipe::apply(&app.pipe, std::mem::take(&mut table.rows), |row| row.line());
+        table.state.select(if table.rows.is_empty() { None } else { Some(0) });
+    } else if let Some(records) = &mut app.records {
+        records.rows = pipe::apply(&app.pipe, std::mem::take(&mut records.rows), |row| Records::row_line(row));
+        app.output = records.lines();
+    } else {
+        app.output = pipe::apply(&app.pipe, std::mem::take(&mut app.output), |line| line.clone());
+    }
+}
+
+fn watch_command<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, sys: &mut System, parts: &[String]) -> io::Result<()> {
+    let interval = parts.get(1).and_then(|i| alerts::parse_duration(i)).filter(|secs| *secs > 0);
+    let Some(interval) = interval.filter(|_| parts.len() > 2) else {
+        fail(app, "usage: watch <interval> <command> [args]".to_string());
+        return Ok(());
+    };
+    // Every command of the line, as it will run
+    let line = parts[2..].join(" ");
+    let refused = expand_aliases(&app.config, chain::split(&line)).into_iter().find_map(|step| {
+        let command = chain::unquoted(&step.line, '|').map_or(step.line.as_str(), |at| &step.line[..at]);
+        let parts: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+        (!parts.is_empty() && has_side_effects(&parts)).then(|| parts[0].clone())
+    });
+    if let Some(refused) = refused {
+        fail(app, format!("watch: cannot watch {}; it would change something every {}s", refused, interval));
+        return Ok(());
+    }
+    let watch = Watch { command: line, interval: Duration::from_secs(interval), last: Instant::now() };
+    run_watched(terminal, app, sys, watch)
+}
+
+/// Runs the watched command and keeps watching, unless it started a live view of its own
+fn run_watched<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, sys: &mut System, mut watch: Watch) -> io::Result<()> {
+    run_command(terminal, app, sys, &watch.command)?;
+    watch.last = Instant::now();
+    if app.live.is_some() {
+        return Ok(());
+    }
+    if app.table.is_none() && app.records.is_none() {
+        let header = format!("Every {}s: {}    {}", watch.interval.as_secs(), watch.command, chrono::Local::now().format("%H:%M:%S"));
+        app.output.insert(0, header);
+    }
+    app.live = Some(Live::Watch(watch));
+    Ok(())
+}
+
+fn refresh_watch<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, sys: &mut System) -> io::Result<()> {
+    let due = matches!(&app.live, Some(Live::Watch(watch)) if watch.last.elapsed() >= watch.interval);
+    if !due {
+        return Ok(());
+    }
+    if let Some(Live::Watch(watch)) = app.live.take() {
+        // Keep the selected row of a watched table across refreshes
+        let selected = app.table.as_ref().and_then(|t| t.state.selected());
+        run_watched(terminal, app, sys, watch)?;
+        if let (Some(table), Some(row)) = (&mut app.table, selected) {
+            table.scroll(row as isize);
+        }
+    }
+    Ok(())
+}
+
+/// `alias` lists the aliases; `alias name=command line` defines one and saves it in config.toml
+fn alias_command(app: &mut App, command: &str) {
+    let definition = command.trim_start().trim_start_matches("alias").trim();
+    if definition.is_empty() {
+        if app.config.aliases.is_empty() {
+            app.output.push("No aliases; define one with alias <name>=<command line>".to_string());
+        }
+        for (name, expansion) in &app.config.aliases {
+            app.output.push(format!("alias {}={}", name, expansion));
+        }
+        return;
+    }
+    let Some((name, expansion)) = definition.split_once('=').map(|(n, e)| (n.trim(), e.trim())).filter(|(_, e)| !e.is_empty()) else {
+        fail(app, "usage: alias [<name>=<command line>]".to_string());
+        return;
+    };
+    if help::find(name).is_some() {
+        app.output.push(format!("alias: {} is already a command", name));
+        return;
+    }
+    match app.config.set_alias(name, Some(expansion)) {
+        Ok(()) => app.output.push(format!("alias {}={} (saved in {})", name, expansion, config::path().display())),
+        Err(e) => fail(app, format!("alias: {}", e)),
+    }
+}
+
+/// Suspends the TUI, runs $VISUAL/$EDITOR on the file and, if the result is valid, applies it
+fn edit_command<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, parts: &[String]) -> io::Result<()> {
+    let path = match parts.get(1).map(|s| s.as_str()) {
+        Some("config") => config::path(),
+        Some("rules") => rules::path(),
+        Some("theme") => theme::path(),
+        _ => {
+            fail(app, "usage: edit config|rules|theme".to_string());
+            return Ok(());
+        },
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    let status = std::process::Command::new(program).args(words).arg(&path).status();
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+
+    match status {
+        Ok(status) if !status.success() => {
+            app.output.push(format!("{} exited with {}; nothing applied", program, status));
+            return Ok(());
+        },
+        Err(e) => {
+            app.output.push(format!("Error starting {}: {}", program, e));
+            return Ok(());
+        },
+        Ok(_) => {},
+    }
+    match parts[1].as_str() {
+        "config" => reload_config(app),
+        "rules" => match rules::load(&path) {
+            Ok(loaded) => {
+                app.output.push(format!("Loaded {} rules from {}", loaded.len(), path.display()));
+                app.rules = loaded;
+            },
+            Err(e) => fail(app, format!("Error in {}: {}; keeping the previous rules", path.display(), e)),
+        },
+        _ => match theme::Theme::load(&app.config.theme) {
+            Ok(theme) => {
+                app.theme = theme;
+                app.output.push(format!("Applied {}", path.display()));
+            },
+            Err(e) => fail(app, format!("Error in {}: {}; keeping the previous colours", path.display(), e)),
+        },
+    }
+    Ok(())
+}
+
+/// Re-reads config.toml, restarting the MQTT publisher when its settings changed
+fn reload_config(app: &mut App) {
+    let mut config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            app.output.push(format!("Error in {}: {}; keeping the previous settings", config::path().display(), e));
+            return;
+        },
+    };
+    if config.mqtt != app.config.mqtt {
+        if let Some(publisher) = app.mqtt.take() {
+            publisher.stop();
+        }
+        if let Some(mqtt) = &config.mqtt {
+            match mqtt::Publisher::start(mqtt, &System::new().host_name().unwrap_or_default()) {
+                Ok(publisher) => app.mqtt = Some(publisher),
+                Err(e) => fail(app, format!("mqtt: {}", e)),
+            }
+        }
+    }
+    app.cli.apply(&mut config);
+    // Same as at agent startup: the desktop is not the connected user's
+    if app.headless {
+        config.notify.desktop = false;
+    }
+    app.config = config;
+    apply_ui_settings(app);
+    app.output.push(format!("Applied {}", config::path().display()));
+}
+
+/// Switches to the configured theme and keys, keeping the current theme if theme.toml is broken
+fn apply_ui_settings(app: &mut App) {
+    match theme::Theme::load(&app.config.theme) {
+        Ok(theme) => app.theme = theme,
+        Err(e) => fail(app, format!("Error in {}: {}; keeping the {} theme", theme::path().display(), e, app.theme.name)),
+    }
+    // Config::load already rejected unknown actions and keys
+    app.keymap = keys::Keymap::new(&app.config.keys).unwrap_or_default();
+}
+
+/// `theme [name]`: lists the built-in themes or switches to one until the next reload
+fn theme_command(app: &mut App, parts: &[String]) {
+    match parts.get(1) {
+        None => {
+            for name in theme::NAMES {
+                let current = if name == app.theme.name { " (current)" } else { "" };
+                app.output.push(format!("{}{}", name, current));
+            }
+        },
+        Some(name) if theme::Theme::builtin(name).is_some() => match theme::Theme::load(name) {
+            Ok(theme) => {
+                app.config.theme = name.clone();
+                app.theme = theme;
+                app.output.push(format!("Theme set to {}", name));
+            },
+            Err(e) => fail(app, format!("Error in {}: {}", theme::path().display(), e)),
+        },
+        Some(name) => fail(app, format!("theme: unknown theme '{}'; try {}", name, theme::NAMES.join(", "))),
+    }
+}
+
+/// Returns the next key to handle: queued macro keys first, then terminal input (recorded if a macro is being recorded);
+/// mouse events are handled on the way
+fn next_key(app: &mut App, timeout: Duration) -> io::Result<Option<KeyEvent>> {
+    if let Some(key) = app.macros.pending.pop_front() {
+        return Ok(Some(key));
+    }
+    if !event::poll(timeout)? {
+        return Ok(None);
+    }
+    match event::read()? {
+        Event::Key(key) => {
+            app.macros.record(key);
+            Ok(Some(key))
+        },
+        Event::Mouse(mouse) => {
+            handle_mouse(app, mouse);
+            Ok(None)
+        },
+        _ => Ok(None),
+    }
+}
+
+/// `pstree` shows the tree, re-reading the one on screen with its folds kept; `pstree kill <pid>`
+/// terminates a process and everything under it, then shows the tree again
+fn pstree_command(app: &mut App, sys: &mut System, parts: &[String], previous: Option<pstree::ProcessTree>) {
+    sys.refresh_processes();
+    let mut tree = match previous {
+        Some(mut tree) => {
+            tree.refresh(sys);
+            tree
+        },
+        None => pstree::ProcessTree::collect(sys),
+    };
+    // Only a positive `pid_t` names one process; 0 and negative ones signal process groups
+    let pid = parts.get(2).and_then(|p| p.parse::<i32>().ok()).filter(|pid| *pid > 0).map(|pid| pid as u32);
+    match (parts.get(1).map(|s| s.as_str()), pid) {
+        (None, _) => {},
+        (Some("kill"), Some(pid)) if !tree.contains(pid) => {
+            fail(app, format!("pstree kill: no process {}", pid));
+            return;
+        },
+        (Some("kill"), Some(pid)) => {
+            let pids = tree.subtree(pid);
+            let failed: Vec<String> = pids
+                .iter()
+                .filter_map(|p| kill(Pid::from_raw(*p as i32), Signal::SIGTERM).err().map(|e| format!("{}: {}", p, e)))
+                .collect();
+            if failed.is_empty() {
+                app.alerts.note(&format!("kill: sent SIGTERM to {} ({}) and {} processes under it", tree.name(pid), pid, pids.len() - 1));
+            } else {
+                app.alerts.note(&format!("kill: {} ({}) and the processes under it: {}", tree.name(pid), pid, failed.join(", ")));
+            }
+            sys.refresh_processes();
+            tree.refresh(sys);
+        },
+        _ => {
+            fail(app, "usage: pstree | pstree kill <pid>".to_string());
+            return;
+        },
+    }
+    app.output = tree.lines();
+    // Piped, only the matching lines are left to show
+    if app.pipe.is_empty() {
+        app.tree = Some(tree);
+    }
+}
+
+/// Terminates the selected process of the tree, asking first when there are processes under it
+fn kill_tree_selected(app: &mut App) {
+    let Some(tree) = &app.tree else {
+        return;
+    };
+    let Some(pid) = tree.selected_pid() else {
+        return;
+    };
+    let under = tree.subtree(pid).len().saturating_sub(1);
+    if under == 0 {
+        kill_selected_pid(app, pid as i32, tree.name(pid).to_string());
+        return;
+    }
+    app.popup = Some(popup::Popup {
+        title: "kill".to_string(),
+        body: popup::Body::Text(vec![format!("Kill {} ({}) and the {} processes under it? (y/n)", tree.name(pid), pid, under)]),
+    });
+    app.confirm = Some(format!("pstree kill {}", pid));
+}
+
+/// Keeps what was on the output pane for `unclear`, dropping the oldest past `CLEARED_KEPT`
+fn clear(app: &mut App, cleared: Cleared) {
+    if cleared.output.is_empty() && cleared.records.is_none() && cleared.table.is_none() && cleared.tree.is_none() {
+        return;
+    }
+    if app.cleared.len() == CLEARED_KEPT {
+        app.cleared.pop_front();
+    }
+    app.cleared.push_back(cleared);
+}
+
+/// Lines or rows one notch of the mouse wheel moves
+const WHEEL_LINES: isize = 3;
+
+/// A click selects a table row or sorts by a column header; the wheel moves the table selection,
+/// or scrolls the output when no table is showing
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    if app.popup.is_some() {
+        return;
+    }
+    let delta = match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            if let Some(table) = active_table(app) {
+                table.click(mouse.column, mouse.row);
+            }
+            return;
+        },
+        MouseEventKind::ScrollDown => WHEEL_LINES,
+        MouseEventKind::ScrollUp => -WHEEL_LINES,
+        _ => return,
+    };
+    if let Some(table) = active_table(app) {
+        table.scroll(delta);
+    } else if app.views.current == View::Output {
+        app.scroll = Some(app.output_top.saturating_add_signed(delta));
+    }
+}
+
+fn macro_command(app: &mut App, parts: &[String]) {
+    let result = match (parts.get(1).map(|s| s.as_str()), parts.get(2)) {
+        (Some("record"), Some(name)) => app.macros.start(name).map(|_| format!("Recording macro '{}'; macro stop to finish", name)),
+        (Some("stop"), None) => app.macros.stop().map_err(|e| e.to_string()),
+        (Some("play"), Some(name)) => app.macros.play(name).map(|_| format!("Playing macro '{}'", name)),
+        (Some("list"), None) => {
+            app.output = app.macros.list();
+            return;
+        },
+        _ => Err("usage: macro record <name> | macro stop | macro play <name> | macro list".to_string()),
+    };
+    match result {
+        Ok(message) => app.output.push(message),
+        Err(e) => fail(app, format!("macro: {}", e)),
+    }
+}
+
+/// Takes in the replies of connected hosts and drops those whose session ended
+fn update_hosts(app: &mut App) {
+    for host in &mut app.hosts {
+        host.update();
+    }
+    let before = app.hosts.len();
+    for host in app.hosts.iter().filter(|h| h.closed.is_some()) {
+        app.output.push(format!("Connection to {} closed: {}", host.target, host.closed.as_deref().unwrap_or_default()));
+    }
+    app.hosts.retain(|h| h.closed.is_none());
+    if app.hosts.len() != before {
+        app.tab = 0;
+    }
+}
+
+/// Shows `message` as a toast unless they are turned off
+fn toast(app: &mut App, message: &str) {
+    if app.config.notify.toast_secs > 0 {
+        app.toasts.push(message);
+    }
+}
+
+/// Refreshes the system metrics, evaluates the loaded rules against them and updates the pane title
+fn sample(app: &mut App, sys: &mut System) {
+    app.paging.update();
+    app.net.update();
+    let mut snap = rules::Snapshot::take(sys);
+    snap.swap_in = app.paging.swap_in;
+    snap.swap_out = app.paging.swap_out;
+    snap.major_faults = app.paging.major_faults;
+    snap.net_rx = app.net.rates.values().map(|r| r.0).sum();
+    snap.net_tx = app.net.rates.values().map(|r| r.1).sum();
+    snap.interfaces = app.net.rates.iter().map(|(name, (rx, tx))| rules::InterfaceSample { name: name.clone(), rx: *rx, tx: *tx }).collect();
+    for process in &mut snap.processes {
+        process.major_faults = app.paging.process_faults.get(&process.pid).copied().unwrap_or(0.0);
+    }
+    app.history.push(&snap);
+    snap.disk_full_days = app.history.min_days_until_full();
+    // Signals sent (or refused) for autokill rules that just fired
+    let mut kills: Vec<String> = vec![];
+    for transition in app.alerts.update(&app.rules, &snap) {
+        toast(app, &transition.message);
+        if app.config.notify.desktop && !transition.resolved {
+            notify::desktop("proclynx alert", &transition.message, true);
+        }
+        if let Some(url) = transition.webhook.as_ref().or(app.config.notify.webhook.as_ref()) {
+            app.webhooks.post(url, app.config.notify.webhook_format, &transition);
+        }
+        let Some(pid) = transition.pid.filter(|_| !transition.resolved) else {
+            continue;
+        };
+        let Some(rule) = app.rules.iter().find(|r| r.name == transition.rule && r.action == rules::Action::Autokill) else {
+            continue;
+        };
+        let name = snap.processes.iter().find(|p| p.pid == pid).map(|p| p.name.as_str()).unwrap_or_default();
+        if app.cli.readonly {
+            kills.push(format!("rule '{}': not killing PID {} {}, read-only mode", rule.name, pid, name));
+            continue;
+        }
+        let op = if rule.op == rules::Op::Above { ">" } else { "<" };
+        let reason = format!("{:?} {} {}", rule.metric, op, rule.threshold);
+        kills.extend(app.guards.autokill(&rule.name, pid, name, &reason, snap.time));
+    }
+    app.warnings = app.config.alarms.check(&snap);
+    // Each event with whether it warrants a desktop notification
+    let mut events: Vec<(String, bool)> = app.guards.check(&snap).into_iter().chain(kills).map(|e| (e, true)).collect();
+    events.extend(app.webhooks.errors().into_iter().map(|e| (e, true)));
+    events.extend(app.watchdogs.check(&mut app.jobs).into_iter().map(|e| (e, true)));
+    events.extend(app.spawns.check(&snap));
+    events.extend(app.ports.check().into_iter().map(|e| (e, true)));
+    events.extend(app.mqtt.as_ref().map(|m| m.errors()).unwrap_or_default().into_iter().map(|e| (e, false)));
+    for (event, desktop) in events {
+        if desktop && app.config.notify.desktop {
+            notify::desktop("proclynx", &event, false);
+        }
+        app.alerts.note(&event);
+        toast(app, &event);
+        if app.table.is_none() && app.records.is_none() && app.live.is_none() {
+            app.output.push(event);
+        }
+    }
+    let uptime = sys.uptime();
+    let users = who::count();
+    app.status = format!(
+        "{} | up {}d {:02}:{:02} | load {:.2} | mem {:.0}% | cpu {:.0}% | {} users",
+        sys.host_name().unwrap_or_default(),
+        uptime / 86400,
+        uptime % 86400 / 3600,
+        uptime % 3600 / 60,
+        snap.load,
+        snap.mem,
+        snap.cpu,
+        users
+    );
+    let mut title = format!("proclynx {} | cpu {:.0}% | {} users | {} alerts", sys.host_name().unwrap_or_default(), snap.cpu, users, app.alerts.firing.len());
+    if let Some(host) = app.tab.checked_sub(1).and_then(|i| app.hosts.get(i)) {
+        title.push_str(&format!(" | on {}", host.target));
+    }
+    for host in &mut app.hosts {
+        host.request_metrics();
+    }
+    if title != app.title && !app.headless {
+        set_pane_title(&title);
+        app.title = title;
+    }
+    if let Some(recorder) = &mut app.recorder {
+        if let Err(e) = recorder.write(&snap) {
+            app.alerts.note(&format!("record: stopped after {} samples, writing {} failed: {}", recorder.rows, recorder.path, e));
+            app.recorder = None;
+        }
+    }
+    if let Some(server) = &app.server {
+        server.publish(&snap);
+    }
+    if let Some(publisher) = &mut app.mqtt {
+        publisher.publish(&snap);
+    }
+    app.snapshot = Some(snap);
+    app.io.update();
+    app.sched.update();
+    update_live(app);
+    refresh_view(app, sys);
+    // Watched commands run their own pipe on every refresh
+    if app.live.is_some() && !matches!(app.live, Some(Live::Watch(_))) {
+        apply_pipe(app);
+    }
+}
+
+/// Fills in the dashboard shown, if any, from the latest sample
+fn refresh_view(app: &mut App, sys: &mut System) {
+    match app.views.current {
+        View::Output => {},
+        View::Processes => match &mut app.views.processes {
+            Some(table) => table.refresh(&app.io, &app.sched, app.config.colors_by_container()),
+            None => {
+                let sort = app.config.sort_column();
+                let mut table = ptable::ProcessTable::collect(&app.io, &app.sched, sort, ptable::largest_first(sort));
+                table.probe(app.config.colors_by_container(), false);
+                app.views.processes = Some(table);
+            },
+        },
+        View::System => app.views.records = get_system_overview(sys),
+        View::Network => app.views.records = network::get_throughput(&app.net).unwrap_or_default(),
+        View::Disks => {
+            let df = DfOptions { power: 0, human: true, inodes: false };
+            app.views.records = get_disks_information(sys, &df, &app.history);
+        },
+        View::Sensors => app.views.records = get_components_information(sys),
+    }
+}
+
+fn update_live(app: &mut App) {
+    match &mut app.live {
+        Some(Live::Vmstat(vmstat)) => {
+            app.output.push(vmstat.next_row());
+            if vmstat.remaining == 0 {
+                app.live = None;
+            }
+        },
+        Some(Live::Journal(journal)) => {
+            app.output.extend(journal.drain());
+            if app.output.len() > FOLLOW_LINES {
+                app.output.drain(..app.output.len() - FOLLOW_LINES);
+            }
+        },
+        Some(Live::Paging) => app.output = paging::get_paging_information(&app.paging, &app.history),
+        Some(Live::Schedlat(pid)) => app.output = sched::get_latency_information(&app.sched, *pid),
+        Some(Live::Network) => show_live(app, network::get_throughput(&app.net)),
+        Some(Live::Nethogs(nethogs)) => app.output = nethogs.sample(),
+        Some(Live::Ping(ping)) => app.output = ping.update(PING_WIDTH),
+        Some(Live::Thermals) => show_live(app, thermals::get_thermals(&app.history)),
+        Some(Live::Graph(graph)) => app.output = graph::lines(graph, &app.history, GRAPH_WIDTH),
+        Some(Live::Ignite(id)) => {
+            let id = *id;
+            show_job(app, id);
+        },
+        Some(Live::Trace(trace)) => {
+            let records = trace.update();
+            if trace.finished {
+                app.output = trace.summary();
+                app.records = None;
+                app.live = None;
+            } else {
+                show(app, records);
+            }
+        },
+        #[cfg(feature = "ebpf")]
+        Some(Live::Snoop(snoop)) => {
+            app.output.extend(snoop.drain());
+            if app.output.len() > FOLLOW_LINES {
+                app.output.drain(..app.output.len() - FOLLOW_LINES);
+            }
+        },
+        Some(Live::PerfStat(perf)) => {
+            app.output = perf.update();
+            if perf.finished() {
+                app.live = None;
+            }
+        },
+        // Refreshed from the main loop, which can run commands
+        Some(Live::Watch(_)) => {},
+        None => {},
+    }
+}
+
+/// The status bar without its clock: the host whose tab is open, or this one and the hosts
+/// connected to, then how many alarms are going off here
+fn status_line(app: &App) -> String {
+    let mut status = match app.tab.checked_sub(1).and_then(|i| app.hosts.get(i)) {
+        Some(host) => match &host.metrics {
+            Some(metrics) => format!("{} | load {:.2} | mem {:.0}% | cpu {:.0}%", host.target, metrics.load, metrics.mem, metrics.cpu),
+            None => host.target.clone(),
+        },
+        None if app.hosts.is_empty() => app.status.clone(),
+        None => format!("{} | connected to {}", app.status, app.hosts.iter().map(|host| host.target.as_str()).collect::<Vec<&str>>().join(", ")),
+    };
+    let alarms = app.alerts.firing.len() + app.warnings.len();
+    if alarms > 0 {
+        status.push_str(&format!(" | {} alarms", alarms));
+    }
+    status
+}
+
+/// Sets the terminal window title, or the pane/window title when running inside tmux or screen
+fn set_pane_title(title: &str) {
+    let sequence = if std::env::var_os("TMUX").is_none() && std::env::var("TERM").map(|t| t.starts_with("screen")).unwrap_or(false) {
+        format!("\x1bk{}\x1b\\", title)
+    } else {
+        format!("\x1b]2;{}\x07", title)
+    };
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(sequence.as_bytes());
+    let _ = stdout.flush();
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    // Zoomed in, the output fills the terminal until toggled back; typing brings the layout back meanwhile
+    if app.zoom && app.input_mode == InputMode::Normal {
+        f.render_widget(Block::default().style(Style::default().bg(app.theme.background)), f.size());
+        render_output(f, app, f.size());
+        toasts::render(f, f.size(), &app.toasts, &app.theme);
+        if let Some(popup) = &app.popup {
+            popup::render(f, popup, &app.theme);
+        }
+        return;
+    }
+    let tabs = !app.hosts.is_empty();
+    // Help line, then the view tabs
+    let mut constraints = vec![Constraint::Length(1), Constraint::Length(1)];
+    if tabs {
+        constraints.push(Constraint::Length(1));
+    }
+    constraints.extend([Constraint::Length(3), Constraint::Min(1)]);
+    let alert_pane = app.alert_pane && !app.alerts.log.is_empty();
+    if alert_pane {
+        constraints.push(Constraint::Length(ALERT_PANE_LINES as u16 + 2));
+    }
+    // Status bar
+    constraints.push(Constraint::Length(1));
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(2)
-        .constraints(
-            [
-                Constraint::Length(1),
-                Constraint::Length(3),
-                Constraint::Min(1),
-            ]
-            .as_ref(),
-        )
+        .constraints(constraints)
         .split(f.size());
+    let theme = &app.theme;
+    f.render_widget(Block::default().style(Style::default().bg(theme.background)), f.size());
+    // The host tab row, when shown, pushes the other chunks down by one more
+    let first = 1 + tabs as usize;
+    let (input_area, output_area) = (chunks[first + 1], chunks[first + 2]);
+
+    // The clock ticks with every redraw, the rest with every sample
+    let status = format!(" {} | {}", status_line(app), chrono::Local::now().format("%H:%M:%S"));
+    f.render_widget(Paragraph::new(status).style(theme.text().add_modifier(Modifier::REVERSED)), chunks[chunks.len() - 1]);
 
     let (msg, style) = match app.input_mode {
         InputMode::Normal => (
             vec![
                 Span::raw("Press "),
-                Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(app.keymap.describe(Action::Quit), Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" to exit, "),
-                Span::styled("e", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(app.keymap.describe(Action::Edit), Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" to start editing."),
             ],
-            Style::default().add_modifier(Modifier::RAPID_BLINK),
+            Style::default().fg(theme.text).add_modifier(Modifier::RAPID_BLINK),
         ),
         InputMode::Editing => (
             vec![
                 Span::raw("Press "),
-                Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(app.keymap.describe(Action::Cancel), Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" to stop editing, "),
-                Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(app.keymap.describe(Action::Submit), Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(" to record the message"),
             ],
-            Style::default(),
+            Style::default().fg(theme.text),
+        ),
+        InputMode::Goto => (
+            vec![
+                Span::raw("Go to a PID or row (#n), "),
+                Span::styled(app.keymap.describe(Action::Submit), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to jump, "),
+                Span::styled(app.keymap.describe(Action::Cancel), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel"),
+            ],
+            Style::default().fg(theme.text),
+        ),
+        InputMode::Search => (
+            vec![
+                Span::raw("Search the output, "),
+                Span::styled(app.keymap.describe(Action::Submit), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to keep the matches, then "),
+                Span::styled(app.keymap.describe(Action::NextMatch), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw("/"),
+                Span::styled(app.keymap.describe(Action::PrevMatch), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" for the next and previous; "),
+                Span::styled(app.keymap.describe(Action::Cancel), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to cancel"),
+            ],
+            Style::default().fg(theme.text),
+        ),
+        InputMode::Filter => (
+            vec![
+                Span::raw("Show processes whose name or command line contains the text, "),
+                Span::styled(app.keymap.describe(Action::Submit), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to keep it, "),
+                Span::styled(app.keymap.describe(Action::Cancel), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to clear it"),
+            ],
+            Style::default().fg(theme.text),
+        ),
+        InputMode::Recall => (
+            vec![
+                Span::raw("Search the commands entered before, "),
+                Span::styled(app.keymap.describe(Action::HistorySearch), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" for an older match, "),
+                Span::styled(app.keymap.describe(Action::Submit), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to edit it, "),
+                Span::styled(app.keymap.describe(Action::Cancel), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to go back"),
+            ],
+            Style::default().fg(theme.text),
         ),
     };
     let mut text = Text::from(Spans::from(msg));
     text.patch_style(style);
-    let help_message = Paragraph::new(text);
+    // A firing alert takes over the help line until it resolves, then config alarms do
+    let help_message = if !app.alerts.firing.is_empty() {
+        Paragraph::new(format!("ALERT: {}", app.alerts.firing.join(" | ")))
+            .style(theme.alert())
+    } else if !app.warnings.is_empty() {
+        Paragraph::new(format!("WARNING: {}", app.warnings.join(" | "))).style(theme.warning())
+    } else {
+        Paragraph::new(text)
+    };
     f.render_widget(help_message, chunks[0]);
 
+    if alert_pane {
+        let entries: Vec<ListItem> = app
+            .alerts
+            .log
+            .iter()
+            .skip(app.alerts.log.len().saturating_sub(ALERT_PANE_LINES))
+            .map(|line| {
+                let style = Style::default().fg(if line.contains(" FIRED ") { theme.fired } else { theme.muted });
+                ListItem::new(line.as_str()).style(style)
+            })
+            .collect();
+        let log = List::new(entries).block(Block::default().borders(Borders::ALL).border_style(theme.border()).title("Alerts"));
+        f.render_widget(log, chunks[first + 3]);
+    }
+
+    if tabs {
+        let mut titles: Vec<Spans> = vec![Spans::from("local")];
+        titles.extend(app.hosts.iter().map(|h| Spans::from(h.target.as_str())));
+        titles.push(Spans::from("Overview"));
+        let tabs = Tabs::new(titles)
+            .select(app.tab)
+            .style(theme.text())
+            .highlight_style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD));
+        f.render_widget(tabs, chunks[2]);
+    }
+
+    let titles: Vec<Spans> = views::VIEWS.iter().map(|v| Spans::from(format!("{} {}", app.keymap.describe(Action::View(*v)), v.title()))).collect();
+    let views = Tabs::new(titles)
+        .select(app.views.current.index())
+        .style(theme.text())
+        .highlight_style(Style::default().fg(theme.accent).add_modifier(Modifier::BOLD));
+    f.render_widget(views, chunks[1]);
+
     let input = Paragraph::new(app.input.as_ref())
         .style(match app.input_mode {
-            InputMode::Normal => Style::default().fg(Color::Yellow),
-            InputMode::Editing => Style::default().fg(Color::Green),
+            InputMode::Normal => Style::default().fg(theme.input),
+            InputMode::Editing => Style::default().fg(theme.editing),
+            InputMode::Goto | InputMode::Search | InputMode::Filter | InputMode::Recall => Style::default().fg(theme.goto),
         })
-        .block(Block::default().borders(Borders::ALL).title("Input"));
-    f.render_widget(input, chunks[1]);
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(match app.input_mode {
+            InputMode::Goto => "Goto".to_string(),
+            InputMode::Search => "Search".to_string(),
+            InputMode::Filter => "Filter".to_string(),
+            InputMode::Recall => match app.recall.found {
+                Some(at) => format!("History: {}", app.messages[at]),
+                None => "History: no match".to_string(),
+            },
+            _ => "Input".to_string(),
+        }));
+    f.render_widget(input, input_area);
     match app.input_mode {
         InputMode::Normal =>
             // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
             {}
 
-        InputMode::Editing => {
+        InputMode::Editing | InputMode::Goto | InputMode::Search | InputMode::Filter | InputMode::Recall => {
             // Make the cursor visible and ask tui-rs to put it at the specified coordinates after rendering
             f.set_cursor(
                 // Put cursor past the end of the input text
-                chunks[1].x + app.input.width() as u16 + 1,
+                input_area.x + app.input.width() as u16 + 1,
                 // Move one line down, from the border to the input line
-                chunks[1].y + 1,
+                input_area.y + 1,
             )
         }
     }
 
-    let output: Vec<ListItem> = app
-        .output
-        .iter()
-        .enumerate()
-        .map(|(_i, m)| {
-            let content = vec![Spans::from(Span::raw(format!("{}", m)))];
-            ListItem::new(content)
-        })
-        .collect();
-    let output =
-        List::new(output).block(Block::default().borders(Borders::ALL).title("Output")).style(Style::default().fg(Color::Green));
+    render_output(f, app, output_area);
+    if app.input_mode == InputMode::Editing && !app.completions.is_empty() {
+        let word = app.input.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        complete::render(f, input_area, app.input[..word].width() as u16 + 1, &app.completions, &app.theme);
+    }
+    toasts::render(f, output_area, &app.toasts, &app.theme);
+    if let Some(popup) = &app.popup {
+        popup::render(f, popup, &app.theme);
+    }
+}
 
+/// The command output, a dashboard, a chart or the process table, whichever is showing
+fn render_output<B: Backend>(f: &mut Frame<B>, app: &mut App, output_area: Rect) {
+    let theme = &app.theme;
+    match app.views.current {
+        View::Output => {},
+        View::Processes => {
+            if let Some(table) = &mut app.views.processes {
+                ptable::render(f, output_area, table, &app.config.colors, &app.theme);
+            }
+            return;
+        },
+        view => {
+            records::render(f, output_area, &app.views.records, view.title(), theme, None, 0);
+            return;
+        },
+    }
 
-    
-    f.render_widget(output, chunks[2]);
+    if let (0, Some(Live::Graph(graph))) = (app.tab, &app.live) {
+        graph::render(f, output_area, graph, &app.history, theme);
+        return;
+    }
+
+    if let (0, Some(Bars::Memory(usages)), Some(records)) = (app.tab, &app.bars, &app.records) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(gauges::height(usages)), Constraint::Min(0)])
+            .split(output_area);
+        gauges::render(f, chunks[0], usages, &app.config.gauges, theme);
+        let top = first_line(app.scroll, app.search.as_ref().and_then(|s| s.current), records.rows.len(), chunks[1].height.saturating_sub(3));
+        records::render(f, chunks[1], records, "Output", theme, app.search.as_ref(), top);
+        scrolled_to(app, top);
+        return;
+    }
+
+    if let (0, Some(Bars::Filesystems(usages)), Some(records)) = (app.tab, &app.bars, &app.records) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(gauges::bars_height(usages, output_area.height)), Constraint::Min(0)])
+            .split(output_area);
+        gauges::render_bars(f, chunks[0], "Used space", usages, &app.config.gauges, theme);
+        let top = first_line(app.scroll, app.search.as_ref().and_then(|s| s.current), records.rows.len(), chunks[1].height.saturating_sub(3));
+        records::render(f, chunks[1], records, "Output", theme, app.search.as_ref(), top);
+        scrolled_to(app, top);
+        return;
+    }
+
+    if app.tab > app.hosts.len() {
+        let mut hosts = vec![("local".to_string(), app.snapshot.as_ref().map(remote::HostMetrics::from_snapshot))];
+        hosts.extend(app.hosts.iter().map(|h| (h.target.clone(), h.metrics.clone())));
+        records::render(f, output_area, &remote::get_overview(&hosts), "Hosts by pressure", theme, None, 0);
+        return;
+    }
+
+    let (title, lines) = match app.tab.checked_sub(1).and_then(|i| app.hosts.get(i)) {
+        Some(host) => (format!("Output on {}", host.target), &host.output),
+        None => {
+            if let Some(table) = &mut app.table {
+                ptable::render(f, output_area, table, &app.config.colors, &app.theme);
+                return;
+            }
+            if let Some(tree) = &mut app.tree {
+                pstree::render(f, output_area, tree, theme);
+                return;
+            }
+            if let Some(records) = &app.records {
+                let mut title = match &app.live {
+                    Some(Live::Watch(watch)) => format!("Every {}s: {}", watch.interval.as_secs(), watch.command),
+                    Some(Live::Trace(trace)) => trace.title(),
+                    _ => "Output".to_string(),
+                };
+                if let Some(search) = &app.search {
+                    title = format!("{} {}", title, search.describe(&records.row_lines()));
+                }
+                let top = first_line(app.scroll, app.search.as_ref().and_then(|s| s.current), records.rows.len(), output_area.height.saturating_sub(3));
+                records::render(f, output_area, records, &title, theme, app.search.as_ref(), top);
+                scrolled_to(app, top);
+                return;
+            }
+            match &app.search {
+                Some(search) => (format!("Output {}", search.describe(&app.output)), &app.output),
+                None => ("Output".to_string(), &app.output),
+            }
+        },
+    };
+    let search = app.search.as_ref().filter(|_| app.tab == 0);
+    let found = Style::default().fg(theme.background).bg(theme.accent);
+    let item = |m: &String| {
+        let m = records::ellipsize(m, output_area.width.saturating_sub(2) as usize);
+        match search {
+            Some(search) => ListItem::new(search.highlight(&m, found)),
+            None => ListItem::new(Spans::from(Span::raw(m))),
+        }
+    };
+    // A table printed as text keeps its header row in place while the rows under it scroll
+    let (header, lines) = match lines.split_first() {
+        Some((first, rest)) if records::is_header(first) => (Some(first), rest),
+        _ => (None, &lines[..]),
+    };
+    let pinned = header.is_some() as usize;
+    // The current match stays in sight; otherwise live views append at the bottom, so keep the newest line there
+    let selected = match search.and_then(|s| s.current) {
+        Some(line) => line.checked_sub(pinned),
+        None if app.live.is_some() && app.tab == 0 && !lines.is_empty() => Some(lines.len() - 1),
+        None => None,
+    };
+    let height = output_area.height.saturating_sub(2 + pinned as u16);
+    let top = first_line(app.scroll, selected, lines.len(), height);
+    let output: Vec<ListItem> = header.map(|h| item(h).style(Style::default().add_modifier(Modifier::BOLD))).into_iter().chain(lines.iter().skip(top).map(item)).collect();
+    let len = lines.len();
+    let title = if len > height as usize { format!("{} {}", title, scrollbar::describe(top, height as usize, len)) } else { title };
+    let output = List::new(output)
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(title))
+        .style(theme.text())
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = ListState::default();
+    state.select(selected.and_then(|line| line.checked_sub(top)).filter(|row| *row < height as usize).map(|row| row + pinned));
+    f.render_stateful_widget(output, output_area, &mut state);
+    f.render_widget(scrollbar::Scrollbar { top, shown: height as usize, len, style: theme.border() }, scrollbar::Scrollbar::track(output_area, pinned as u16));
+    scrolled_to(app, top);
 }
 
+/// The first of `len` lines shown `height` rows at a time: where the wheel scrolled to, or else
+/// the first that keeps `selected` in sight, never so far down that the last line leaves the bottom
+fn first_line(scroll: Option<usize>, selected: Option<usize>, len: usize, height: u16) -> usize {
+    let height = height as usize;
+    let top = scroll.unwrap_or_else(|| selected.map(|line| (line + 1).saturating_sub(height)).unwrap_or(0));
+    top.min(len.saturating_sub(height))
+}
 
-fn get_system_information(sys: &System) -> Vec<String> {
-    let mut vec: Vec<String> = vec![];
-    vec.push(format!("Name: {}", sys.name().unwrap()));
-    vec.push(format!("Kernel version: {}", sys.kernel_version().unwrap()));
-    vec.push(format!("OS version: {}", sys.os_version().unwrap()));
-    vec.push(format!("Host name: {}", sys.host_name().unwrap()));
-    return vec;
+/// Remembers where the output pane starts for the next turn of the wheel, which stops at the last line
+fn scrolled_to(app: &mut App, top: usize) {
+    app.output_top = top;
+    if app.scroll.is_some() {
+        app.scroll = Some(top);
+    }
 }
 
-fn get_components_information(sys: &mut System) -> Vec<String> {
-    let mut vec: Vec<String> = vec![];
+
+fn get_system_information(sys: &System) -> Records {
+    let mut records = Records::new(&["Field", "Value"]);
+    records.push(vec!["Name".to_string(), sys.name().unwrap()]);
+    records.push(vec!["Kernel version".to_string(), sys.kernel_version().unwrap()]);
+    records.push(vec!["OS version".to_string(), sys.os_version().unwrap()]);
+    records.push(vec!["Host name".to_string(), sys.host_name().unwrap()]);
+    records
+}
+
+/// Host, uptime, load and usage for the System view
+fn get_system_overview(sys: &System) -> Records {
+    let mut records = get_system_information(sys);
+    let uptime = sys.uptime();
+    let load = sys.load_average();
+    let percent = |used: u64, total: u64| if total == 0 { 0.0 } else { used as f64 * 100.0 / total as f64 };
+    records.push(vec!["Uptime".to_string(), format!("{}d {:02}:{:02}", uptime / 86400, uptime % 86400 / 3600, uptime % 3600 / 60)]);
+    records.push(vec!["Load average".to_string(), format!("{:.2} {:.2} {:.2}", load.one, load.five, load.fifteen)]);
+    records.push(vec!["CPU".to_string(), format!("{:.1}% of {} cores", sys.global_cpu_info().cpu_usage(), sys.cpus().len())]);
+    records.push(vec!["Memory".to_string(), format!("{} of {} ({:.0}%)", convert(sys.used_memory() as f64), convert(sys.total_memory() as f64), percent(sys.used_memory(), sys.total_memory()))]);
+    records.push(vec!["Swap".to_string(), format!("{} of {} ({:.0}%)", convert(sys.used_swap() as f64), convert(sys.total_swap() as f64), percent(sys.used_swap(), sys.total_swap()))]);
+    records.push(vec!["Processes".to_string(), sys.processes().len().to_string()]);
+    records
+}
+
+fn get_components_information(sys: &mut System) -> Records {
+    let mut records = Records::new(&["Label", "Temperature", "Max", "Critical"]);
     for component in sys.components() {
-        vec.push(format!("{:?}", component));
+        let critical = component.critical().map(|c| format!("{:.1}°C", c)).unwrap_or_else(|| "-".to_string());
+        records.push(vec![component.label().to_string(), format!("{:.1}°C", component.temperature()), format!("{:.1}°C", component.max()), critical]);
     }
-    return vec;
+    records
+}
+
+/// Drive sensors: labelled SSD/HDD by some drivers, or exposed by the nvme and drivetemp hwmon drivers
+fn is_drive_sensor(label: &str) -> bool {
+    let label = label.to_lowercase();
+    ["ssd", "hdd", "nvme", "drivetemp", "composite"].iter().any(|name| label.contains(name))
 }
 
 fn get_hddtemp(sys: &mut System, arg: String) -> Vec<String> {
@@ -360,7 +2597,7 @@ fn get_hddtemp(sys: &mut System, arg: String) -> Vec<String> {
     match arg.as_str() {
         "" => {
             for component in sys.components_mut() {
-                if component.label().contains("SSD") || component.label().contains("HDD"){
+                if is_drive_sensor(component.label()) {
                     vec.push(format!("{}: {:?}°C", component.label(), component.temperature()));
                     component.refresh();
                 }
@@ -368,7 +2605,7 @@ fn get_hddtemp(sys: &mut System, arg: String) -> Vec<String> {
         },
         "max" => {
             for component in sys.components_mut() {
-                if component.label().contains("SSD") || component.label().contains("HDD"){
+                if is_drive_sensor(component.label()) {
                     vec.push(format!("{}: {:?}°C", component.label(), component.max()));
                     component.refresh();
                 }
@@ -376,41 +2613,134 @@ fn get_hddtemp(sys: &mut System, arg: String) -> Vec<String> {
         },
         "crit" => {
             for component in sys.components_mut() {
-                if component.label().contains("SSD") || component.label().contains("HDD"){
-                    vec.push(format!("{}: {:?}°C", component.label(), component.critical().unwrap()));
+                if is_drive_sensor(component.label()) {
+                    match component.critical() {
+                        Some(critical) => vec.push(format!("{}: {:?}°C", component.label(), critical)),
+                        None => vec.push(format!("{}: no critical threshold reported", component.label())),
+                    }
                     component.refresh();
                 }
             }
         },
         _ => {},
     }   
+    if vec.is_empty() {
+        vec.push("No drive temperature sensors found".to_string());
+    }
     return vec;
 }
 
-fn get_disks_information(sys: &mut System, arg: String) -> Vec<String> {
-    let mut vec: Vec<String> = vec![];
+/// Options understood by `df`, mirroring the coreutils flags of the same name
+struct DfOptions {
+    /// Block size as a power of two (0 = bytes, 10 = KiB, 20 = MiB)
+    power: u32,
+    human: bool,
+    inodes: bool,
+}
+
+fn parse_df_options(args: &[String]) -> Result<DfOptions, String> {
+    let mut options = DfOptions { power: 0, human: false, inodes: false };
+    for arg in args {
+        match arg.as_str() {
+            "--human-readable" => options.human = true,
+            "--inodes" => options.inodes = true,
+            _ if arg.starts_with('-') && !arg.starts_with("--") && arg.len() > 1 => {
+                for flag in arg[1..].chars() {
+                    match flag {
+                        'h' => options.human = true,
+                        'i' => options.inodes = true,
+                        'k' => options.power = 10,
+                        'm' => options.power = 20,
+                        _ => return Err(format!("df: invalid option -- '{}' (expected -h, -i, -k or -m)", flag)),
+                    }
+                }
+            },
+            _ => return Err(format!("df: unrecognized argument '{}' (expected -h, -i, -k or -m)", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn get_disks_information(sys: &mut System, options: &DfOptions, history: &history::History) -> Records {
+    if options.inodes {
+        return get_inodes_information(sys);
+    }
     let base: u64 = 2;
-    let mut power: u32 = 0;
-    match arg.as_str() {
-        "" => {power = 0;},
-        "k" => {power = 10;},
-        "m" => {power = 20;},
-        _ => {},
+    let size = |bytes: u64| -> String {
+        if options.human {
+            convert(bytes as f64)
+        } else {
+            (bytes / base.pow(options.power)).to_string()
+        }
+    };
+    let mut records = Records::new(&["Name", "Mount Point", "Filesystem", "Total Space", "Available Space", "Used Space", "Options", "Growth/day", "Full in"]);
+    for disk in sys.disks() {
+        let mount_point = disk.mount_point().to_str().unwrap();
+        let growth = match history.fs_growth(mount_point) {
+            Some(rate) if rate < 0.0 => format!("-{}", size((-rate * 86400.0) as u64)),
+            Some(rate) => size((rate * 86400.0) as u64),
+            None => "-".to_string(),
+        };
+        let full_in = match history.days_until_full(mount_point) {
+            Some(days) => format!("{:.1} days", days),
+            None => "-".to_string(),
+        };
+        records.push(vec![
+            disk.name().to_str().unwrap().to_string(),
+            mount_point.to_string(),
+            str::from_utf8(disk.file_system()).unwrap().to_string(),
+            size(disk.total_space()),
+            size(disk.available_space()),
+            size(disk.total_space() - disk.available_space()),
+            get_mount_options(mount_point),
+            growth,
+            full_in,
+        ]);
     }
-    vec.push(format!("{:<50} {:<50} {:<50} {:<50} {:<50} {:<50}", "Name", "Mount Point", "Filesystem", "Total Space", "Available Space", "Used Space"));
+    records
+}
+
+fn get_inodes_information(sys: &mut System) -> Records {
+    let mut records = Records::new(&["Name", "Mount Point", "Inodes", "IUsed", "IFree", "Options"]);
     for disk in sys.disks() {
-        vec.push(format!("{:<50} {:<50} {:<50} {:<50} {:<50} {:<50}", disk.name().to_str().unwrap(), disk.mount_point().to_str().unwrap(), str::from_utf8(disk.file_system()).unwrap(), disk.total_space()/(base.pow(power)), disk.available_space()/(base.pow(power)), disk.total_space()/(base.pow(power)) - disk.available_space()/(base.pow(power))));
+        let mount_point = disk.mount_point().to_str().unwrap();
+        let name = disk.name().to_str().unwrap().to_string();
+        match statvfs(mount_point) {
+            Ok(stat) => {
+                let total = stat.files();
+                let free = stat.files_free();
+                records.push(vec![name, mount_point.to_string(), total.to_string(), (total - free).to_string(), free.to_string(), get_mount_options(mount_point)]);
+            },
+            Err(e) => records.push(vec![name, mount_point.to_string(), format!("error: {}", e)]),
+        }
     }
-    return vec;
+    records
 }
 
-fn get_cpu_information(sys: &mut System) -> Vec<String> {
-    let mut vec: Vec<String> = vec![];
-    vec.push(format!("{:<50} {:<50} {:<50} {:<50}", "Brand", "Vendor ID", "Name", "Frequency"));
+/// Returns the notable mount flags (ro/rw, atime handling, nosuid, ...) of `mount_point` from /proc/mounts
+fn get_mount_options(mount_point: &str) -> String {
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    // Later entries shadow earlier ones mounted on the same directory
+    let options = mounts
+        .lines()
+        .rev()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .find(|fields| fields.len() >= 4 && fields[1].replace("\\040", " ") == mount_point)
+        .map(|fields| fields[3].to_string())
+        .unwrap_or_default();
+    options
+        .split(',')
+        .filter(|o| matches!(*o, "ro" | "rw" | "noatime" | "relatime" | "nodiratime" | "nosuid" | "nodev" | "noexec" | "sync"))
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+fn get_cpu_information(sys: &mut System) -> Records {
+    let mut records = Records::new(&["Brand", "Vendor ID", "Name", "Frequency"]);
     for cpu in sys.cpus() {
-        vec.push(format!("{:<50} {:<50} {:<50} {:<50}", cpu.brand(), cpu.vendor_id(), cpu.name(), cpu.frequency()));
+        records.push(vec![cpu.brand().to_string(), cpu.vendor_id().to_string(), cpu.name().to_string(), cpu.frequency().to_string()]);
     }
-    return vec;
+    records
 }
 
 fn get_gputemp(sys: &mut System, arg: String) -> Vec<String> {
@@ -437,27 +2767,10 @@ fn get_gputemp(sys: &mut System, arg: String) -> Vec<String> {
     return vec;
 }
 
-fn printptable(app: &mut App) -> i32 {
-    let mut num: i32 = 0;
-    let processes = psutil::process::processes().unwrap();
-    app.output.push(format!("{:<30} {:<30} {:<30} {:<30}", "PID", "%CPU", "%MEM", "COMMAND"));
-    for process in processes {
-        let mut p = process.unwrap();
-        match p.cmdline() {
-            Ok(None) => {},
-            _=> {
-                num = num + 1;
-                app.output.push(format!("{:<30} {:<30} {:<30} {:<30}", p.pid(), p.cpu_percent().unwrap(), p.memory_percent().unwrap(), p.name().unwrap()));
-            },
-        }
-    }
-    return num;
-}
-
 fn kill_by_pid(app: &mut App, pid: i32) {
     match kill(Pid::from_raw(pid), Signal::SIGTERM) {
         Ok(_) => app.output.push(format!("Process with killed successfully.\n")),
-        Err(e) => app.output.push(format!("Error killing process: {}\n", e)),
+        Err(e) => fail(app, format!("Error killing process: {}\n", e)),
     }
 }
 
@@ -471,7 +2784,7 @@ fn kill_by_name(app: &mut App, name: String) {
                 if name == p.name().unwrap().to_string() {
                     match kill(Pid::from_raw(p.pid().try_into().unwrap()), Signal::SIGTERM) {
                         Ok(_) => app.output.push(format!("Process with killed successfully.\n")),
-                        Err(e) => app.output.push(format!("Error killing process: {}\n", e)),
+                        Err(e) => fail(app, format!("Error killing process: {}\n", e)),
                     }
                 }
 
@@ -480,44 +2793,501 @@ fn kill_by_name(app: &mut App, name: String) {
     }
 }
 
+fn kill_by_port(app: &mut App, port: &str) {
+    let Ok(port) = port.parse::<u16>() else {
+        fail(app, format!("kill: invalid port '{}'", port));
+        return;
+    };
+    let pids = sockets::port_owners(port);
+    if pids.is_empty() {
+        app.output.push(format!("Nothing we can see is listening on port {}", port));
+    }
+    for pid in pids {
+        kill_by_pid(app, pid);
+    }
+}
+
+fn find_process(app: &mut App, pid: i32) {
+    if findbypid(pid).is_none() {
+        fail(app, format!("Process not found with PID {}", pid));
+        return;
+    }
+    let details = get_process_details(app, pid);
+    app.output.extend(details);
+}
+
+/// What `find` prints about `pid`, also shown by Enter on a table row
+fn get_process_details(app: &App, pid: i32) -> Vec<String> {
+    let not_found = vec![format!("Process not found with PID {}", pid)];
+    let Some(mut process) = findbypid(pid) else {
+        return not_found;
+    };
+    // Each of these fails once the process exits, which it may have done since it was listed
+    let (Ok(name), Ok(cpu), Ok(mem), Ok(cmdline)) = (process.name(), process.cpu_percent(), process.memory_percent(), process.cmdline()) else {
+        return not_found;
+    };
+    let mut vec: Vec<String> = vec![format!("Process with PID {} found!: {:?}", pid, name)];
+    let mut records = Records::new(&["PID", "%CPU", "%MEM", "COMMAND"]);
+    // Kernel threads have no command line
+    if let Some(cmdline) = cmdline {
+        records.push(vec![process.pid().to_string(), cpu.to_string(), mem.to_string(), cmdline]);
+    }
+    vec.extend(records.lines());
+    let processes = app.snapshot.as_ref().map(|snap| snap.processes.as_slice()).unwrap_or_default();
+    match cgroup::cpu_quota(pid, processes) {
+        Some((path, Some(quota))) => {
+            vec.push(format!("cgroup {}", path));
+            vec.push(format!("CPU {}", cgroup::gauge(&quota, 40)));
+        },
+        Some((path, None)) => vec.push(format!("cgroup {} (no CPU quota)", path)),
+        None => {},
+    }
+    vec
+}
+
+/// `pick` offers the processes mentioned on the clipboard (by PID or name); `pick <n>` selects one of them
+fn pick(app: &mut App, choice: Option<&String>) {
+    if let Some(choice) = choice {
+        match choice.parse::<usize>().ok().and_then(|n| app.picks.get(n.wrapping_sub(1)).copied()) {
+            Some(pid) => {
+                app.selected = Some(pid);
+                find_process(app, pid);
+                app.output.push(format!("Selected PID {}; kill without arguments now targets it", pid));
+            },
+            None => app.output.push(format!("pick: no candidate {}", choice)),
+        }
+        return;
+    }
+    let text = match app.clipboard.read() {
+        Ok(text) => text,
+        Err(e) => {
+            app.output.push(format!("Error reading clipboard: {}", e));
+            return;
+        },
+    };
+    let words: Vec<&str> = text
+        .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    app.picks.clear();
+    for process in psutil::process::processes().unwrap().into_iter().flatten() {
+        let name = process.name().unwrap_or_default();
+        let pid = process.pid().to_string();
+        if words.iter().any(|w| *w == pid || *w == name) {
+            app.picks.push(process.pid() as i32);
+            app.output.push(format!("[{}] {:<10} {}", app.picks.len(), pid, name));
+        }
+    }
+    if app.picks.is_empty() {
+        app.output.push("No PID or process name on the clipboard matches a running process".to_string());
+    } else {
+        app.output.push("pick <n> selects a candidate".to_string());
+    }
+}
+
 pub fn findbypid(pid: i32) -> Option<Process> {
-    match Process::new(pid.try_into().unwrap()) {
-        Ok(process) => Some(process),
-        Err(_) => None
+    // Negative PIDs name no process
+    Process::new(pid.try_into().ok()?).ok()
+}
+
+
+fn memutil(usages: &[gauges::Usage]) -> Records {
+    let mut records = Records::new(&["Field", "Value"]);
+    for usage in usages {
+        records.push(vec![format!("Total {}", usage.label), convert(usage.total as f64)]);
+        records.push(vec![format!("Used {}", usage.label), format!("{} ({:.1}%)", convert(usage.used as f64), usage.percent())]);
+        records.push(vec![format!("Free {}", usage.label), convert(usage.total.saturating_sub(usage.used) as f64)]);
+    }
+    records
+}
+
+/// `export all <dir|archive.tar[.gz]>`: the state of the box in one go
+fn export_command(app: &mut App, sys: &mut System, parts: &[String]) {
+    let target = match (parts.get(1).map(|s| s.as_str()), parts.get(2)) {
+        (Some("all"), Some(target)) => target,
+        _ => {
+            fail(app, "usage: export all <dir|file.tar|file.tar.gz>".to_string());
+            return;
+        },
+    };
+    sys.refresh_all();
+    let df = DfOptions { power: 0, human: false, inodes: false };
+    let views = [
+        ("sysinfo", get_system_information(sys)),
+        ("df", get_disks_information(sys, &df, &app.history)),
+        ("sensors", get_components_information(sys)),
+        ("memory", memutil(&gauges::memory())),
+        ("network", Records::from_lines(&network::get_network_information(&app.net))),
+        ("routes", network::get_routes()),
+        ("connections", sockets::get_connections(None)),
+    ];
+    let mut table = ptable::ProcessTable::collect(&app.io, &app.sched, ptable::SortColumn::Pid, false);
+    // ptable.json has every field
+    table.probe(true, true);
+    match export::export_all(target, &views, &table, app.snapshot.as_ref()) {
+        Ok(files) => app.output.push(format!("Exported {} to {}", files.join(", "), target)),
+        Err(e) => fail(app, format!("Error exporting: {}", e)),
     }
 }
 
+/// Processes listed in a report, busiest first
+const REPORT_TOP: usize = 20;
 
-fn networkuti(app: &mut App) {
-    let mut system = System::new_all();
-    system.refresh_all();
+/// `report <file.html|file.md>`: the main views in one file to share
+fn report_command(app: &mut App, sys: &mut System, parts: &[String]) {
+    let Some((path, format)) = parts.get(1).and_then(|p| report::Format::from_path(p).map(|f| (p, f))) else {
+        fail(app, "usage: report <file.html|file.md>".to_string());
+        return;
+    };
+    sys.refresh_all();
+    let df = DfOptions { power: 0, human: true, inodes: false };
+    let table = ptable::ProcessTable::collect(&app.io, &app.sched, ptable::SortColumn::Cpu, true);
+    let sections = [
+        ("System", get_system_information(sys).lines()),
+        ("CPU", get_cpu_information(sys).lines()),
+        ("Memory", memutil(&gauges::memory()).lines()),
+        ("Disks", get_disks_information(sys, &df, &app.history).lines()),
+        ("Sensors", get_components_information(sys).lines()),
+        ("Network", network::get_network_information(&app.net)),
+        ("Top processes", table.lines().into_iter().take(REPORT_TOP + 1).collect()),
+    ];
+    match report::write(path, format, &sys.host_name().unwrap_or_default(), &sections) {
+        Ok(_) => app.output.push(format!("Wrote report to {}", path)),
+        Err(e) => fail(app, format!("Error writing report: {}", e)),
+    }
+}
+
+fn snapshot_command(app: &mut App, parts: &[String]) {
+    match (parts.get(1).map(|s| s.as_str()), parts.get(2), parts.get(3)) {
+        (Some("save"), Some(name), None) => {
+            let Some(snap) = &app.snapshot else {
+                app.output.push("snapshot: no sample taken yet".to_string());
+                return;
+            };
+            match snapshots::save(name, snap) {
+                Ok(path) => app.output.push(format!("Saved {} processes to {}", snap.processes.len(), path.display())),
+                Err(e) => fail(app, format!("Error saving snapshot: {}", e)),
+            }
+        },
+        (Some("diff"), Some(a), Some(b)) => {
+            let before = snapshots::load(a);
+            let after = match (b.as_str(), &app.snapshot) {
+                ("now", Some(snap)) => Ok(snap.clone()),
+                _ => snapshots::load(b),
+            };
+            match (before, after) {
+                (Ok(before), Ok(after)) => show(app, snapshots::diff(&before, &after)),
+                (Err(e), _) | (_, Err(e)) => app.output.push(format!("Error loading snapshot: {}", e)),
+            }
+        },
+        (None, None, None) | (Some("list"), None, None) => {
+            let records = snapshots::get_snapshot_list();
+            if records.rows.is_empty() {
+                app.output.push("No saved snapshots".to_string());
+            } else {
+                show(app, records);
+            }
+        },
+        _ => fail(app, "usage: snapshot save <name> | snapshot diff <a> <b|now> | snapshot list".to_string()),
+    }
+}
 
-    for (interface_name, network_interface) in system.networks() {
-        app.output.push(format!("Interface {}: transmitted: {}, received: {}", interface_name, network_interface.total_packets_transmitted(), network_interface.total_packets_received()));
+fn serve_command(app: &mut App, parts: &[String]) {
+    match parts.get(1).map(|s| s.as_str()) {
+        Some("stop") => match app.server.take() {
+            Some(server) => {
+                server.stop();
+                app.output.push(format!("Stopped serving on {}", server.addr));
+            },
+            None => app.output.push("serve: not running".to_string()),
+        },
+        addr => {
+            if let Some(server) = &app.server {
+                app.output.push(format!("serve: already serving on {}", server.addr));
+                return;
+            }
+            let addr = addr.unwrap_or("127.0.0.1:9100");
+            match rest::Server::start(addr, app.cli.readonly, app.config.serve.token.clone()) {
+                Ok(server) => {
+                    if let Some(snap) = &app.snapshot {
+                        server.publish(snap);
+                    }
+                    app.output.push(format!("Serving http://{}/processes, /system, /sensors and POST /kill", addr));
+                    if app.cli.readonly || app.config.serve.token.is_none() {
+                        app.output.push("POST /kill is off; set token under [serve] in config.toml to enable it".to_string());
+                    } else if !addr.starts_with("127.") && !addr.starts_with("localhost") && !addr.starts_with("[::1]") {
+                        // The token travels in plain HTTP
+                        app.output.push(format!("Warning: {} is reachable from other hosts and the token is sent unencrypted", addr));
+                    }
+                    app.server = Some(server);
+                },
+                Err(e) => fail(app, format!("serve: {}", e)),
+            }
+        },
     }
 }
 
-fn memutil(app: &mut App) {
-    let s = System::new_all();
-    app.output.push(format!("Total Memory: {}", convert(s.total_memory()as f64)));
-    app.output.push(format!("Used Memory: {}", convert(s.used_memory()as f64)));
-    app.output.push(format!("Free Memory: {}", convert(s.free_memory()as f64)));
+fn mqtt_command(app: &mut App, sys: &System, parts: &[String]) {
+    match (parts.get(1).map(|s| s.as_str()), parts.get(2)) {
+        (Some("start"), broker) => {
+            if let Some(publisher) = &app.mqtt {
+                app.output.push(format!("mqtt: already publishing to {}", publisher.broker));
+                return;
+            }
+            let mut config = app.config.mqtt.clone().unwrap_or_default();
+            if let Some(broker) = broker {
+                config.broker = broker.clone();
+            }
+            match mqtt::Publisher::start(&config, &sys.host_name().unwrap_or_default()) {
+                Ok(publisher) => {
+                    app.output.push(format!("Publishing to {} under {}/ every {}s", publisher.broker, publisher.topic, config.interval_secs));
+                    app.mqtt = Some(publisher);
+                },
+                Err(e) => fail(app, format!("mqtt: {}", e)),
+            }
+        },
+        (Some("stop"), None) => match app.mqtt.take() {
+            Some(publisher) => {
+                app.output.push(format!("Stopped publishing to {} after {} samples", publisher.broker, publisher.published));
+                publisher.stop();
+            },
+            None => app.output.push("mqtt: not publishing".to_string()),
+        },
+        (None, None) => match &app.mqtt {
+            Some(publisher) => app.output.push(format!("Publishing to {} under {}/, {} samples so far", publisher.broker, publisher.topic, publisher.published)),
+            None => app.output.push("mqtt: not publishing".to_string()),
+        },
+        _ => fail(app, "usage: mqtt [start [host[:port]] | stop]".to_string()),
+    }
+}
 
+fn record_command(app: &mut App, parts: &[String]) {
+    match (parts.get(1).map(|s| s.as_str()), parts.get(2)) {
+        (Some("start"), Some(path)) => {
+            if let Some(recorder) = &app.recorder {
+                app.output.push(format!("record: already recording to {}", recorder.path));
+                return;
+            }
+            match record::Recorder::start(path) {
+                Ok(recorder) => {
+                    app.output.push(format!("Recording every {:.1}s to {} until record stop", app.config.refresh().as_secs_f64(), path));
+                    app.recorder = Some(recorder);
+                },
+                Err(e) => fail(app, format!("Error creating {}: {}", path, e)),
+            }
+        },
+        (Some("stop"), None) => match app.recorder.take() {
+            Some(recorder) => app.output.push(format!("Recorded {} samples to {}", recorder.rows, recorder.path)),
+            None => app.output.push("record: not recording".to_string()),
+        },
+        (None, None) => match &app.recorder {
+            Some(recorder) => app.output.push(format!("Recording to {}, {} samples so far", recorder.path, recorder.rows)),
+            None => app.output.push("Not recording".to_string()),
+        },
+        _ => fail(app, "usage: record start <file.csv|file.jsonl> | record stop".to_string()),
+    }
 }
 
-fn desc(app: &mut App) {
-    let mut processes = psutil::process::processes().unwrap();
-    processes.reverse();
-    app.output.push(format!("{:<30} {:<30} {:<30} {:<30}", "PID","%CPU", "%MEM", "COMMAND"));
-    app.output.push(format!("{:<30} {:<30} {:<30} {:<30}", "PID", "%CPU", "%MEM", "COMMAND"));
-    for process in processes {
-        let mut p = process.unwrap();
-        match p.cmdline() {
-            Ok(None) => {},
-            _=> {
-                app.output.push(format!("{:<30} {:<30} {:<30} {:<30}", p.pid(), p.cpu_percent().unwrap(), p.memory_percent().unwrap(), p.name().unwrap()));
+fn guard_command(app: &mut App, parts: &[String]) {
+    match parts.get(1).map(|s| s.as_str()) {
+        None | Some("list") => {
+            for guard in &app.guards.list {
+                app.output.push(guard.describe());
+            }
+            if app.guards.list.is_empty() {
+                app.output.push("No guards".to_string());
+            }
+        },
+        Some("log") => app.output = guard::get_audit_log(),
+        Some("remove") => match parts.get(2).and_then(|id| id.parse::<usize>().ok()) {
+            Some(id) if app.guards.remove(id) => app.output.push(format!("Removed guard [{}]", id)),
+            Some(id) => fail(app, format!("guard: no guard [{}]", id)),
+            None => fail(app, "usage: guard remove <id>".to_string()),
+        },
+        Some(pattern) => {
+            let usage = "usage: guard <pattern> [--mem <size>] [--cpu <percent>] [--grace <duration>]";
+            let mut mem: Option<u64> = None;
+            let mut cpu: Option<f64> = None;
+            let mut grace = guard::DEFAULT_GRACE;
+            let mut args = parts[2..].iter();
+            while let Some(arg) = args.next() {
+                let value = args.next().map(|s| s.as_str()).unwrap_or_default();
+                match arg.as_str() {
+                    "--mem" => mem = guard::parse_size(value),
+                    "--cpu" => cpu = value.parse::<f64>().ok().filter(|c| *c > 0.0),
+                    "--grace" => grace = alerts::parse_duration(value).unwrap_or(0),
+                    _ => {
+                        fail(app, usage.to_string());
+                        return;
+                    },
+                }
+                if (arg == "--mem" && mem.is_none()) || (arg == "--cpu" && cpu.is_none()) || (arg == "--grace" && grace == 0) {
+                    fail(app, format!("guard: invalid value '{}' for {}", value, arg));
+                    return;
+                }
+            }
+            if mem.is_none() && cpu.is_none() {
+                fail(app, usage.to_string());
+                return;
+            }
+            let guard = app.guards.add(pattern, mem, cpu, grace);
+            app.output.push(format!("Guarding {}; actions are logged to {}", guard.describe(), guard::audit_path().display()));
+        },
+    }
+}
+
+fn portwatch_command(app: &mut App, parts: &[String]) {
+    match (parts.get(1).map(|s| s.as_str()), parts.get(2).map(|p| p.parse::<u16>())) {
+        (None, None) | (Some("list"), None) => {
+            for watch in &app.ports.list {
+                app.output.push(watch.describe());
+            }
+            if app.ports.list.is_empty() {
+                app.output.push("No watched ports".to_string());
+            }
+        },
+        (Some("remove"), Some(Ok(port))) => {
+            if app.ports.remove(port) {
+                app.output.push(format!("Stopped watching port {}", port));
+            } else {
+                fail(app, format!("portwatch: port {} is not watched", port));
+            }
+        },
+        (Some(port), None) => match port.parse::<u16>() {
+            Ok(port) => {
+                let state = app.ports.add(port);
+                app.output.push(state);
             },
+            Err(_) => fail(app, format!("portwatch: invalid port '{}'", port)),
+        },
+        _ => fail(app, "usage: portwatch <port> | portwatch remove <port>".to_string()),
+    }
+}
+
+fn spawn_command(app: &mut App, parts: &[String]) {
+    match (parts.get(1).map(|s| s.as_str()), parts.get(2).map(|s| s.as_str())) {
+        (None, None) | (Some("list"), None) => {
+            for watch in &app.spawns.list {
+                app.output.push(format!("[{}] '{}'{}", watch.id, watch.pattern, if watch.desktop { " (desktop)" } else { "" }));
+            }
+            if app.spawns.list.is_empty() {
+                app.output.push("No spawn watches".to_string());
+            }
+        },
+        (Some("remove"), Some(id)) => match id.parse::<usize>() {
+            Ok(id) if app.spawns.remove(id) => app.output.push(format!("Removed spawn watch [{}]", id)),
+            _ => app.output.push(format!("notify-on-spawn: no watch [{}]", id)),
+        },
+        (Some(pattern), desktop @ (None | Some("--desktop"))) if parts.len() <= 3 => {
+            let watch = app.spawns.add(pattern, desktop.is_some());
+            app.output.push(format!("Watching for new processes matching '{}' [{}]", watch.pattern, watch.id));
+        },
+        _ => fail(app, "usage: notify-on-spawn <pattern> [--desktop] | notify-on-spawn remove <id>".to_string()),
+    }
+}
+
+fn watchdog_command(app: &mut App, parts: &[String]) {
+    match parts.get(1).map(|s| s.as_str()) {
+        None | Some("list") => {
+            for watch in &app.watchdogs.list {
+                app.output.push(watch.describe());
+            }
+            if app.watchdogs.list.is_empty() {
+                app.output.push("No watched processes".to_string());
+            }
+        },
+        Some("remove") => match parts.get(2).and_then(|id| id.parse::<usize>().ok()) {
+            Some(id) if app.watchdogs.remove(id) => app.output.push(format!("Stopped watchdog [{}]", id)),
+            Some(id) => app.output.push(format!("watchdog: no watch [{}]", id)),
+            None => fail(app, "usage: watchdog remove <id>".to_string()),
+        },
+        Some(target) => {
+            let restart = match parts.get(2).map(|s| s.as_str()) {
+                None => None,
+                Some("--restart") if parts.len() > 3 => Some(parts[3..].to_vec()),
+                Some(_) => {
+                    fail(app, "usage: watchdog <name|pid> [--restart <cmd> [args]]".to_string());
+                    return;
+                },
+            };
+            match app.watchdogs.add(target, restart) {
+                Ok(watch) => app.output.push(format!("Watching {}", watch.describe())),
+                Err(e) => fail(app, e),
+            }
+        },
+    }
+}
+
+fn alert_command(app: &mut App, parts: &[String]) {
+    match parts.get(1).map(|s| s.as_str()) {
+        None | Some("list") => {
+            for rule in &app.rules {
+                app.output.push(rules::describe(rule));
+            }
+            if app.rules.is_empty() {
+                app.output.push("No alert rules; add one with alert <metric> > <threshold>".to_string());
+            }
+            for firing in &app.alerts.firing {
+                app.output.push(format!("FIRING {}", firing));
+            }
+        },
+        Some("log") => app.output = app.alerts.log.iter().cloned().collect(),
+        Some("clear") => {
+            app.alerts.log.clear();
+            app.output.push("Alert log cleared".to_string());
+        },
+        Some("remove") => {
+            let name = parts[2..].join(" ");
+            let before = app.rules.len();
+            app.rules.retain(|rule| rule.name != name);
+            if app.rules.len() == before {
+                app.output.push(format!("alert: no rule named '{}'", name));
+            } else {
+                app.output.push(format!("Removed alert '{}'", name));
+            }
+        },
+        Some(_) => match alerts::add(&mut app.rules, &parts[1..]) {
+            Ok(message) => app.output.push(message),
+            Err(e) => fail(app, e),
+        },
+    }
+}
+
+fn rules_command(app: &mut App, sys: &mut System, parts: &[String]) {
+    let path = parts.get(2).map(std::path::PathBuf::from).unwrap_or_else(rules::path);
+    match parts.get(1).map(|s| s.as_str()) {
+        Some("load") => match rules::load(&path) {
+            Ok(loaded) => {
+                app.output.push(format!("Loaded {} rules from {}", loaded.len(), path.display()));
+                app.rules = loaded;
+            }
+            Err(e) => fail(app, format!("Error loading rules: {}", e)),
+        },
+        Some("save") => match rules::save(&path, &app.rules) {
+            Ok(_) => app.output.push(format!("Saved {} rules to {}", app.rules.len(), path.display())),
+            Err(e) => fail(app, format!("Error saving rules: {}", e)),
+        },
+        Some("list") => {
+            for rule in &app.rules {
+                app.output.push(rules::describe(rule));
+            }
+        }
+        Some("check") => {
+            let snap = match app.snapshot.take() {
+                Some(snap) => snap,
+                None => rules::Snapshot::take(sys),
+            };
+            app.output = rules::dry_run(&app.rules, &snap);
+            app.snapshot = Some(snap);
         }
+        Some("test") => match parts.get(2) {
+            Some(recording) => match rules::replay(&app.rules, recording) {
+                Ok(report) => app.output = report,
+                Err(e) => fail(app, format!("Error replaying recording: {}", e)),
+            },
+            None => fail(app, "usage: rules test <recording>".to_string()),
+        },
+        _ => fail(app, "usage: rules load|save [file] | rules list|check | rules test <recording>".to_string()),
     }
 }
 
@@ -572,3 +3342,39 @@ fn desc(app: &mut App) {
     
 
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn aliases_expand_before_the_line_is_split() {
+        let mut config = config::Config::default();
+        config.aliases.insert("x".to_string(), "uname; hostname".to_string());
+        config.aliases.insert("y".to_string(), "lscpu && sysinfo".to_string());
+        let steps = expand_aliases(&config, chain::split("x && y; ps"));
+        let lines: Vec<(&str, bool)> = steps.iter().map(|s| (s.line.trim(), s.after_success)).collect();
+        assert_eq!(lines, [("uname", false), ("hostname", false), ("lscpu", true), ("sysinfo", true), ("ps", false)]);
+        // Only the start of a step is an alias
+        let steps = expand_aliases(&config, chain::split("echo x"));
+        assert_eq!(steps[0].line, "echo x");
+    }
+
+    #[test]
+    fn watch_refuses_side_effects() {
+        for line in ["kill 1", "ignite sleep 1", "killjob 1", "pstree kill 1", "serve", "export all /tmp/x", "record start x.csv", "edit config", "connect host", "guard sshd --mem 1G", "alert cpu > 90", "watch 2 ports", "mqtt start"] {
+            assert!(has_side_effects(&parts(line)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn watch_allows_what_only_shows() {
+        for line in ["ports", "df -h", "ptable --sort cpu", "pstree", "docker", "guard list", "alert list", "snapshot list", "mqtt", "jobs"] {
+            assert!(!has_side_effects(&parts(line)), "{}", line);
+        }
+    }
+}
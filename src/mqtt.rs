@@ -0,0 +1,174 @@
+use crate::rules::Snapshot;
+use rumqttc::{Client, ConnectionError, Event, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Port used when `broker` leaves it out
+const DEFAULT_PORT: u16 = 1883;
+
+/// Wait between reconnection attempts while the broker is unreachable
+const RETRY: Duration = Duration::from_secs(5);
+
+/// Requests queued for the connection thread before publishing starts dropping samples
+const QUEUE: usize = 64;
+
+/// Published metric, unit shown in Home Assistant, value
+type Metric = (&'static str, &'static str, f64);
+
+fn metrics(snap: &Snapshot) -> [Metric; 8] {
+    [
+        ("cpu", "%", snap.cpu),
+        ("mem", "%", snap.mem),
+        ("swap", "%", snap.swap),
+        ("load", "", snap.load),
+        ("temp", "°C", snap.temp),
+        ("disk", "%", snap.disk),
+        ("net_rx", "B/s", snap.net_rx),
+        ("net_tx", "B/s", snap.net_tx),
+    ]
+}
+
+/// The `[mqtt]` section of config.toml; publishing starts with proclynx when it is present
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttConfig {
+    /// "host" or "host:port"
+    pub broker: String,
+    /// Topics are `<prefix>/<hostname>/<metric>`
+    pub prefix: String,
+    pub interval_secs: u64,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Announce every metric as a Home Assistant sensor through MQTT discovery
+    pub discovery: bool,
+}
+
+impl Default for MqttConfig {
+    fn default() -> MqttConfig {
+        MqttConfig { broker: String::new(), prefix: "proclynx".to_string(), interval_secs: 10, username: None, password: None, discovery: true }
+    }
+}
+
+/// Publishes the sampler's system metrics to an MQTT broker every `interval_secs`
+pub struct Publisher {
+    pub broker: String,
+    /// Topic prefix including the host name
+    pub topic: String,
+    host: String,
+    interval: Duration,
+    discovery: bool,
+    client: Client,
+    errors: Receiver<String>,
+    last: Option<Instant>,
+    announced: bool,
+    pub published: usize,
+}
+
+impl Publisher {
+    pub fn start(config: &MqttConfig, host: &str) -> Result<Publisher, String> {
+        let (address, port) = match config.broker.rsplit_once(':') {
+            Some((address, port)) => (address, port.parse::<u16>().map_err(|_| format!("invalid broker port '{}'", port))?),
+            None => (config.broker.as_str(), DEFAULT_PORT),
+        };
+        if address.is_empty() {
+            return Err("no broker set; use mqtt start <host[:port]> or broker in the [mqtt] section of config.toml".to_string());
+        }
+        let mut options = MqttOptions::new(format!("proclynx-{}", host), address, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            options.set_credentials(username, password);
+        }
+        let (client, mut connection) = Client::new(options, QUEUE);
+        let (tx, rx) = mpsc::channel();
+        let broker = format!("{}:{}", address, port);
+        let thread_broker = broker.clone();
+        thread::spawn(move || {
+            // Iterating drives the connection and reconnects after errors
+            let mut connected = false;
+            let mut reported = false;
+            for event in connection.iter() {
+                match event {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        connected = true;
+                        reported = false;
+                    },
+                    Ok(_) => {},
+                    // The client was dropped by `stop`
+                    Err(ConnectionError::RequestsDone) => break,
+                    Err(e) => {
+                        // One report per outage rather than one per retry
+                        if !reported {
+                            let what = if connected { "lost" } else { "failed" };
+                            if tx.send(format!("mqtt: connection to {} {}: {}", thread_broker, what, e)).is_err() {
+                                break;
+                            }
+                            reported = true;
+                        }
+                        connected = false;
+                        thread::sleep(RETRY);
+                    },
+                }
+            }
+        });
+        Ok(Publisher {
+            broker,
+            topic: format!("{}/{}", config.prefix.trim_end_matches('/'), host),
+            host: host.to_string(),
+            interval: Duration::from_secs(config.interval_secs.max(1)),
+            discovery: config.discovery,
+            client,
+            errors: rx,
+            last: None,
+            announced: false,
+            published: 0,
+        })
+    }
+
+    /// Retained Home Assistant discovery configs, so the sensors appear without YAML
+    fn announce(&mut self, snap: &Snapshot) {
+        for (name, unit, _) in metrics(snap) {
+            let id = format!("proclynx_{}_{}", self.host, name);
+            let mut config = json!({
+                "name": name,
+                "unique_id": id,
+                "state_topic": format!("{}/{}", self.topic, name),
+                "device": { "identifiers": [format!("proclynx_{}", self.host)], "name": self.host, "manufacturer": "proclynx" },
+            });
+            if !unit.is_empty() {
+                config["unit_of_measurement"] = json!(unit);
+            }
+            let _ = self.client.try_publish(format!("homeassistant/sensor/{}/config", id), QoS::AtLeastOnce, true, config.to_string());
+        }
+    }
+
+    /// Publishes `snap` when the interval has passed since the last one
+    pub fn publish(&mut self, snap: &Snapshot) {
+        if self.last.map(|last| last.elapsed() < self.interval).unwrap_or(false) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        if self.discovery && !self.announced {
+            self.announce(snap);
+            self.announced = true;
+        }
+        for (name, _, value) in metrics(snap) {
+            // A full queue means the broker is unreachable; the sample is dropped rather than blocking the UI
+            let _ = self.client.try_publish(format!("{}/{}", self.topic, name), QoS::AtMostOnce, false, format!("{:.2}", value));
+        }
+        self.published += 1;
+    }
+
+    /// Connection problems reported since the last call
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.try_iter().collect()
+    }
+
+    pub fn stop(self) {
+        let _ = self.client.try_disconnect();
+    }
+}
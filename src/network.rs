@@ -0,0 +1,296 @@
+use crate::records::Records;
+use nix::ifaddrs::getifaddrs;
+use pretty_bytes::converter::convert;
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddrV4},
+    path::Path,
+    process::Command,
+    time::Instant,
+};
+
+fn read_attr(interface: &str, name: &str) -> String {
+    fs::read_to_string(Path::new("/sys/class/net").join(interface).join(name)).map(|s| s.trim().to_string()).unwrap_or_default()
+}
+
+/// Interface names from /sys/class/net, sorted
+pub fn interfaces() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir("/sys/class/net")
+        .map(|entries| entries.filter_map(|e| e.ok()?.file_name().into_string().ok()).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// IPv4/IPv6 addresses of `interface` with their prefix length
+fn addresses(interface: &str) -> Vec<(IpAddr, u32)> {
+    let Ok(addrs) = getifaddrs() else {
+        return vec![];
+    };
+    addrs
+        .filter(|a| a.interface_name == interface)
+        .filter_map(|a| {
+            let address = a.address?;
+            let netmask = a.netmask;
+            if let Some(sin) = address.as_sockaddr_in() {
+                let prefix = netmask.as_ref().and_then(|m| m.as_sockaddr_in()).map(|m| u32::from(*SocketAddrV4::from(*m).ip()).count_ones());
+                Some((IpAddr::V4(*SocketAddrV4::from(*sin).ip()), prefix.unwrap_or(32)))
+            } else if let Some(sin6) = address.as_sockaddr_in6() {
+                let prefix = netmask.as_ref().and_then(|m| m.as_sockaddr_in6()).map(|m| u128::from(m.ip()).count_ones());
+                Some((IpAddr::V6(sin6.ip()), prefix.unwrap_or(128)))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn read_counter(interface: &str, name: &str) -> u64 {
+    read_attr(interface, &format!("statistics/{}", name)).parse::<u64>().unwrap_or(0)
+}
+
+/// Turns the cumulative interface byte counters into per-second rates between samples
+pub struct NetTracker {
+    prev: HashMap<String, (u64, u64)>,
+    at: Instant,
+    /// (received, transmitted) bytes per second over the last interval, keyed by interface
+    pub rates: HashMap<String, (f64, f64)>,
+}
+
+impl Default for NetTracker {
+    fn default() -> NetTracker {
+        NetTracker { prev: HashMap::new(), at: Instant::now(), rates: HashMap::new() }
+    }
+}
+
+impl NetTracker {
+    pub fn update(&mut self) {
+        let secs = self.at.elapsed().as_secs_f64().max(0.001);
+        let now: HashMap<String, (u64, u64)> = interfaces()
+            .into_iter()
+            .map(|interface| {
+                let counters = (read_counter(&interface, "rx_bytes"), read_counter(&interface, "tx_bytes"));
+                (interface, counters)
+            })
+            .collect();
+        self.rates = now
+            .iter()
+            .filter_map(|(interface, (rx, tx))| {
+                let (prev_rx, prev_tx) = self.prev.get(interface)?;
+                Some((interface.clone(), (rx.saturating_sub(*prev_rx) as f64 / secs, tx.saturating_sub(*prev_tx) as f64 / secs)))
+            })
+            .collect();
+        self.prev = now;
+        self.at = Instant::now();
+    }
+}
+
+/// Receive/transmit rate of every interface, busiest first, once two samples gave the first rates
+pub fn get_throughput(tracker: &NetTracker) -> Result<Records, String> {
+    if tracker.rates.is_empty() {
+        return Err("Waiting for the next sample...".to_string());
+    }
+    let mut rates: Vec<(&String, &(f64, f64))> = tracker.rates.iter().collect();
+    rates.sort_by(|a, b| (b.1 .0 + b.1 .1).total_cmp(&(a.1 .0 + a.1 .1)).then(a.0.cmp(b.0)));
+    let mut records = Records::new(&["Interface", "RX/s", "TX/s"]);
+    for (interface, (rx, tx)) in rates {
+        records.push(vec![interface.clone(), format!("{}/s", convert(*rx)), format!("{}/s", convert(*tx))]);
+    }
+    Ok(records)
+}
+
+/// Link state, MAC, MTU, addresses and current throughput of every interface
+pub fn get_network_information(tracker: &NetTracker) -> Vec<String> {
+    let mut vec: Vec<String> = vec![];
+    for interface in interfaces() {
+        // operstate is "unknown" for interfaces without carrier detection such as loopback
+        let state = match read_attr(&interface, "operstate").as_str() {
+            "up" => "UP",
+            "down" => "DOWN",
+            _ if u32::from_str_radix(read_attr(&interface, "flags").trim_start_matches("0x"), 16).map(|f| f & 1 == 1).unwrap_or(false) => "UP",
+            _ => "DOWN",
+        };
+        vec.push(format!("{}: {}  mtu {}  mac {}", interface, state, read_attr(&interface, "mtu"), read_attr(&interface, "address")));
+        for (ip, prefix) in addresses(&interface) {
+            let family = if ip.is_ipv4() { "inet" } else { "inet6" };
+            vec.push(format!("    {} {}/{}", family, ip, prefix));
+        }
+        match tracker.rates.get(&interface) {
+            Some((rx, tx)) => vec.push(format!("    RX {}/s  TX {}/s", convert(*rx), convert(*tx))),
+            None => vec.push("    RX -  TX -".to_string()),
+        }
+    }
+    vec
+}
+
+/// Interfaces with a wireless extension or an 802.11 PHY
+fn wireless_interfaces() -> Vec<String> {
+    interfaces()
+        .into_iter()
+        .filter(|i| Path::new("/sys/class/net").join(i).join("wireless").exists() || Path::new("/sys/class/net").join(i).join("phy80211").exists())
+        .collect()
+}
+
+/// (link quality, signal dBm, noise dBm) per interface from /proc/net/wireless
+fn read_wireless_quality() -> HashMap<String, (f64, f64, f64)> {
+    let mut quality: HashMap<String, (f64, f64, f64)> = HashMap::new();
+    for line in fs::read_to_string("/proc/net/wireless").unwrap_or_default().lines().skip(2) {
+        let Some((interface, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let fields: Vec<f64> = rest.split_whitespace().skip(1).take(3).filter_map(|f| f.trim_end_matches('.').parse::<f64>().ok()).collect();
+        if let [link, level, noise] = fields[..] {
+            quality.insert(interface.trim().to_string(), (link, level, noise));
+        }
+    }
+    quality
+}
+
+/// SSID, signal, bitrates and frequency of the current association, as reported by `iw dev <interface> link`
+fn read_iw_link(interface: &str) -> Option<Vec<(String, String)>> {
+    let output = Command::new("iw").args(["dev", interface, "link"]).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.starts_with("Not connected") {
+        return Some(vec![("state".to_string(), "not connected".to_string())]);
+    }
+    Some(
+        stdout
+            .lines()
+            .filter_map(|line| line.trim().split_once(": "))
+            .filter(|(key, _)| matches!(*key, "SSID" | "freq" | "signal" | "rx bitrate" | "tx bitrate"))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+/// SSID, signal strength, bitrate and frequency of every wireless interface
+pub fn get_wifi_information() -> Vec<String> {
+    let mut vec: Vec<String> = vec![];
+    let quality = read_wireless_quality();
+    for interface in wireless_interfaces() {
+        vec.push(format!("{}:", interface));
+        match read_iw_link(&interface) {
+            Some(link) => {
+                for (key, value) in link {
+                    let value = if key == "freq" { format!("{} MHz", value) } else { value };
+                    vec.push(format!("    {:<12} {}", key, value));
+                }
+            },
+            None => vec.push("    (install iw for SSID, bitrate and frequency)".to_string()),
+        }
+        if let Some((link, level, noise)) = quality.get(&interface) {
+            // Link quality is out of 70 for most drivers
+            vec.push(format!("    {:<12} {:.0}/70  level {:.0} dBm  noise {:.0} dBm", "quality", link, level, noise));
+        }
+    }
+    if vec.is_empty() {
+        vec.push("No wireless interfaces found".to_string());
+    }
+    vec
+}
+
+/// IPv4 addresses in /proc/net/route are printed as the raw (network order) word read natively
+fn parse_route_ipv4(hex: &str) -> Option<Ipv4Addr> {
+    u32::from_str_radix(hex, 16).ok().map(|word| Ipv4Addr::from(word.to_ne_bytes()))
+}
+
+/// (destination/prefix, gateway, interface, metric) of every IPv4 and IPv6 route
+fn read_routes() -> Vec<(String, Option<IpAddr>, String, u32)> {
+    let mut routes: Vec<(String, Option<IpAddr>, String, u32)> = vec![];
+    for line in fs::read_to_string("/proc/net/route").unwrap_or_default().lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 8 {
+            continue;
+        }
+        let (Some(destination), Some(gateway), Some(mask)) = (parse_route_ipv4(fields[1]), parse_route_ipv4(fields[2]), parse_route_ipv4(fields[7])) else {
+            continue;
+        };
+        let flags = u32::from_str_radix(fields[3], 16).unwrap_or(0);
+        // RTF_GATEWAY
+        let gateway = if flags & 0x2 != 0 { Some(IpAddr::V4(gateway)) } else { None };
+        routes.push((format!("{}/{}", destination, u32::from(mask).count_ones()), gateway, fields[0].to_string(), fields[6].parse::<u32>().unwrap_or(0)));
+    }
+    for line in fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default().lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 {
+            continue;
+        }
+        let (Ok(destination), Ok(prefix), Ok(gateway), Ok(metric)) = (
+            u128::from_str_radix(fields[0], 16),
+            u32::from_str_radix(fields[1], 16),
+            u128::from_str_radix(fields[4], 16),
+            u32::from_str_radix(fields[5], 16),
+        ) else {
+            continue;
+        };
+        let gateway = if gateway == 0 { None } else { Some(IpAddr::V6(Ipv6Addr::from(gateway))) };
+        routes.push((format!("{}/{}", Ipv6Addr::from(destination), prefix), gateway, fields[9].to_string(), metric));
+    }
+    routes
+}
+
+/// Routing table with the default routes first, their destination marked "default"
+pub fn get_routes() -> Records {
+    let mut routes = read_routes();
+    let is_default = |destination: &str| destination == "0.0.0.0/0" || destination == "::/0";
+    // Stable, so the rest keep the kernel's order
+    routes.sort_by_key(|(destination, _, _, _)| !is_default(destination));
+    let mut records = Records::new(&["DESTINATION", "GATEWAY", "INTERFACE", "METRIC"]);
+    for (destination, gateway, interface, metric) in routes {
+        let destination = if is_default(&destination) { format!("default ({})", destination) } else { destination };
+        let gateway = gateway.map(|g| g.to_string()).unwrap_or_else(|| "-".to_string());
+        records.push(vec![destination, gateway, interface, metric.to_string()]);
+    }
+    records
+}
+
+/// (address, interface, MAC, state) from `ip neigh`, which knows the NUD states and IPv6 neighbours
+fn read_ip_neighbours() -> Option<Vec<(String, String, String, String)>> {
+    let output = Command::new("ip").args(["neigh", "show"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(
+        stdout
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let address = fields.first()?.to_string();
+                let value_of = |key: &str| fields.iter().position(|f| *f == key).and_then(|i| fields.get(i + 1)).map(|v| v.to_string());
+                // The state is the last word, possibly followed by "router"/"proxy" markers
+                let state = fields.iter().rev().find(|f| f.chars().all(|c| c.is_ascii_uppercase())).map(|s| s.to_string()).unwrap_or_default();
+                Some((address, value_of("dev").unwrap_or_default(), value_of("lladdr").unwrap_or_else(|| "-".to_string()), state))
+            })
+            .collect(),
+    )
+}
+
+/// IPv4 neighbours from /proc/net/arp, which only knows complete/incomplete
+fn read_proc_arp() -> Vec<(String, String, String, String)> {
+    fs::read_to_string("/proc/net/arp")
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            // ATF_COM
+            let complete = u32::from_str_radix(fields[2].trim_start_matches("0x"), 16).map(|f| f & 0x2 != 0).unwrap_or(false);
+            let state = if complete { "COMPLETE" } else { "INCOMPLETE" };
+            Some((fields[0].to_string(), fields[5].to_string(), fields[3].to_string(), state.to_string()))
+        })
+        .collect()
+}
+
+/// IP-to-MAC neighbour entries with their state
+pub fn get_neighbours() -> Records {
+    let mut records = Records::new(&["Address", "Interface", "MAC", "State"]);
+    for (address, interface, mac, state) in read_ip_neighbours().unwrap_or_else(read_proc_arp) {
+        records.push(vec![address, interface, mac, state]);
+    }
+    records
+}
@@ -0,0 +1,15 @@
+use notify_rust::{Notification, Urgency};
+use std::thread;
+
+/// Raises a desktop notification without holding up the UI; failures (no notification daemon,
+/// no session bus over SSH) are ignored since the same event is also shown in the app
+pub fn desktop(summary: &str, body: &str, critical: bool) {
+    let mut notification = Notification::new();
+    notification.appname("proclynx").summary(summary).body(body);
+    if critical {
+        notification.urgency(Urgency::Critical);
+    }
+    thread::spawn(move || {
+        let _ = notification.show();
+    });
+}
@@ -0,0 +1,115 @@
+use crate::chart;
+use crate::history::History;
+use crate::records::Records;
+use nix::unistd::{sysconf, SysconfVar};
+use std::{collections::HashMap, fs, time::Instant};
+
+/// Width of the history sparklines in `paging`
+const CHART_WIDTH: usize = 60;
+
+/// (pswpin, pswpout, pgmajfault) from /proc/vmstat
+fn read_system() -> (u64, u64, u64) {
+    let mut counters = (0, 0, 0);
+    for line in fs::read_to_string("/proc/vmstat").unwrap_or_default().lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(key), Some(value)) = (fields.next(), fields.next().and_then(|v| v.parse::<u64>().ok())) else {
+            continue;
+        };
+        match key {
+            "pswpin" => counters.0 = value,
+            "pswpout" => counters.1 = value,
+            "pgmajfault" => counters.2 = value,
+            _ => {}
+        }
+    }
+    counters
+}
+
+/// Major faults of a process from /proc/<pid>/stat
+fn read_major_faults(pid: i32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // comm may contain spaces and parentheses, so count fields from the last ')'; majflt is field 12
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(9)?.parse::<u64>().ok()
+}
+
+/// Turns the swap and major fault counters into per-second rates between samples
+pub struct PagingTracker {
+    prev: (u64, u64, u64),
+    prev_processes: HashMap<i32, u64>,
+    at: Instant,
+    /// KiB swapped in per second
+    pub swap_in: f64,
+    /// KiB swapped out per second
+    pub swap_out: f64,
+    /// Major page faults per second, system-wide
+    pub major_faults: f64,
+    /// Major page faults per second, keyed by PID
+    pub process_faults: HashMap<i32, f64>,
+}
+
+impl Default for PagingTracker {
+    fn default() -> PagingTracker {
+        PagingTracker {
+            prev: read_system(),
+            prev_processes: HashMap::new(),
+            at: Instant::now(),
+            swap_in: 0.0,
+            swap_out: 0.0,
+            major_faults: 0.0,
+            process_faults: HashMap::new(),
+        }
+    }
+}
+
+impl PagingTracker {
+    pub fn update(&mut self) {
+        let secs = self.at.elapsed().as_secs_f64().max(0.001);
+        let page_kib = sysconf(SysconfVar::PAGE_SIZE).ok().flatten().unwrap_or(4096) as f64 / 1024.0;
+        let rate = |cur: u64, prev: u64| cur.saturating_sub(prev) as f64 / secs;
+        let now = read_system();
+        self.swap_in = rate(now.0, self.prev.0) * page_kib;
+        self.swap_out = rate(now.1, self.prev.1) * page_kib;
+        self.major_faults = rate(now.2, self.prev.2);
+        self.prev = now;
+
+        let pids: Vec<i32> = fs::read_dir("/proc")
+            .map(|entries| entries.filter_map(|e| e.ok()?.file_name().to_str()?.parse::<i32>().ok()).collect())
+            .unwrap_or_default();
+        let processes: HashMap<i32, u64> = pids.into_iter().filter_map(|pid| Some((pid, read_major_faults(pid)?))).collect();
+        self.process_faults = processes
+            .iter()
+            .filter_map(|(pid, faults)| Some((*pid, rate(*faults, *self.prev_processes.get(pid)?))))
+            .collect();
+        self.prev_processes = processes;
+        self.at = Instant::now();
+    }
+}
+
+/// Current swap and fault rates, their history as sparklines, and the processes faulting the most
+pub fn get_paging_information(tracker: &PagingTracker, history: &History) -> Vec<String> {
+    let mut vec: Vec<String> = vec![];
+    let series = |f: fn(&crate::rules::Snapshot) -> f64| history.samples.iter().map(f).collect::<Vec<f64>>();
+    let swap_in = series(|s| s.swap_in);
+    let swap_out = series(|s| s.swap_out);
+    let faults = series(|s| s.major_faults);
+    let peak = |values: &[f64]| values.iter().copied().fold(0.0, f64::max);
+    let mut rates = Records::new(&["", "now", "peak", "history"]);
+    for (label, now, series) in [("swap in (KiB/s)", tracker.swap_in, &swap_in), ("swap out (KiB/s)", tracker.swap_out, &swap_out), ("major faults/s", tracker.major_faults, &faults)] {
+        rates.push(vec![label.to_string(), format!("{:.1}", now), format!("{:.1}", peak(series)), chart::sparkline(series, CHART_WIDTH)]);
+    }
+    vec.extend(rates.lines());
+    vec.push(String::new());
+    let mut processes: Vec<(&i32, &f64)> = tracker.process_faults.iter().filter(|(_, rate)| **rate > 0.0).collect();
+    processes.sort_by(|a, b| b.1.total_cmp(a.1));
+    let mut faulting = Records::new(&["PID", "COMMAND", "MAJFLT/s"]);
+    for (pid, rate) in processes.iter().take(15) {
+        let name = fs::read_to_string(format!("/proc/{}/comm", pid)).map(|c| c.trim().to_string()).unwrap_or_default();
+        faulting.push(vec![pid.to_string(), name, format!("{:.1}", rate)]);
+    }
+    vec.extend(faulting.lines());
+    if processes.is_empty() {
+        vec.push("No process took a major fault in the last interval".to_string());
+    }
+    vec
+}
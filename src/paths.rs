@@ -0,0 +1,36 @@
+use std::{env, path::PathBuf};
+
+/// `$XDG_CONFIG_HOME/proclynx`, falling back to `~/.config/proclynx`
+pub fn config_dir() -> PathBuf {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".config"),
+    };
+    base.join("proclynx")
+}
+
+/// `$XDG_STATE_HOME/proclynx`, falling back to `~/.local/state/proclynx`
+pub fn state_dir() -> PathBuf {
+    let base = match env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".local/state"),
+    };
+    base.join("proclynx")
+}
+
+/// `$XDG_DATA_HOME/proclynx`, falling back to `~/.local/share/proclynx`
+pub fn data_dir() -> PathBuf {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".local/share"),
+    };
+    base.join("proclynx")
+}
+
+/// Control socket of the running instance: `$XDG_RUNTIME_DIR/proclynx.sock`, or one per user in /tmp
+pub fn control_socket() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("proclynx.sock"),
+        _ => PathBuf::from(format!("/tmp/proclynx-{}.sock", nix::unistd::getuid())),
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// Measured when no duration is given
+pub const DEFAULT_SECONDS: u64 = 5;
+
+const EVENTS: &str = "cycles,instructions,cache-references,cache-misses,branches,branch-misses";
+
+/// `perf stat` counting hardware events of a process for a fixed time
+pub struct PerfStat {
+    pid: i32,
+    duration: Duration,
+    started: Instant,
+    child: Child,
+    result: Option<Vec<String>>,
+}
+
+/// Event counts from `perf stat -x,` output; unsupported or uncounted events are left out
+fn parse_counts(text: &str) -> HashMap<String, f64> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let value = fields.first()?.parse::<f64>().ok()?;
+            // The event name may carry a modifier (cycles:u) or a PMU prefix (cpu_core/cycles/)
+            let event = fields.get(2)?.trim_end_matches('/').rsplit('/').next()?.split(':').next()?;
+            Some((event.to_string(), value))
+        })
+        .collect()
+}
+
+fn ratio(counts: &HashMap<String, f64>, numerator: &str, denominator: &str) -> Option<f64> {
+    let denominator = *counts.get(denominator).filter(|d| **d > 0.0)?;
+    Some(counts.get(numerator)? / denominator)
+}
+
+fn format_count(counts: &HashMap<String, f64>, event: &str) -> String {
+    counts.get(event).map(|v| format!("{:.0}", v)).unwrap_or_else(|| "not supported".to_string())
+}
+
+impl PerfStat {
+    /// Reading another user's counters takes root or kernel.perf_event_paranoid at 0 or below
+    pub fn start(pid: i32, seconds: u64) -> io::Result<PerfStat> {
+        // perf counts the process for as long as the sleep runs
+        let child = Command::new("perf")
+            .args(["stat", "-x", ",", "-e", EVENTS, "-p", &pid.to_string(), "--", "sleep", &seconds.to_string()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        Ok(PerfStat { pid, duration: Duration::from_secs(seconds), started: Instant::now(), child, result: None })
+    }
+
+    pub fn finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    fn report(&mut self) -> Vec<String> {
+        let mut text = String::new();
+        if let Some(stderr) = &mut self.child.stderr {
+            let _ = stderr.read_to_string(&mut text);
+        }
+        let counts = parse_counts(&text);
+        if counts.is_empty() {
+            let mut vec: Vec<String> = vec![format!("perfstat: no counters read for PID {}", self.pid)];
+            vec.extend(text.lines().filter(|l| !l.trim().is_empty()).map(|l| l.to_string()));
+            return vec;
+        }
+        let mut vec: Vec<String> = vec![format!("PID {} over {}s", self.pid, self.duration.as_secs())];
+        vec.push(format!("{:<24} {}", "cycles", format_count(&counts, "cycles")));
+        vec.push(format!("{:<24} {}", "instructions", format_count(&counts, "instructions")));
+        match ratio(&counts, "instructions", "cycles") {
+            Some(ipc) => vec.push(format!("{:<24} {:.2}", "IPC", ipc)),
+            None => vec.push(format!("{:<24} -", "IPC")),
+        }
+        vec.push(format!("{:<24} {}", "cache references", format_count(&counts, "cache-references")));
+        match ratio(&counts, "cache-misses", "cache-references") {
+            Some(rate) => vec.push(format!("{:<24} {} ({:.2}% of references)", "cache misses", format_count(&counts, "cache-misses"), rate * 100.0)),
+            None => vec.push(format!("{:<24} {}", "cache misses", format_count(&counts, "cache-misses"))),
+        }
+        vec.push(format!("{:<24} {}", "branches", format_count(&counts, "branches")));
+        match ratio(&counts, "branch-misses", "branches") {
+            Some(rate) => vec.push(format!("{:<24} {} ({:.2}% of branches)", "branch mispredictions", format_count(&counts, "branch-misses"), rate * 100.0)),
+            None => vec.push(format!("{:<24} {}", "branch mispredictions", format_count(&counts, "branch-misses"))),
+        }
+        vec
+    }
+
+    /// A countdown while perf runs, then the report
+    pub fn update(&mut self) -> Vec<String> {
+        if let Some(result) = &self.result {
+            return result.clone();
+        }
+        match self.child.try_wait() {
+            Ok(Some(_)) => {
+                let result = self.report();
+                self.result = Some(result.clone());
+                result
+            },
+            Ok(None) => {
+                let left = self.duration.saturating_sub(self.started.elapsed()).as_secs();
+                vec![format!("Counting hardware events of PID {}, {}s left...", self.pid, left)]
+            },
+            Err(e) => {
+                self.result = Some(vec![format!("Error waiting for perf: {}", e)]);
+                self.result.clone().unwrap()
+            },
+        }
+    }
+}
+
+impl Drop for PerfStat {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
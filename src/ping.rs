@@ -0,0 +1,157 @@
+use crate::chart;
+use std::{
+    io::{self, BufRead, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Probes kept for the sparkline and the summary
+const SAMPLES: usize = 300;
+
+/// Port timed by the TCP fallback when ICMP is unavailable
+const TCP_PORT: u16 = 443;
+
+enum Probe {
+    /// Round trip in milliseconds
+    Reply(f64),
+    Lost,
+    Error(String),
+}
+
+/// A running `ping <host>`: the system ping when present, timed TCP connects otherwise
+pub struct Ping {
+    host: String,
+    method: String,
+    child: Option<Child>,
+    probes: Receiver<Probe>,
+    samples: Vec<Option<f64>>,
+    error: Option<String>,
+}
+
+fn parse_ping_line(line: &str) -> Option<Probe> {
+    if let Some((_, rest)) = line.split_once("time=") {
+        return rest.split_whitespace().next()?.parse::<f64>().ok().map(Probe::Reply);
+    }
+    if line.starts_with("no answer yet") {
+        return Some(Probe::Lost);
+    }
+    if line.starts_with("ping:") {
+        return Some(Probe::Error(line.to_string()));
+    }
+    None
+}
+
+fn tcp_probe(host: String, tx: Sender<Probe>) {
+    loop {
+        let started = Instant::now();
+        let probe = match (host.as_str(), TCP_PORT).to_socket_addrs().map(|mut addrs| addrs.next()) {
+            Ok(Some(addr)) => match TcpStream::connect_timeout(&addr, Duration::from_secs(1)) {
+                Ok(_) => Probe::Reply(started.elapsed().as_secs_f64() * 1000.0),
+                Err(_) => Probe::Lost,
+            },
+            Ok(None) => Probe::Error(format!("{}: no address", host)),
+            Err(e) => Probe::Error(format!("{}: {}", host, e)),
+        };
+        let fatal = matches!(probe, Probe::Error(_));
+        if tx.send(probe).is_err() || fatal {
+            return;
+        }
+        thread::sleep(Duration::from_secs(1).saturating_sub(started.elapsed()));
+    }
+}
+
+impl Ping {
+    pub fn start(host: &str) -> Ping {
+        let (tx, rx) = mpsc::channel();
+        // -O reports every unanswered probe, which is how loss shows up
+        let spawned = Command::new("ping").args(["-n", "-O", "-i", "1", host]).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+        let (method, child) = match spawned {
+            Ok(mut child) => {
+                let stdout = child.stdout.take().unwrap();
+                let stderr = child.stderr.take().unwrap();
+                let err_tx = tx.clone();
+                thread::spawn(move || {
+                    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        if let Some(probe) = parse_ping_line(&line) {
+                            if tx.send(probe).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                });
+                thread::spawn(move || {
+                    for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                        if err_tx.send(Probe::Error(line)).is_err() {
+                            break;
+                        }
+                    }
+                });
+                ("ICMP".to_string(), Some(child))
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let host = host.to_string();
+                thread::spawn(move || tcp_probe(host, tx));
+                (format!("TCP connect to port {}", TCP_PORT), None)
+            },
+            Err(e) => {
+                let _ = tx.send(Probe::Error(format!("Error running ping: {}", e)));
+                ("ICMP".to_string(), None)
+            },
+        };
+        Ping { host: host.to_string(), method, child, probes: rx, samples: vec![], error: None }
+    }
+
+    /// Takes in the probes answered since the last call and renders the graph and summary
+    pub fn update(&mut self, width: usize) -> Vec<String> {
+        for probe in self.probes.try_iter() {
+            match probe {
+                Probe::Reply(ms) => self.samples.push(Some(ms)),
+                Probe::Lost => self.samples.push(None),
+                Probe::Error(e) => self.error = Some(e),
+            }
+        }
+        if self.samples.len() > SAMPLES {
+            self.samples.drain(..self.samples.len() - SAMPLES);
+        }
+        let mut vec: Vec<String> = vec![];
+        vec.push(format!("ping {} ({}), Esc to stop", self.host, self.method));
+        if let Some(error) = &self.error {
+            vec.push(error.clone());
+        }
+        let replies: Vec<f64> = self.samples.iter().flatten().copied().collect();
+        if self.samples.is_empty() {
+            vec.push("Waiting for replies...".to_string());
+            return vec;
+        }
+        // Lost probes draw as the lowest bar; the loss figure below tells them apart
+        let values: Vec<f64> = self.samples.iter().map(|s| s.unwrap_or(0.0)).collect();
+        vec.push(chart::sparkline(&values, width));
+        let lost = self.samples.len() - replies.len();
+        let loss = lost as f64 * 100.0 / self.samples.len() as f64;
+        if replies.is_empty() {
+            vec.push(format!("{} sent, {} lost ({:.0}% loss)", self.samples.len(), lost, loss));
+        } else {
+            let min = replies.iter().copied().fold(f64::MAX, f64::min);
+            let max = replies.iter().copied().fold(0.0, f64::max);
+            let avg = replies.iter().sum::<f64>() / replies.len() as f64;
+            let last = match self.samples.last() {
+                Some(Some(ms)) => format!("{:.1} ms", ms),
+                _ => "lost".to_string(),
+            };
+            vec.push(format!("last {}  min/avg/max {:.1}/{:.1}/{:.1} ms  {} sent, {:.0}% loss", last, min, avg, max, self.samples.len(), loss));
+        }
+        vec
+    }
+}
+
+impl Drop for Ping {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,99 @@
+/// Lines kept by `head`/`tail` without a count
+const DEFAULT_COUNT: usize = 10;
+
+/// A filter applied to a command's output after `|`
+#[derive(Clone)]
+pub enum Stage {
+    /// Lines containing `pattern`, or not containing it when `invert`
+    Grep { pattern: String, invert: bool, ignore_case: bool },
+    Head(usize),
+    Tail(usize),
+}
+
+impl Stage {
+    /// Parses one `grep [-v] [-i] <pattern>`, `head [n]` or `tail [n]` segment
+    pub fn parse(segment: &str) -> Result<Stage, String> {
+        let words: Vec<&str> = segment.split_whitespace().collect();
+        let count = |args: &[&str]| match args {
+            [] => Ok(DEFAULT_COUNT),
+            // `head -n 5` and `head -5` as well as `head 5`
+            ["-n", n] | [n] => n.trim_start_matches('-').parse::<usize>().map_err(|_| format!("{}: invalid count '{}'", words[0], n)),
+            _ => Err(format!("usage: {} [n]", words[0])),
+        };
+        match words.split_first() {
+            Some((&"grep", args)) => {
+                let mut invert = false;
+                let mut ignore_case = false;
+                let mut pattern: Vec<&str> = vec![];
+                for arg in args {
+                    match *arg {
+                        "-v" if pattern.is_empty() => invert = true,
+                        "-i" if pattern.is_empty() => ignore_case = true,
+                        "-vi" | "-iv" if pattern.is_empty() => (invert, ignore_case) = (true, true),
+                        word => pattern.push(word),
+                    }
+                }
+                if pattern.is_empty() {
+                    return Err("usage: grep [-v] [-i] <pattern>".to_string());
+                }
+                let pattern = pattern.join(" ");
+                let pattern = if ignore_case { pattern.to_lowercase() } else { pattern };
+                Ok(Stage::Grep { pattern, invert, ignore_case })
+            },
+            Some((&"head", args)) => Ok(Stage::Head(count(args)?)),
+            Some((&"tail", args)) => Ok(Stage::Tail(count(args)?)),
+            Some((other, _)) => Err(format!("cannot pipe into '{}'; use grep, head or tail", other)),
+            None => Err("empty pipe stage".to_string()),
+        }
+    }
+}
+
+/// Runs `items` through every stage in order, matching on the text `line` gives for each item
+pub fn apply<T>(stages: &[Stage], mut items: Vec<T>, line: impl Fn(&T) -> String) -> Vec<T> {
+    for stage in stages {
+        match stage {
+            Stage::Grep { pattern, invert, ignore_case } => items.retain(|item| {
+                let text = line(item);
+                let found = if *ignore_case { text.to_lowercase().contains(pattern.as_str()) } else { text.contains(pattern.as_str()) };
+                found != *invert
+            }),
+            Stage::Head(n) => items.truncate(*n),
+            Stage::Tail(n) => {
+                let skip = items.len().saturating_sub(*n);
+                items.drain(..skip);
+            },
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_grep_flags_before_the_pattern() {
+        assert!(matches!(Stage::parse(" grep ssh "), Ok(Stage::Grep { pattern, invert: false, ignore_case: false }) if pattern == "ssh"));
+        assert!(matches!(Stage::parse("grep -v -i SSH"), Ok(Stage::Grep { pattern, invert: true, ignore_case: true }) if pattern == "ssh"));
+        assert!(matches!(Stage::parse("grep -iv a"), Ok(Stage::Grep { invert: true, ignore_case: true, .. })));
+        // Flags after the pattern are part of it
+        assert!(matches!(Stage::parse("grep a -v b"), Ok(Stage::Grep { pattern, invert: false, .. }) if pattern == "a -v b"));
+        assert!(Stage::parse("grep -v").is_err());
+    }
+
+    #[test]
+    fn parse_head_and_tail_counts() {
+        assert!(matches!(Stage::parse("head"), Ok(Stage::Head(DEFAULT_COUNT))));
+        assert!(matches!(Stage::parse("head 5"), Ok(Stage::Head(5))));
+        assert!(matches!(Stage::parse("head -5"), Ok(Stage::Head(5))));
+        assert!(matches!(Stage::parse("tail -n 3"), Ok(Stage::Tail(3))));
+        assert_eq!(Stage::parse("head x").err(), Some("head: invalid count 'x'".to_string()));
+        assert_eq!(Stage::parse("tail 1 2").err(), Some("usage: tail [n]".to_string()));
+    }
+
+    #[test]
+    fn parse_refuses_other_commands_and_empty_stages() {
+        assert!(Stage::parse("sort").is_err());
+        assert_eq!(Stage::parse("  ").err(), Some("empty pipe stage".to_string()));
+    }
+}
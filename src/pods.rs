@@ -0,0 +1,83 @@
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    api::{Api, ApiResource, DynamicObject, GroupVersionKind, ListParams},
+    Client,
+};
+use crate::records::Records;
+use pretty_bytes::converter::convert;
+use std::collections::HashMap;
+
+/// CPU quantity ("250m", "1", "1234567n") in cores
+fn parse_cpu(quantity: &str) -> f64 {
+    let (number, scale) = match quantity.char_indices().last() {
+        Some((i, 'n')) => (&quantity[..i], 1e-9),
+        Some((i, 'u')) => (&quantity[..i], 1e-6),
+        Some((i, 'm')) => (&quantity[..i], 1e-3),
+        _ => (quantity, 1.0),
+    };
+    number.parse::<f64>().unwrap_or(0.0) * scale
+}
+
+/// Memory quantity ("128Mi", "1G", "2048Ki", plain bytes) in bytes
+fn parse_memory(quantity: &str) -> u64 {
+    let suffixes: [(&str, f64); 8] =
+        [("Ki", 1024.0), ("Mi", 1048576.0), ("Gi", 1073741824.0), ("Ti", 1099511627776.0), ("k", 1e3), ("M", 1e6), ("G", 1e9), ("T", 1e12)];
+    for (suffix, scale) in suffixes {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return (number.parse::<f64>().unwrap_or(0.0) * scale) as u64;
+        }
+    }
+    quantity.parse::<u64>().unwrap_or(0)
+}
+
+/// Cores and bytes used by all containers of a pod, from a metrics.k8s.io PodMetrics object
+fn sum_usage(metrics: &DynamicObject) -> (f64, u64) {
+    let containers = metrics.data["containers"].as_array().cloned().unwrap_or_default();
+    containers.iter().fold((0.0, 0), |(cpu, mem), c| {
+        (
+            cpu + c["usage"]["cpu"].as_str().map(parse_cpu).unwrap_or(0.0),
+            mem + c["usage"]["memory"].as_str().map(parse_memory).unwrap_or(0),
+        )
+    })
+}
+
+async fn list_pods(namespace: Option<&str>) -> Result<(Records, bool), kube::Error> {
+    let client = Client::try_default().await?;
+    let gvk = GroupVersionKind::gvk("metrics.k8s.io", "v1beta1", "PodMetrics");
+    let resource = ApiResource::from_gvk_with_plural(&gvk, "pods");
+    let (pods, metrics): (Api<Pod>, Api<DynamicObject>) = match namespace {
+        Some(ns) => (Api::namespaced(client.clone(), ns), Api::namespaced_with(client, ns, &resource)),
+        None => (Api::all(client.clone()), Api::all_with(client, &resource)),
+    };
+    let pods = pods.list(&ListParams::default()).await?;
+    // Without metrics-server the pods are still worth listing, just without usage
+    let usage: Option<HashMap<(String, String), (f64, u64)>> = metrics.list(&ListParams::default()).await.ok().map(|list| {
+        list.items
+            .iter()
+            .map(|m| ((m.metadata.namespace.clone().unwrap_or_default(), m.metadata.name.clone().unwrap_or_default()), sum_usage(m)))
+            .collect()
+    });
+    let mut records = Records::new(&["NAMESPACE", "NAME", "PHASE", "RESTARTS", "CPU(m)", "MEM", "NODE"]);
+    for pod in &pods.items {
+        let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+        let name = pod.metadata.name.clone().unwrap_or_default();
+        let status = pod.status.as_ref();
+        let phase = status.and_then(|s| s.phase.clone()).unwrap_or_else(|| "Unknown".to_string());
+        let restarts: i32 = status.and_then(|s| s.container_statuses.as_ref()).map(|cs| cs.iter().map(|c| c.restart_count).sum()).unwrap_or(0);
+        let node = pod.spec.as_ref().and_then(|s| s.node_name.clone()).unwrap_or_default();
+        let (cpu, mem) = match usage.as_ref().and_then(|u| u.get(&(namespace.clone(), name.clone()))) {
+            Some((cpu, mem)) => (format!("{:.0}", cpu * 1000.0), convert(*mem as f64)),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        records.push(vec![namespace, name, phase, restarts.to_string(), cpu, mem, node]);
+    }
+    Ok((records, usage.is_some()))
+}
+
+/// Pods of a namespace, or of every namespace, with phase, restarts and usage from metrics-server,
+/// and whether metrics-server answered. The cluster is found the way kubectl finds it: KUBECONFIG,
+/// ~/.kube/config or the in-cluster service account.
+pub fn get_pods(namespace: Option<&str>) -> Result<(Records, bool), String> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| format!("pods: {}", e))?;
+    runtime.block_on(list_pods(namespace)).map_err(|e| format!("pods: {}", e))
+}
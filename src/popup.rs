@@ -0,0 +1,82 @@
+use crate::theme::Theme;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+/// Share of the screen a popup covers, in percent of each side; columns get more room to fit
+const SIZE: u16 = 70;
+const COLUMNS_SIZE: u16 = 90;
+
+/// Widest a column of entries gets before they are cut
+const MAX_COLUMN: usize = 40;
+
+/// Text shown in a box over everything else until dismissed with Esc
+pub struct Popup {
+    pub title: String,
+    pub body: Body,
+}
+
+pub enum Body {
+    /// Lines shown as they are, wrapped at the edge
+    Text(Vec<String>),
+    /// Titled sections of short entries, each laid out in as many columns as fit
+    Columns(Vec<(String, Vec<String>)>),
+}
+
+/// The middle `size` percent of `area`
+fn centered(area: Rect, size: u16) -> Rect {
+    let width = area.width * size / 100;
+    let height = area.height * size / 100;
+    Rect { x: area.x + (area.width - width) / 2, y: area.y + (area.height - height) / 2, width, height }
+}
+
+/// `entries` top to bottom, then left to right, in columns no wider than `width`
+fn columns(entries: &[String], width: usize) -> Vec<Spans<'static>> {
+    let column = entries.iter().map(|e| e.chars().count()).max().unwrap_or(0).min(MAX_COLUMN) + 2;
+    let count = (width / column).max(1);
+    let rows = entries.len().div_ceil(count);
+    (0..rows)
+        .map(|row| {
+            let cells: Vec<String> = (0..count)
+                .filter_map(|col| entries.get(col * rows + row))
+                .map(|entry| {
+                    let entry: String = if entry.chars().count() > MAX_COLUMN {
+                        entry.chars().take(MAX_COLUMN - 1).chain(std::iter::once('…')).collect()
+                    } else {
+                        entry.clone()
+                    };
+                    format!("{:<column$}", entry, column = column)
+                })
+                .collect();
+            Spans::from(cells.concat())
+        })
+        .collect()
+}
+
+pub fn render<B: Backend>(f: &mut Frame<B>, popup: &Popup, theme: &Theme) {
+    let area = centered(f.size(), if matches!(popup.body, Body::Columns(_)) { COLUMNS_SIZE } else { SIZE });
+    let block = Block::default().borders(Borders::ALL).border_style(theme.border()).title(format!("{} (Esc to close)", popup.title));
+    let text: Vec<Spans> = match &popup.body {
+        Body::Text(lines) => lines.iter().map(|line| Spans::from(line.clone())).collect(),
+        Body::Columns(sections) => {
+            let width = block.inner(area).width as usize;
+            let mut text: Vec<Spans> = vec![];
+            for (title, entries) in sections {
+                if !text.is_empty() {
+                    text.push(Spans::default());
+                }
+                text.push(Spans::from(Span::styled(title.clone(), Style::default().add_modifier(Modifier::BOLD))));
+                text.extend(columns(entries, width));
+            }
+            text
+        },
+    };
+    let paragraph = Paragraph::new(text).block(block).style(theme.text()).wrap(Wrap { trim: false });
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
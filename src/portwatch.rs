@@ -0,0 +1,72 @@
+use crate::sockets::{self, Socket};
+use std::collections::BTreeSet;
+
+/// A port `portwatch` reports on, with the protocols it was listening on at the last check
+pub struct PortWatch {
+    pub port: u16,
+    pub listening: BTreeSet<&'static str>,
+}
+
+#[derive(Default)]
+pub struct PortWatcher {
+    pub list: Vec<PortWatch>,
+}
+
+fn listening_on(sockets: &[Socket], port: u16) -> BTreeSet<&'static str> {
+    sockets.iter().filter(|s| s.listening() && s.local.port() == port).map(|s| s.proto).collect()
+}
+
+impl PortWatch {
+    pub fn describe(&self) -> String {
+        if self.listening.is_empty() {
+            format!("port {}: closed", self.port)
+        } else {
+            format!("port {}: listening on {}", self.port, self.listening.iter().copied().collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+impl PortWatcher {
+    /// Starts watching `port`, returning its current state
+    pub fn add(&mut self, port: u16) -> String {
+        if let Some(watch) = self.list.iter().find(|w| w.port == port) {
+            return format!("Already watching {}", watch.describe());
+        }
+        let watch = PortWatch { port, listening: listening_on(&sockets::read_sockets(), port) };
+        let state = format!("Watching {}", watch.describe());
+        self.list.push(watch);
+        state
+    }
+
+    pub fn remove(&mut self, port: u16) -> bool {
+        let before = self.list.len();
+        self.list.retain(|w| w.port != port);
+        self.list.len() != before
+    }
+
+    /// One message per watched port that started or stopped listening on some protocol
+    pub fn check(&mut self) -> Vec<String> {
+        if self.list.is_empty() {
+            return vec![];
+        }
+        let sockets = sockets::read_sockets();
+        let mut events: Vec<String> = vec![];
+        for watch in &mut self.list {
+            let now = listening_on(&sockets, watch.port);
+            for proto in now.difference(&watch.listening) {
+                // Only resolve owners on a change: walking every process's fds is not cheap
+                let owners: Vec<String> = sockets::port_owners(watch.port).iter().map(|pid| pid.to_string()).collect();
+                if owners.is_empty() {
+                    events.push(format!("portwatch: {}/{} opened", watch.port, proto));
+                } else {
+                    events.push(format!("portwatch: {}/{} opened by PID {}", watch.port, proto, owners.join(", ")));
+                }
+            }
+            for proto in watch.listening.difference(&now) {
+                events.push(format!("portwatch: {}/{} closed", watch.port, proto));
+            }
+            watch.listening = now;
+        }
+        events
+    }
+}
@@ -0,0 +1,40 @@
+use crate::records::Records;
+use std::fs;
+
+/// Hugepage counters worth showing from /proc/meminfo
+const HUGEPAGE_FIELDS: [&str; 8] = ["HugePages_Total", "HugePages_Free", "HugePages_Rsvd", "HugePages_Surp", "Hugepagesize", "Hugetlb", "AnonHugePages", "ShmemHugePages"];
+
+/// Stall averages from /proc/pressure (PSI), followed by hugepage usage from /proc/meminfo with
+/// the counter as the kind and its value as the total
+pub fn get_pressure_information() -> Records {
+    let mut records = Records::new(&["RESOURCE", "KIND", "AVG10", "AVG60", "AVG300", "TOTAL"]);
+    let dash = || "-".to_string();
+    for resource in ["cpu", "memory", "io"] {
+        let pressure = match fs::read_to_string(format!("/proc/pressure/{}", resource)) {
+            Ok(pressure) => pressure,
+            Err(e) => {
+                records.push(vec![resource.to_string(), dash(), dash(), dash(), dash(), format!("unavailable: {} (needs a kernel with CONFIG_PSI)", e)]);
+                continue;
+            },
+        };
+        // "some avg10=0.00 avg60=0.00 avg300=0.00 total=12345"
+        for line in pressure.lines() {
+            let mut fields = line.split_whitespace();
+            let Some(kind) = fields.next() else {
+                continue;
+            };
+            let values: Vec<&str> = fields.filter_map(|f| f.split_once('=').map(|(_, v)| v)).collect();
+            if let [avg10, avg60, avg300, total] = values[..] {
+                let total = total.parse::<u64>().map(|us| format!("{:.1} s stalled", us as f64 / 1e6)).unwrap_or_else(|_| total.to_string());
+                records.push(vec![resource.to_string(), kind.to_string(), format!("{}%", avg10), format!("{}%", avg60), format!("{}%", avg300), total]);
+            }
+        }
+    }
+    let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    for field in HUGEPAGE_FIELDS {
+        if let Some(value) = meminfo.lines().find_map(|line| line.strip_prefix(field).and_then(|rest| rest.strip_prefix(':'))) {
+            records.push(vec!["hugepages".to_string(), field.to_string(), dash(), dash(), dash(), value.trim().to_string()]);
+        }
+    }
+    records
+}
@@ -0,0 +1,54 @@
+use std::{collections::HashMap, fs, time::Instant};
+
+/// Cumulative (read_bytes, write_bytes) of a process from /proc/<pid>/io, if we may read it
+pub fn read_io(pid: i32) -> Option<(u64, u64)> {
+    let io = fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+    let mut read = None;
+    let mut write = None;
+    for line in io.lines() {
+        match line.split_once(':') {
+            Some(("read_bytes", value)) => read = value.trim().parse::<u64>().ok(),
+            Some(("write_bytes", value)) => write = value.trim().parse::<u64>().ok(),
+            _ => {}
+        }
+    }
+    Some((read?, write?))
+}
+
+/// Turns the cumulative per-process I/O counters into bytes-per-second rates between samples
+pub struct IoTracker {
+    prev: HashMap<i32, (u64, u64)>,
+    at: Instant,
+    /// (read, write) bytes per second over the last interval, keyed by PID
+    pub rates: HashMap<i32, (f64, f64)>,
+}
+
+impl Default for IoTracker {
+    fn default() -> IoTracker {
+        IoTracker { prev: HashMap::new(), at: Instant::now(), rates: HashMap::new() }
+    }
+}
+
+impl IoTracker {
+    pub fn update(&mut self) {
+        let secs = self.at.elapsed().as_secs_f64().max(0.001);
+        let mut now: HashMap<i32, (u64, u64)> = HashMap::new();
+        let pids = fs::read_dir("/proc")
+            .map(|entries| entries.filter_map(|e| e.ok()?.file_name().to_str()?.parse::<i32>().ok()).collect())
+            .unwrap_or_else(|_| vec![]);
+        for pid in pids {
+            if let Some(counters) = read_io(pid) {
+                now.insert(pid, counters);
+            }
+        }
+        self.rates = now
+            .iter()
+            .filter_map(|(pid, (read, write))| {
+                let (prev_read, prev_write) = self.prev.get(pid)?;
+                Some((*pid, (read.saturating_sub(*prev_read) as f64 / secs, write.saturating_sub(*prev_write) as f64 / secs)))
+            })
+            .collect();
+        self.prev = now;
+        self.at = Instant::now();
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{scrollbar::Scrollbar, theme::Theme};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use sysinfo::{PidExt, ProcessExt, System, SystemExt};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+
+/// Rows moved by PageUp/PageDown
+const PAGE: isize = 20;
+
+/// Processes under their parents, with branches folded away by Left and opened again by Right
+pub struct ProcessTree {
+    names: HashMap<u32, String>,
+    /// Children of each process in PID order, with the processes that have no parent under 0
+    children: BTreeMap<u32, Vec<u32>>,
+    /// Processes whose children are hidden
+    collapsed: HashSet<u32>,
+    pub state: ListState,
+    /// First row shown when last drawn
+    offset: usize,
+}
+
+impl ProcessTree {
+    pub fn collect(sys: &System) -> ProcessTree {
+        let mut names: HashMap<u32, String> = HashMap::new();
+        let mut children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for (pid, process) in sys.processes() {
+            names.insert(pid.as_u32(), process.name().to_string());
+        }
+        for (pid, process) in sys.processes() {
+            // A parent that has exited leaves its children at the top
+            let parent = process.parent().map(|p| p.as_u32()).filter(|p| names.contains_key(p)).unwrap_or(0);
+            children.entry(parent).or_default().push(pid.as_u32());
+        }
+        for pids in children.values_mut() {
+            pids.sort();
+        }
+        let mut tree = ProcessTree { names, children, collapsed: HashSet::new(), state: ListState::default(), offset: 0 };
+        tree.state.select(if tree.rows().is_empty() { None } else { Some(0) });
+        tree
+    }
+
+    /// Re-reads the processes, keeping the folded branches and the selected process
+    pub fn refresh(&mut self, sys: &System) {
+        let selected = self.selected_pid();
+        let collapsed = std::mem::take(&mut self.collapsed);
+        let offset = self.offset;
+        *self = ProcessTree::collect(sys);
+        self.collapsed = collapsed.into_iter().filter(|pid| self.names.contains_key(pid)).collect();
+        self.offset = offset;
+        let rows = self.rows();
+        let index = selected.and_then(|pid| rows.iter().position(|(p, _)| *p == pid)).or(if rows.is_empty() { None } else { Some(0) });
+        self.state.select(index);
+    }
+
+    /// The processes not folded away, parents before their children, with how deep each one is
+    fn rows(&self) -> Vec<(u32, usize)> {
+        let mut rows: Vec<(u32, usize)> = vec![];
+        let mut stack: Vec<(u32, usize)> = self.children.get(&0).map(|roots| roots.iter().rev().map(|pid| (*pid, 0)).collect()).unwrap_or_default();
+        while let Some((pid, depth)) = stack.pop() {
+            rows.push((pid, depth));
+            if !self.collapsed.contains(&pid) {
+                if let Some(children) = self.children.get(&pid) {
+                    stack.extend(children.iter().rev().map(|child| (*child, depth + 1)));
+                }
+            }
+        }
+        rows
+    }
+
+    pub fn count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, pid: u32) -> &str {
+        self.names.get(&pid).map(|name| name.as_str()).unwrap_or_default()
+    }
+
+    pub fn selected_pid(&self) -> Option<u32> {
+        self.rows().get(self.state.selected()?).map(|(pid, _)| *pid)
+    }
+
+    /// Moves the selection by `delta` rows, stopping at either end
+    pub fn scroll(&mut self, delta: isize) {
+        let len = self.rows().len() as isize;
+        if len == 0 {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.state.select(Some((current + delta).clamp(0, len - 1) as usize));
+    }
+
+    pub fn page(&mut self, down: bool) {
+        self.scroll(if down { PAGE } else { -PAGE });
+    }
+
+    /// Folds the selected branch, or moves up to its parent when it is folded already or has no children
+    pub fn collapse(&mut self) {
+        let Some(pid) = self.selected_pid() else {
+            return;
+        };
+        if self.children.contains_key(&pid) && self.collapsed.insert(pid) {
+            return;
+        }
+        let rows = self.rows();
+        let at = self.state.selected().unwrap_or(0);
+        let depth = rows[at].1;
+        if let Some(parent) = rows[..at].iter().rposition(|(_, d)| *d + 1 == depth) {
+            self.state.select(Some(parent));
+        }
+    }
+
+    /// Opens the selected branch, or moves down to its first child when it is open already
+    pub fn expand(&mut self) {
+        let Some(pid) = self.selected_pid() else {
+            return;
+        };
+        if !self.collapsed.remove(&pid) && self.children.contains_key(&pid) {
+            self.scroll(1);
+        }
+    }
+
+    pub fn contains(&self, pid: u32) -> bool {
+        self.names.contains_key(&pid)
+    }
+
+    /// `pid` and every process under it, deepest first so children are signalled before their parents.
+    /// Empty for a PID not in the tree, which includes the 0 the roots hang under; a PID that would
+    /// turn negative as a `pid_t` is never in it either, since those signal whole process groups.
+    pub fn subtree(&self, pid: u32) -> Vec<u32> {
+        if !self.contains(pid) {
+            return vec![];
+        }
+        let mut pids: Vec<u32> = vec![pid];
+        let mut at = 0;
+        while at < pids.len() {
+            pids.extend(self.children.get(&pids[at]).into_iter().flatten());
+            at += 1;
+        }
+        pids.retain(|p| *p > 0 && i32::try_from(*p).is_ok());
+        pids.reverse();
+        pids
+    }
+
+    fn line(&self, pid: u32, depth: usize) -> String {
+        let marker = match (self.children.contains_key(&pid), self.collapsed.contains(&pid)) {
+            (false, _) => "─",
+            (true, false) => "▾",
+            (true, true) => "▸",
+        };
+        format!("{}{} {} ({})", "  ".repeat(depth), marker, self.name(pid), pid)
+    }
+
+    /// The unfolded rows as indented text, for `copy`, pipes and output outside the TUI
+    pub fn lines(&self) -> Vec<String> {
+        self.rows().into_iter().map(|(pid, depth)| self.line(pid, depth)).collect()
+    }
+}
+
+pub fn render<B: Backend>(f: &mut Frame<B>, area: Rect, tree: &mut ProcessTree, theme: &Theme) {
+    // Scrolled just far enough to keep the selected row in sight
+    let height = area.height.saturating_sub(2) as usize;
+    let selected = tree.state.selected().unwrap_or(0);
+    if selected < tree.offset {
+        tree.offset = selected;
+    } else if height > 0 && selected >= tree.offset + height {
+        tree.offset = selected + 1 - height;
+    }
+    let rows = tree.rows();
+    let items: Vec<ListItem> = rows.iter().skip(tree.offset).take(height).map(|(pid, depth)| ListItem::new(tree.line(*pid, *depth))).collect();
+    let title = format!("Process tree ({} processes, Left/Right to fold)", tree.count());
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(title))
+        .style(theme.text())
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    state.select(tree.state.selected().map(|selected| selected - tree.offset));
+    f.render_stateful_widget(list, area, &mut state);
+    f.render_widget(Scrollbar { top: tree.offset, shown: height, len: rows.len(), style: theme.border() }, Scrollbar::track(area, 0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// init (1) with a shell (10) running sleep (11), and a process whose parent exited (20)
+    fn tree() -> ProcessTree {
+        let names: HashMap<u32, String> = [(1, "init"), (10, "sh"), (11, "sleep"), (20, "orphan")].into_iter().map(|(p, n)| (p, n.to_string())).collect();
+        let children: BTreeMap<u32, Vec<u32>> = [(0, vec![1, 20]), (1, vec![10]), (10, vec![11])].into_iter().collect();
+        ProcessTree { names, children, collapsed: HashSet::new(), state: ListState::default(), offset: 0 }
+    }
+
+    #[test]
+    fn subtree_is_deepest_first() {
+        assert_eq!(tree().subtree(1), vec![11, 10, 1]);
+        assert_eq!(tree().subtree(11), vec![11]);
+    }
+
+    #[test]
+    fn subtree_never_returns_0() {
+        let tree = tree();
+        assert!(tree.subtree(0).is_empty());
+        for pid in [1, 10, 11, 20] {
+            assert!(!tree.subtree(pid).contains(&0));
+        }
+    }
+
+    #[test]
+    fn subtree_of_unknown_pid_is_empty() {
+        assert!(tree().subtree(42).is_empty());
+        assert!(tree().subtree(u32::MAX).is_empty());
+    }
+}
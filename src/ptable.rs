@@ -0,0 +1,502 @@
+use crate::{
+    cgroup,
+    config::ColorRule,
+    gpu,
+    procio::IoTracker,
+    records::{self, Records, GAP},
+    sched::SchedTracker,
+    scrollbar::Scrollbar,
+    theme::Theme,
+};
+use nix::unistd::{getuid, Uid, User};
+use pretty_bytes::converter::convert;
+use serde::Serialize;
+use std::{collections::HashMap, fs};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Margin, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+
+/// Rows moved by PageUp/PageDown
+const PAGE: isize = 20;
+
+/// %CPU from which a row's CPU cell takes the middle, then the high colour of the theme
+const CPU_BANDS: [f32; 2] = [50.0, 90.0];
+
+const COLUMNS: [&str; 9] = ["PID", "USER", "S", "%CPU", "%MEM", "READ/s", "WRITE/s", "RQ ms/s", "COMMAND"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortColumn {
+    Pid,
+    Cpu,
+    Mem,
+    Read,
+    Write,
+    RunQueue,
+    Name,
+}
+
+#[derive(Serialize)]
+pub struct ProcessRow {
+    pub pid: u32,
+    pub uid: u32,
+    pub user: String,
+    /// ps-style state letter (R, S, D, T, Z, ...)
+    pub state: char,
+    /// Whether the process runs in a container; only read when something looks at it, see `probe`
+    pub container: bool,
+    /// Whether the process has a GPU device open; likewise
+    pub gpu: bool,
+    pub cpu: f32,
+    pub mem: f32,
+    pub read: Option<f64>,
+    pub write: Option<f64>,
+    /// Run queue latency in milliseconds per second
+    pub run_queue: Option<f64>,
+    pub name: String,
+    /// Arguments joined by spaces, empty for kernel threads
+    pub cmdline: String,
+}
+
+impl ProcessRow {
+    /// The row as plain text, for filtering with `| grep`
+    pub fn line(&self) -> String {
+        format!("{} {} {} {:.1} {:.1} {}", self.pid, self.user, self.state, self.cpu, self.mem, self.name)
+    }
+}
+
+/// Predefined subsets toggled with a single key on the table view, bound in `keys::ACTIONS`
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    Mine,
+    Root,
+    Container,
+    Zombie,
+    Gpu,
+}
+
+impl Filter {
+    fn label(self) -> &'static str {
+        match self {
+            Filter::Mine => "mine",
+            Filter::Root => "root",
+            Filter::Container => "containers",
+            Filter::Zombie => "zombies",
+            Filter::Gpu => "GPU users",
+        }
+    }
+
+    fn matches(self, row: &ProcessRow) -> bool {
+        match self {
+            Filter::Mine => row.uid == getuid().as_raw(),
+            Filter::Root => row.uid == 0,
+            Filter::Container => row.container,
+            Filter::Zombie => row.state == 'Z',
+            Filter::Gpu => row.gpu,
+        }
+    }
+}
+
+pub fn parse_sort(name: &str) -> Option<SortColumn> {
+    match name {
+        "pid" => Some(SortColumn::Pid),
+        "cpu" => Some(SortColumn::Cpu),
+        "mem" => Some(SortColumn::Mem),
+        "read" => Some(SortColumn::Read),
+        "write" => Some(SortColumn::Write),
+        "rq" => Some(SortColumn::RunQueue),
+        "name" => Some(SortColumn::Name),
+        _ => None,
+    }
+}
+
+/// Parses `[--sort pid|cpu|mem|read|write|rq|name] [--asc|--desc]`, sorting by `default` without `--sort`;
+/// resource columns sort largest first unless told otherwise
+pub fn parse_options(args: &[String], default: SortColumn) -> Result<(SortColumn, bool), String> {
+    let mut sort = default;
+    let mut descending: Option<bool> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sort" => {
+                sort = match args.next().and_then(|s| parse_sort(s)) {
+                    Some(sort) => sort,
+                    None => return Err("ptable: --sort expects pid, cpu, mem, read, write, rq or name".to_string()),
+                }
+            },
+            "--asc" => descending = Some(false),
+            "--desc" => descending = Some(true),
+            _ => return Err(format!("ptable: unrecognized argument '{}'", arg)),
+        }
+    }
+    Ok((sort, descending.unwrap_or_else(|| largest_first(sort))))
+}
+
+/// Resource columns sort largest first by default, PID and name in ascending order
+pub fn largest_first(sort: SortColumn) -> bool {
+    !matches!(sort, SortColumn::Pid | SortColumn::Name)
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    match rate {
+        Some(rate) => format!("{}/s", convert(rate)),
+        None => "-".to_string(),
+    }
+}
+
+/// Real UID and state letter from /proc/<pid>/status
+fn read_owner_and_state(pid: u32) -> Option<(u32, char)> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let mut uid = None;
+    let mut state = None;
+    for line in status.lines() {
+        match line.split_once(':') {
+            Some(("Uid", value)) => uid = value.split_whitespace().next().and_then(|u| u.parse::<u32>().ok()),
+            Some(("State", value)) => state = value.trim().chars().next(),
+            _ => {},
+        }
+    }
+    Some((uid?, state?))
+}
+
+/// Process table shown in the output pane, with the row the arrow keys have moved to
+pub struct ProcessTable {
+    pub rows: Vec<ProcessRow>,
+    pub state: TableState,
+    /// Active quick filters; a row must match all of them
+    pub filters: Vec<Filter>,
+    /// Text typed after F4 that rows must contain in their name or command line, ignoring case
+    pub query: String,
+    pub sort: SortColumn,
+    pub descending: bool,
+    /// Where the table was last drawn, its first row shown there and the widths its columns got,
+    /// to tell what a click landed on
+    area: Rect,
+    offset: usize,
+    widths: Vec<usize>,
+}
+
+impl ProcessTable {
+    pub fn collect(io: &IoTracker, sched: &SchedTracker, sort: SortColumn, descending: bool) -> ProcessTable {
+        let mut rows: Vec<ProcessRow> = vec![];
+        let mut users: HashMap<u32, String> = HashMap::new();
+        for process in psutil::process::processes().unwrap_or_default().into_iter().flatten() {
+            let mut p = process;
+            let cmdline = match p.cmdline() {
+                Ok(None) => continue,
+                Ok(Some(cmdline)) => cmdline,
+                Err(_) => String::new(),
+            };
+            let (uid, state) = read_owner_and_state(p.pid()).unwrap_or((0, '?'));
+            let user = users
+                .entry(uid)
+                .or_insert_with(|| User::from_uid(Uid::from_raw(uid)).ok().flatten().map(|u| u.name).unwrap_or_else(|| uid.to_string()))
+                .clone();
+            let rates = io.rates.get(&(p.pid() as i32));
+            rows.push(ProcessRow {
+                pid: p.pid(),
+                uid,
+                user,
+                state,
+                container: false,
+                gpu: false,
+                cpu: p.cpu_percent().unwrap_or(0.0),
+                mem: p.memory_percent().unwrap_or(0.0),
+                read: rates.map(|r| r.0),
+                write: rates.map(|r| r.1),
+                run_queue: sched.latency.get(&(p.pid() as i32)).map(|l| l.wait),
+                name: p.name().unwrap_or_default(),
+                cmdline,
+            });
+        }
+        let mut table = ProcessTable { rows, state: TableState::default(), filters: vec![], query: String::new(), sort, descending, area: Rect::default(), offset: 0, widths: vec![] };
+        table.sort_by(sort, descending);
+        table
+    }
+
+    /// Reads whether each process runs in a container and whether it uses a GPU, which takes a
+    /// walk through /proc for every process, so only for what a filter or colour rule needs
+    pub fn probe(&mut self, containers: bool, gpus: bool) {
+        for row in &mut self.rows {
+            if containers {
+                row.container = cgroup::in_container(row.pid as i32);
+            }
+            if gpus {
+                row.gpu = gpu::uses_gpu(row.pid as i32);
+            }
+        }
+    }
+
+    /// Re-reads the processes, keeping the sort order, the filters and the selected process;
+    /// `containers` when a colour rule needs to know which run in one
+    pub fn refresh(&mut self, io: &IoTracker, sched: &SchedTracker, containers: bool) {
+        let selected = self.selected_row().map(|row| row.pid);
+        let filters = std::mem::take(&mut self.filters);
+        let query = std::mem::take(&mut self.query);
+        let (area, offset, widths) = (self.area, self.offset, std::mem::take(&mut self.widths));
+        *self = ProcessTable::collect(io, sched, self.sort, self.descending);
+        self.filters = filters;
+        self.query = query;
+        (self.area, self.offset, self.widths) = (area, offset, widths);
+        self.probe(containers || self.filters.contains(&Filter::Container), self.filters.contains(&Filter::Gpu));
+        let visible = self.visible();
+        let index = selected.and_then(|pid| visible.iter().position(|row| row.pid == pid)).or(if visible.is_empty() { None } else { Some(0) });
+        self.state.select(index);
+    }
+
+    /// Re-orders the rows and goes back to the first one
+    pub fn sort_by(&mut self, sort: SortColumn, descending: bool) {
+        self.rows.sort_by(|a, b| {
+            let ordering = match sort {
+                SortColumn::Pid => a.pid.cmp(&b.pid),
+                SortColumn::Cpu => a.cpu.total_cmp(&b.cpu),
+                SortColumn::Mem => a.mem.total_cmp(&b.mem),
+                SortColumn::Read => a.read.unwrap_or(-1.0).total_cmp(&b.read.unwrap_or(-1.0)),
+                SortColumn::Write => a.write.unwrap_or(-1.0).total_cmp(&b.write.unwrap_or(-1.0)),
+                SortColumn::RunQueue => a.run_queue.unwrap_or(-1.0).total_cmp(&b.run_queue.unwrap_or(-1.0)),
+                SortColumn::Name => a.name.cmp(&b.name),
+            };
+            if descending { ordering.reverse() } else { ordering }
+        });
+        self.sort = sort;
+        self.descending = descending;
+        self.state.select(if self.visible().is_empty() { None } else { Some(0) });
+    }
+
+    /// Rows passing every active filter and containing the query, in display order
+    pub fn visible(&self) -> Vec<&ProcessRow> {
+        let query = self.query.to_lowercase();
+        self.rows
+            .iter()
+            .filter(|row| self.filters.iter().all(|f| f.matches(row)))
+            .filter(|row| query.is_empty() || row.name.to_lowercase().contains(&query) || row.cmdline.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// Narrows the rows to those containing `query` and goes back to the first one
+    pub fn set_query(&mut self, query: &str) {
+        self.query = query.to_string();
+        self.state.select(if self.visible().is_empty() { None } else { Some(0) });
+    }
+
+    /// Switches `filter` on or off and goes back to the first row
+    pub fn toggle(&mut self, filter: Filter) {
+        match self.filters.iter().position(|f| *f == filter) {
+            Some(i) => {
+                self.filters.remove(i);
+            },
+            None => {
+                self.filters.push(filter);
+                self.probe(filter == Filter::Container, filter == Filter::Gpu);
+            },
+        }
+        self.state.select(if self.visible().is_empty() { None } else { Some(0) });
+    }
+
+    /// Moves the selection by `delta` rows, stopping at either end
+    pub fn scroll(&mut self, delta: isize) {
+        let len = self.visible().len() as isize;
+        if len == 0 {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len - 1);
+        self.state.select(Some(next as usize));
+    }
+
+    /// Selects the row of a PID, or the n-th visible row for `#n` or a number that is no visible PID
+    pub fn goto(&mut self, target: &str) -> bool {
+        let visible = self.visible();
+        let index = match target.strip_prefix('#') {
+            Some(row) => row.parse::<usize>().ok().filter(|n| (1..=visible.len()).contains(n)).map(|n| n - 1),
+            None => {
+                let Ok(n) = target.parse::<usize>() else {
+                    return false;
+                };
+                visible.iter().position(|row| row.pid as usize == n).or_else(|| (1..=visible.len()).contains(&n).then(|| n - 1))
+            },
+        };
+        match index {
+            Some(index) => {
+                self.state.select(Some(index));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Selects the row clicked at `x`, `y` on the screen; a click on a header sorts by its column,
+    /// reversing the order when it is already sorted by it
+    pub fn click(&mut self, x: u16, y: u16) {
+        let inner = self.area.inner(&Margin { vertical: 1, horizontal: 1 });
+        if !(inner.left()..inner.right()).contains(&x) || !(inner.top()..inner.bottom()).contains(&y) {
+            return;
+        }
+        if y == inner.y {
+            if let Some(sort) = column_at(x - inner.x, &self.widths) {
+                let descending = if sort == self.sort { !self.descending } else { largest_first(sort) };
+                self.sort_by(sort, descending);
+            }
+            return;
+        }
+        let index = self.offset + (y - inner.y - 1) as usize;
+        if index < self.visible().len() {
+            self.state.select(Some(index));
+        }
+    }
+
+    pub fn selected_row(&self) -> Option<&ProcessRow> {
+        self.visible().get(self.state.selected()?).copied()
+    }
+
+    pub fn page(&mut self, down: bool) {
+        self.scroll(if down { PAGE } else { -PAGE });
+    }
+
+    /// The visible rows as plain text lines under a header, for output outside the TUI
+    pub fn lines(&self) -> Vec<String> {
+        self.records().lines()
+    }
+
+    /// The visible rows with their cells as shown, for CSV and other exports
+    pub fn records(&self) -> Records {
+        let mut records = Records::new(&COLUMNS);
+        for row in self.visible() {
+            records.push(cells(row));
+        }
+        records
+    }
+}
+
+/// A row's cells as shown, one per entry of `COLUMNS`
+fn cells(row: &ProcessRow) -> Vec<String> {
+    vec![
+        row.pid.to_string(),
+        row.user.clone(),
+        row.state.to_string(),
+        format!("{:.1}", row.cpu),
+        format!("{:.1}", row.mem),
+        format_rate(row.read),
+        format_rate(row.write),
+        row.run_queue.map(|w| format!("{:.1}", w)).unwrap_or_else(|| "-".to_string()),
+        row.name.clone(),
+    ]
+}
+
+/// The column sorted by a click `x` cells into the table, laid out in `widths` as `render` does with a gap between columns
+fn column_at(x: u16, widths: &[usize]) -> Option<SortColumn> {
+    let column = widths
+        .iter()
+        .scan(0, |end, width| {
+            *end += width + GAP.len();
+            Some(*end)
+        })
+        .position(|end| (x as usize) < end);
+    match column? {
+        0 => Some(SortColumn::Pid),
+        3 => Some(SortColumn::Cpu),
+        4 => Some(SortColumn::Mem),
+        5 => Some(SortColumn::Read),
+        6 => Some(SortColumn::Write),
+        7 => Some(SortColumn::RunQueue),
+        8 => Some(SortColumn::Name),
+        _ => None,
+    }
+}
+
+/// The %CPU cell in the theme's colour for its band, bold when busy
+fn cpu_style(cpu: f32, theme: &Theme) -> Style {
+    if cpu >= CPU_BANDS[1] {
+        Style::default().fg(theme.cpu_high).add_modifier(Modifier::BOLD)
+    } else if cpu >= CPU_BANDS[0] {
+        Style::default().fg(theme.cpu_mid)
+    } else {
+        Style::default().fg(theme.cpu_low)
+    }
+}
+
+/// Draws the table, colouring each row by the first colour rule it matches and %CPU by how busy the process is;
+/// columns are as wide as their longest cell, or narrowed to fit the terminal with long cells cut short
+pub fn render<B: Backend>(f: &mut Frame<B>, area: Rect, table: &mut ProcessTable, colors: &[ColorRule], theme: &Theme) {
+    // Scrolled just far enough to keep the selected row in sight, below the header
+    let height = area.height.saturating_sub(3) as usize;
+    let selected = table.state.selected().unwrap_or(0);
+    if selected < table.offset {
+        table.offset = selected;
+    } else if height > 0 && selected >= table.offset + height {
+        table.offset = selected + 1 - height;
+    }
+    table.area = area;
+    let records = table.records();
+    table.widths = records::fit(&records.widths(), area.width.saturating_sub(2) as usize);
+    let widths = &table.widths;
+    let header = Row::new(COLUMNS.iter().zip(widths).map(|(name, width)| records::ellipsize(name, *width)).collect::<Vec<String>>())
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let visible = table.visible();
+    let len = visible.len();
+    let title = if table.filters.is_empty() && table.query.is_empty() {
+        format!("Output ({} processes)", table.rows.len())
+    } else {
+        let mut labels: Vec<String> = table.filters.iter().map(|f| f.label().to_string()).collect();
+        if !table.query.is_empty() {
+            labels.push(format!("\"{}\"", table.query));
+        }
+        format!("Output ({} of {} processes: {})", visible.len(), table.rows.len(), labels.join(" + "))
+    };
+    let rows = visible.iter().zip(&records.rows).skip(table.offset).take(height).map(|(row, cells)| {
+        // A colour rule takes the whole row; without one zombies stand out, and root in the USER column
+        let rule = colors.iter().find(|rule| rule.matches(row));
+        let style = match rule {
+            Some(rule) => rule.fg().map(|color| Style::default().fg(color)).unwrap_or_default(),
+            None if row.state == 'Z' => Style::default().fg(theme.zombie),
+            None => Style::default(),
+        };
+        let user = if rule.is_none() && row.uid == 0 { Style::default().fg(theme.root) } else { Style::default() };
+        let cpu = if row.state == 'Z' { Style::default() } else { cpu_style(row.cpu, theme) };
+        let cells = cells.iter().zip(widths).enumerate().map(|(i, (cell, width))| {
+            let cell = Cell::from(records::ellipsize(cell, *width));
+            match i {
+                1 => cell.style(user),
+                3 => cell.style(cpu),
+                _ => cell,
+            }
+        });
+        Row::new(cells.collect::<Vec<Cell>>()).style(style)
+    });
+    let mut constraints: Vec<Constraint> = widths.iter().map(|w| Constraint::Length(*w as u16)).collect();
+    // COMMAND takes whatever room is left
+    if let Some(last) = constraints.last_mut() {
+        *last = Constraint::Min(widths[widths.len() - 1] as u16);
+    }
+    let widget = Table::new(rows)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(title))
+        .style(theme.text())
+        .column_spacing(GAP.len() as u16)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&constraints);
+    let mut state = TableState::default();
+    state.select(table.state.selected().map(|selected| selected - table.offset));
+    f.render_stateful_widget(widget, area, &mut state);
+    f.render_widget(Scrollbar { top: table.offset, shown: height, len, style: theme.border() }, Scrollbar::track(area, 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_at_follows_the_rendered_widths() {
+        let widths = [3, 4, 1, 4, 4, 6, 7, 7, 7];
+        assert!(column_at(0, &widths) == Some(SortColumn::Pid));
+        // The gap after a column still belongs to it
+        assert!(column_at(4, &widths) == Some(SortColumn::Pid));
+        assert!(column_at(5, &widths).is_none());
+        assert!(column_at(14, &widths) == Some(SortColumn::Cpu));
+        assert!(column_at(60, &widths) == Some(SortColumn::Name));
+        assert!(column_at(0, &[]).is_none());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{paths, records::Records};
+use std::{fs, io, ops::Range, path::PathBuf};
+
+/// Where Up/Down and Ctrl-R are in the command lines entered so far
+#[derive(Default)]
+pub struct Recall {
+    /// Index of the entry in the input box, `None` while typing a new line
+    position: Option<usize>,
+    /// What was typed before going back through the history, put back past the newest entry
+    draft: String,
+    /// Entry the Ctrl-R query last matched
+    pub found: Option<usize>,
+}
+
+impl Recall {
+    /// The entry before the one shown, keeping what was being typed when starting out
+    pub fn older(&mut self, entries: &[String], input: &str) -> Option<String> {
+        let position = match self.position {
+            None if entries.is_empty() => return None,
+            None => {
+                self.draft = input.to_string();
+                entries.len() - 1
+            },
+            Some(0) => 0,
+            Some(at) => at - 1,
+        };
+        self.position = Some(position);
+        Some(entries[position].clone())
+    }
+
+    /// The entry after the one shown, or the draft past the newest
+    pub fn newer(&mut self, entries: &[String]) -> Option<String> {
+        let at = self.position?;
+        if at + 1 < entries.len() {
+            self.position = Some(at + 1);
+            Some(entries[at + 1].clone())
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    /// Starts a Ctrl-R search, keeping the line being typed to go back to on cancel
+    pub fn start_search(&mut self, input: &str) {
+        self.draft = input.to_string();
+        self.found = None;
+    }
+
+    /// Finds the newest entry containing `query`, or with `older` the next one before the last match
+    pub fn search(&mut self, entries: &[String], query: &str, older: bool) {
+        let before = match (older, self.found) {
+            (true, Some(at)) => at,
+            _ => entries.len(),
+        };
+        let found = entries[..before].iter().rposition(|entry| entry.contains(query));
+        // An older search with nothing further back stays on its match
+        if found.is_some() || !older {
+            self.found = found;
+        }
+    }
+
+    /// Ends a Ctrl-R search: the match to edit on, or the line typed before it
+    pub fn finish_search(&mut self, entries: &[String], accept: bool) -> String {
+        let line = match self.found.filter(|_| accept) {
+            Some(at) => entries[at].clone(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.found = None;
+        line
+    }
+
+    /// Back to typing a new line, after one was run
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+}
+
+/// Command lines kept between sessions, one per line
+pub fn path() -> PathBuf {
+    paths::data_dir().join("history")
+}
+
+/// The lines entered in earlier sessions, oldest first and at most `size` of them
+pub fn load(size: usize) -> Vec<String> {
+    let mut entries: Vec<String> = vec![];
+    for line in fs::read_to_string(path()).unwrap_or_default().lines() {
+        push(&mut entries, line, size);
+    }
+    entries
+}
+
+/// Adds `line` as the newest entry, dropping an earlier copy of it and the oldest entries past `size`.
+/// Lines with `!!` or `!n` are left out; the line they stand for goes in when it runs.
+pub fn push(entries: &mut Vec<String>, line: &str, size: usize) {
+    if line.trim().is_empty() || !recalls(line).is_empty() {
+        return;
+    }
+    entries.retain(|entry| entry != line);
+    entries.push(line.to_string());
+    let excess = entries.len().saturating_sub(size);
+    entries.drain(..excess);
+}
+
+pub fn save(entries: &[String]) -> io::Result<()> {
+    fs::create_dir_all(paths::data_dir())?;
+    fs::write(path(), entries.iter().map(|entry| format!("{}\n", entry)).collect::<String>())
+}
+
+/// Where `line` has a word starting with `!` in place of a command: at its start or after
+/// `;` or `&&`, outside quotes
+fn recalls(line: &str) -> Vec<Range<usize>> {
+    let mut found: Vec<Range<usize>> = vec![];
+    let mut quote: Option<char> = None;
+    let mut command_start = true;
+    let mut chars = line.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {},
+            (None, ';') => command_start = true,
+            (None, '&') if matches!(chars.peek(), Some((_, '&'))) => {
+                chars.next();
+                command_start = true;
+            },
+            (None, c) if c.is_whitespace() => {},
+            (None, '!') if command_start => {
+                let end = line[at..].find(|c: char| c.is_whitespace() || c == ';' || c == '&').map_or(line.len(), |len| at + len);
+                found.push(at..end);
+                while matches!(chars.peek(), Some((next, _)) if *next < end) {
+                    chars.next();
+                }
+                command_start = false;
+            },
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                command_start = false;
+            },
+            (None, _) => command_start = false,
+        }
+    }
+    found
+}
+
+/// Replaces each `!!` (the newest entry) and `!n` (entry n as `history` numbers them) that stands
+/// for a command of `line` with that entry, keeping the rest of the line
+pub fn expand(entries: &[String], line: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut from = 0;
+    for range in recalls(line) {
+        let word = &line[range.clone()];
+        let entry = match &word[1..] {
+            "!" => entries.last().ok_or_else(|| "!!: no command lines entered yet".to_string())?,
+            n => match n.parse::<usize>() {
+                Ok(n) => entries.get(n.wrapping_sub(1)).ok_or_else(|| format!("!{}: no such entry; history lists them", n))?,
+                Err(_) => return Err(format!("{}: use !! or !n with a number from history", word)),
+            },
+        };
+        expanded.push_str(&line[from..range.start]);
+        expanded.push_str(entry);
+        from = range.end;
+    }
+    expanded.push_str(&line[from..]);
+    Ok(expanded)
+}
+
+/// `history`: every entry numbered from the oldest, the numbers `!n` takes
+pub fn table(entries: &[String]) -> Records {
+    let mut records = Records::new(&["#", "COMMAND"]);
+    for (i, entry) in entries.iter().enumerate() {
+        records.push(vec![(i + 1).to_string(), entry.clone()]);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<String> {
+        vec!["ptable".to_string(), "df -h".to_string(), "sensors".to_string()]
+    }
+
+    #[test]
+    fn expand_bang_bang_and_n() {
+        assert_eq!(expand(&entries(), "!!"), Ok("sensors".to_string()));
+        assert_eq!(expand(&entries(), "!1"), Ok("ptable".to_string()));
+        assert_eq!(expand(&entries(), "  !2"), Ok("  df -h".to_string()));
+    }
+
+    #[test]
+    fn expand_keeps_text_after_the_word() {
+        assert_eq!(expand(&entries(), "!1 --sort cpu | head 5"), Ok("ptable --sort cpu | head 5".to_string()));
+        assert_eq!(expand(&entries(), "!!;hostname"), Ok("sensors;hostname".to_string()));
+    }
+
+    #[test]
+    fn expand_every_command_of_a_line() {
+        assert_eq!(expand(&entries(), "hostname; !! && !1"), Ok("hostname; sensors && ptable".to_string()));
+    }
+
+    #[test]
+    fn expand_leaves_other_words_alone() {
+        for line in ["ptable | grep !1", "echo '; !!'", "watch 2 !!", "ignite sh -c \"a && !!\""] {
+            assert_eq!(expand(&entries(), line), Ok(line.to_string()));
+        }
+    }
+
+    #[test]
+    fn expand_refuses_what_names_no_entry() {
+        assert!(expand(&entries(), "!").is_err());
+        assert!(expand(&entries(), "!0").is_err());
+        assert!(expand(&entries(), "!4").is_err());
+        assert!(expand(&entries(), "!x").is_err());
+        assert!(expand(&[], "!!").is_err());
+        assert!(expand(&entries(), "hostname; !4").is_err());
+    }
+
+    #[test]
+    fn push_leaves_out_lines_that_recall() {
+        let mut entries = entries();
+        push(&mut entries, "ptable; !!", 10);
+        push(&mut entries, "!2", 10);
+        assert_eq!(entries, super::tests::entries());
+    }
+}
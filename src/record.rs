@@ -0,0 +1,45 @@
+use crate::rules::Snapshot;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+const CSV_HEADER: &str = "time,cpu,mem,swap,load,temp,disk,net_rx,net_tx,swap_in,swap_out,major_faults";
+
+/// Appends every sampler snapshot to a file until stopped. A `.csv` file gets the system-wide
+/// columns only; anything else gets full snapshots as JSON lines, which `rules test` can replay.
+pub struct Recorder {
+    pub path: String,
+    csv: bool,
+    file: BufWriter<File>,
+    pub rows: usize,
+}
+
+impl Recorder {
+    pub fn start(path: &str) -> io::Result<Recorder> {
+        let csv = Path::new(path).extension().map(|e| e.eq_ignore_ascii_case("csv")).unwrap_or(false);
+        let mut file = BufWriter::new(File::create(path)?);
+        if csv {
+            writeln!(file, "{}", CSV_HEADER)?;
+        }
+        Ok(Recorder { path: path.to_string(), csv, file, rows: 0 })
+    }
+
+    pub fn write(&mut self, snap: &Snapshot) -> io::Result<()> {
+        if self.csv {
+            writeln!(
+                self.file,
+                "{},{:.1},{:.1},{:.1},{:.2},{:.1},{:.1},{:.0},{:.0},{:.1},{:.1},{:.1}",
+                snap.time, snap.cpu, snap.mem, snap.swap, snap.load, snap.temp, snap.disk, snap.net_rx, snap.net_tx, snap.swap_in, snap.swap_out, snap.major_faults
+            )?;
+        } else {
+            serde_json::to_writer(&mut self.file, snap)?;
+            writeln!(self.file)?;
+        }
+        // Flushed every sample so the file can be inspected while the incident is still going on
+        self.file.flush()?;
+        self.rows += 1;
+        Ok(())
+    }
+}
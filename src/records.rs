@@ -0,0 +1,233 @@
+use crate::{scrollbar::Scrollbar, search::Search, theme::Theme};
+use serde_json::{Map, Value};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+
+/// Space between columns in the text rendering
+pub(crate) const GAP: &str = "  ";
+
+/// Narrowest a column gets squeezed to on a small terminal
+const MIN_COLUMN: usize = 4;
+
+/// A command's result as named columns and rows of cells, shown as a table in the TUI
+/// and written as text, JSON or CSV everywhere else
+#[derive(Clone, Default)]
+pub struct Records {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Records {
+    pub fn new(columns: &[&str]) -> Records {
+        Records { columns: columns.iter().map(|c| c.to_string()).collect(), rows: vec![] }
+    }
+
+    /// Adds a row; missing cells are left empty and extra ones dropped
+    pub fn push(&mut self, mut row: Vec<String>) {
+        row.resize(self.columns.len(), String::new());
+        self.rows.push(row);
+    }
+
+    /// Output with no columns of its own, one `line` per row
+    pub fn from_lines(lines: &[String]) -> Records {
+        let mut records = Records::new(&["line"]);
+        for line in lines {
+            records.push(vec![line.clone()]);
+        }
+        records
+    }
+
+    /// Each row as one line of text, for searching with `/`
+    pub fn row_lines(&self) -> Vec<String> {
+        self.rows.iter().map(|row| Records::row_line(row)).collect()
+    }
+
+    /// A row as one line of text, for filtering with `| grep`
+    pub fn row_line(row: &[String]) -> String {
+        row.join(" ")
+    }
+
+    /// How wide each column's longest cell, header included, is
+    pub(crate) fn widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.columns.iter().map(|c| c.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        widths
+    }
+
+    /// Header and rows as aligned plain text lines
+    pub fn lines(&self) -> Vec<String> {
+        let widths = self.widths();
+        let format = |cells: &[String]| -> String {
+            let padded: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+            padded.join(GAP).trim_end().to_string()
+        };
+        let mut vec: Vec<String> = vec![format(&self.columns)];
+        vec.extend(self.rows.iter().map(|row| format(row)));
+        vec
+    }
+
+    /// One object per row, keyed by column name
+    pub fn to_json(&self) -> Value {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| Value::Object(self.columns.iter().cloned().zip(row.iter().map(|cell| Value::String(cell.clone()))).collect::<Map<String, Value>>()))
+            .collect();
+        Value::Array(rows)
+    }
+
+    /// RFC 4180 CSV with a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in std::iter::once(&self.columns).chain(&self.rows) {
+            let cells: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+            csv.push_str(&cells.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) || cell.starts_with(' ') || cell.ends_with(' ') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// `text` cut to `width` characters, ending in an ellipsis when anything was cut
+pub fn ellipsize(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    if width > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+/// Whether a line of text output is the header of a table: two or more columns apart by at
+/// least a gap, mostly named in capitals like the ones `lines` and the other commands print
+pub fn is_header(line: &str) -> bool {
+    let columns = line.split(GAP).filter(|c| !c.trim().is_empty()).count();
+    let words: Vec<&str> = line.split_whitespace().filter(|w| w.chars().any(|c| c.is_alphabetic())).collect();
+    let capitals = words.iter().filter(|w| !w.chars().any(|c| c.is_lowercase())).count();
+    columns >= 2 && capitals * 2 > words.len()
+}
+
+/// `widths` narrowed until they fit in `room` with the gaps between them, taking from the widest column first
+pub(crate) fn fit(widths: &[usize], room: usize) -> Vec<usize> {
+    let mut widths = widths.to_vec();
+    let gaps = GAP.len() * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + gaps > room {
+        let Some(widest) = (0..widths.len()).max_by_key(|i| widths[*i]) else {
+            break;
+        };
+        if widths[widest] <= MIN_COLUMN {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+    widths
+}
+
+/// Draws the records as a table with one column per field, each as wide as its longest cell or narrowed
+/// to fit the terminal with long cells cut short, with the matches of `search` highlighted and its current
+/// row selected, from row `top` on
+pub fn render<B: Backend>(f: &mut Frame<B>, area: Rect, records: &Records, title: &str, theme: &Theme, search: Option<&Search>, top: usize) {
+    let widths = fit(&records.widths(), area.width.saturating_sub(2) as usize);
+    let cut = |row: &[String]| -> Vec<String> { row.iter().zip(&widths).map(|(cell, width)| ellipsize(cell, *width)).collect() };
+    let header = Row::new(cut(&records.columns)).style(Style::default().add_modifier(Modifier::BOLD));
+    let found = Style::default().fg(theme.background).bg(theme.accent);
+    let rows = records.rows.iter().skip(top).map(|row| match search {
+        Some(search) => Row::new(cut(row).iter().map(|cell| Cell::from(search.highlight(cell, found))).collect::<Vec<Cell>>()),
+        None => Row::new(cut(row)),
+    });
+    let mut constraints: Vec<Constraint> = widths.iter().map(|w| Constraint::Length(*w as u16)).collect();
+    // The last column takes whatever room is left
+    if let Some(last) = constraints.last_mut() {
+        *last = Constraint::Min(widths[widths.len() - 1] as u16);
+    }
+    let widget = Table::new(rows)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border()).title(format!("{} ({} rows)", title, records.rows.len())))
+        .style(theme.text())
+        .column_spacing(GAP.len() as u16)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&constraints);
+    let shown = area.height.saturating_sub(3) as usize;
+    let mut state = TableState::default();
+    state.select(search.and_then(|s| s.current).and_then(|current| current.checked_sub(top)).filter(|row| *row < shown));
+    f.render_stateful_widget(widget, area, &mut state);
+    f.render_widget(Scrollbar { top, shown, len: records.rows.len(), style: theme.border() }, Scrollbar::track(area, 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_csv_quotes_only_cells_that_need_it() {
+        let mut records = Records::new(&["PID", "COMMAND"]);
+        records.push(vec!["1".to_string(), "init".to_string()]);
+        records.push(vec!["2".to_string(), "sh -c \"a, b\"".to_string()]);
+        records.push(vec!["3".to_string(), " padded\nline".to_string()]);
+        assert_eq!(records.to_csv(), "PID,COMMAND\r\n1,init\r\n2,\"sh -c \"\"a, b\"\"\"\r\n3,\" padded\nline\"\r\n");
+    }
+
+    #[test]
+    fn to_csv_of_no_rows_is_the_header() {
+        assert_eq!(Records::new(&["A", "B"]).to_csv(), "A,B\r\n");
+    }
+
+    #[test]
+    fn fit_narrows_the_widest_column_first() {
+        assert_eq!(fit(&[10, 20, 5], 25), vec![8, 8, 5]);
+        assert_eq!(fit(&[10, 20, 5], 40), vec![10, 20, 5]);
+    }
+
+    #[test]
+    fn fit_stops_at_the_narrowest_column() {
+        assert_eq!(fit(&[10, 10], 5), vec![MIN_COLUMN, MIN_COLUMN]);
+        assert_eq!(fit(&[2, 30], 10), vec![2, 6]);
+        assert_eq!(fit(&[2, 30], 4), vec![2, MIN_COLUMN]);
+        assert!(fit(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn ellipsize_marks_what_was_cut() {
+        assert_eq!(ellipsize("sshd", 4), "sshd");
+        assert_eq!(ellipsize("systemd-journald", 8), "systemd…");
+        assert_eq!(ellipsize("abc", 0), "");
+    }
+
+    #[test]
+    fn is_header_wants_capitals_in_several_columns() {
+        assert!(is_header("PID    USER  %CPU  COMMAND"));
+        assert!(is_header("#  COMMAND"));
+        assert!(!is_header("1234   root  0.5   sshd"));
+        assert!(!is_header("PID USER COMMAND"));
+        assert!(!is_header("USAGE"));
+        assert!(!is_header(""));
+    }
+
+    #[test]
+    fn is_header_of_lines_output() {
+        let mut records = Records::new(&["ID", "STATUS"]);
+        records.push(vec!["1".to_string(), "running".to_string()]);
+        let lines = records.lines();
+        assert!(is_header(&lines[0]));
+        assert!(!is_header(&lines[1]));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{records::Records, rules::Snapshot};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{channel, Receiver},
+    thread,
+};
+
+/// Ends the output of one command in the agent protocol: the agent answers every command line it
+/// reads with the output lines followed by a line holding only this
+pub const END: &str = "\x1e";
+
+/// Agent request answered with one line of `HostMetrics` JSON instead of command output
+pub const METRICS: &str = "@metrics";
+
+/// The headline numbers of a host, compared on the overview tab
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct HostMetrics {
+    pub cpu: f64,
+    pub mem: f64,
+    pub swap: f64,
+    pub load: f64,
+    pub disk: f64,
+}
+
+impl HostMetrics {
+    pub fn from_snapshot(snap: &Snapshot) -> HostMetrics {
+        HostMetrics { cpu: snap.cpu, mem: snap.mem, swap: snap.swap, load: snap.load, disk: snap.disk }
+    }
+
+    /// The most saturated of CPU, memory and the fullest disk, in percent
+    pub fn pressure(&self) -> f64 {
+        self.cpu.max(self.mem).max(self.disk)
+    }
+}
+
+/// Ranks hosts by pressure, busiest first
+pub fn get_overview(hosts: &[(String, Option<HostMetrics>)]) -> Records {
+    let mut ranked: Vec<&(String, Option<HostMetrics>)> = hosts.iter().collect();
+    ranked.sort_by(|a, b| {
+        let pressure = |h: &(String, Option<HostMetrics>)| h.1.as_ref().map(|m| m.pressure()).unwrap_or(-1.0);
+        pressure(b).total_cmp(&pressure(a))
+    });
+    let mut records = Records::new(&["HOST", "PRESSURE", "%CPU", "%MEM", "%SWAP", "%DISK", "LOAD"]);
+    for (host, metrics) in ranked {
+        match metrics {
+            Some(m) => records.push(vec![
+                host.clone(),
+                format!("{:.1}", m.pressure()),
+                format!("{:.1}", m.cpu),
+                format!("{:.1}", m.mem),
+                format!("{:.1}", m.swap),
+                format!("{:.1}", m.disk),
+                format!("{:.2}", m.load),
+            ]),
+            None => records.push(vec![host.clone(), "waiting".to_string()]),
+        }
+    }
+    records
+}
+
+enum Reply {
+    Output(Vec<String>),
+    /// The SSH session ended, with what ssh printed on stderr
+    Closed(String),
+}
+
+/// What a reply still to come answers
+enum Pending {
+    Command,
+    Metrics,
+}
+
+/// A `proclynx --agent` on another host, driven over `ssh`
+pub struct Remote {
+    pub target: String,
+    child: Child,
+    stdin: ChildStdin,
+    receiver: Receiver<Reply>,
+    pending: VecDeque<Pending>,
+    /// Output of the last command run on this host
+    pub output: Vec<String>,
+    pub metrics: Option<HostMetrics>,
+    /// Set once the session ended, with the reason
+    pub closed: Option<String>,
+}
+
+impl Remote {
+    /// Starts `program --agent` on `target` (`user@host` or an ssh config alias), passing `--readonly`
+    /// on when set here. Key or agent based authentication is required: a password prompt cannot get
+    /// past the TUI.
+    pub fn connect(target: &str, program: &str, readonly: bool) -> io::Result<Remote> {
+        let mut child = Command::new("ssh")
+            // `--` so a target starting with '-' cannot pass for an ssh option
+            .args(["-T", "-o", "BatchMode=yes", "--", target, program, "--agent"])
+            .args(readonly.then_some("--readonly"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let mut stderr = child.stderr.take().unwrap();
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let mut lines: Vec<String> = vec![];
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line == END {
+                    if sender.send(Reply::Output(std::mem::take(&mut lines))).is_err() {
+                        return;
+                    }
+                } else {
+                    lines.push(line);
+                }
+            }
+            let mut error = String::new();
+            let _ = stderr.read_to_string(&mut error);
+            let _ = sender.send(Reply::Closed(error.trim().to_string()));
+        });
+        let mut remote = Remote {
+            target: target.to_string(),
+            child,
+            stdin,
+            receiver,
+            pending: VecDeque::new(),
+            output: vec![format!("Connecting to {}...", target)],
+            metrics: None,
+            closed: None,
+        };
+        // Answered once the agent is up, replacing the line above
+        remote.run("hostname");
+        Ok(remote)
+    }
+
+    fn send(&mut self, line: &str, pending: Pending) -> io::Result<()> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()?;
+        self.pending.push_back(pending);
+        Ok(())
+    }
+
+    /// Sends a command line; its output shows up in `output` once `update` sees the reply
+    pub fn run(&mut self, line: &str) {
+        self.output = match self.send(line, Pending::Command) {
+            Ok(_) => vec![format!("Running on {}...", self.target)],
+            Err(e) => vec![format!("Error sending to {}: {}", self.target, e)],
+        };
+    }
+
+    /// Asks for fresh metrics unless a request is still outstanding
+    pub fn request_metrics(&mut self) {
+        if !self.pending.iter().any(|p| matches!(p, Pending::Metrics)) {
+            let _ = self.send(METRICS, Pending::Metrics);
+        }
+    }
+
+    /// Takes in the replies that arrived; the agent answers in the order it was asked
+    pub fn update(&mut self) {
+        for reply in self.receiver.try_iter().collect::<Vec<Reply>>() {
+            match reply {
+                Reply::Output(lines) => match self.pending.pop_front() {
+                    Some(Pending::Metrics) => self.metrics = lines.first().and_then(|line| serde_json::from_str(line).ok()),
+                    _ => self.output = lines,
+                },
+                Reply::Closed(error) => self.closed = Some(if error.is_empty() { "remote agent exited".to_string() } else { error }),
+            }
+        }
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
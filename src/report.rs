@@ -0,0 +1,54 @@
+use chrono::Local;
+use std::{fs, io, path::Path};
+
+pub enum Format {
+    Html,
+    Markdown,
+}
+
+impl Format {
+    /// Picked from the file extension
+    pub fn from_path(path: &str) -> Option<Format> {
+        match Path::new(path).extension()?.to_str()?.to_lowercase().as_str() {
+            "html" | "htm" => Some(Format::Html),
+            "md" | "markdown" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn render_html(host: &str, generated: &str, sections: &[(&str, Vec<String>)]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>System report: {}</title>\n", escape_html(host)));
+    html.push_str("<style>body { font-family: sans-serif; margin: 2em; } pre { background: #f4f4f4; padding: 1em; overflow-x: auto; }</style>\n");
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>System report: {}</h1>\n<p>Generated by proclynx on {}</p>\n", escape_html(host), generated));
+    for (title, lines) in sections {
+        html.push_str(&format!("<h2>{}</h2>\n<pre>{}</pre>\n", escape_html(title), escape_html(&lines.join("\n"))));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_markdown(host: &str, generated: &str, sections: &[(&str, Vec<String>)]) -> String {
+    let mut md = format!("# System report: {}\n\nGenerated by proclynx on {}\n", host, generated);
+    for (title, lines) in sections {
+        md.push_str(&format!("\n## {}\n\n```\n{}\n```\n", title, lines.join("\n")));
+    }
+    md
+}
+
+/// Writes the sections, each a titled block of preformatted lines, as one report file
+pub fn write(path: &str, format: Format, host: &str, sections: &[(&str, Vec<String>)]) -> io::Result<()> {
+    let generated = Local::now().format("%Y-%m-%d %H:%M:%S %Z").to_string();
+    let text = match format {
+        Format::Html => render_html(host, &generated, sections),
+        Format::Markdown => render_markdown(host, &generated, sections),
+    };
+    fs::write(path, text)
+}
@@ -0,0 +1,176 @@
+use crate::rules::Snapshot;
+use nix::{
+    sys::signal::{kill, Signal},
+    unistd::{gethostname, Pid},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    io::Read,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+};
+use sysinfo::{System, SystemExt};
+use tiny_http::{Header, Method, Request, Response};
+
+/// Largest request body accepted, plenty for `POST /kill`
+const MAX_BODY: u64 = 4096;
+
+#[derive(Deserialize)]
+struct KillRequest {
+    pid: i32,
+    /// Signal name such as "SIGTERM" or "SIGKILL"; SIGTERM when left out
+    signal: Option<String>,
+}
+
+/// JSON API answering from the sampler's latest snapshot:
+/// `GET /processes`, `GET /system`, `GET /sensors` and `POST /kill {"pid": 123, "signal": "SIGTERM"}`.
+/// `POST /kill` needs `token` as a bearer token and is off without one.
+pub struct Server {
+    pub addr: String,
+    server: Arc<tiny_http::Server>,
+    latest: Arc<Mutex<Option<Snapshot>>>,
+}
+
+impl Server {
+    /// A `readonly` server, or one without a `token`, answers `POST /kill` with 403
+    pub fn start(addr: &str, readonly: bool, token: Option<String>) -> Result<Server, String> {
+        let server = Arc::new(tiny_http::Server::http(addr).map_err(|e| format!("cannot listen on {}: {}", addr, e))?);
+        let latest: Arc<Mutex<Option<Snapshot>>> = Arc::new(Mutex::new(None));
+        let (requests, snapshot) = (Arc::clone(&server), Arc::clone(&latest));
+        thread::spawn(move || {
+            // Ends once `stop` unblocks the server
+            for request in requests.incoming_requests() {
+                handle(request, &snapshot, readonly, token.as_deref());
+            }
+        });
+        Ok(Server { addr: addr.to_string(), server, latest })
+    }
+
+    /// Makes `snap` what the API reports from now on
+    pub fn publish(&self, snap: &Snapshot) {
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = Some(snap.clone());
+        }
+    }
+
+    pub fn stop(&self) {
+        self.server.unblock();
+    }
+}
+
+fn respond(request: Request, status: u16, body: Value) {
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let _ = request.respond(Response::from_string(body.to_string()).with_status_code(status).with_header(header));
+}
+
+/// Value of the first `name` header of `request`
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request.headers().iter().find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name)).map(|h| h.value.as_str())
+}
+
+/// Why `request` may not kill anything, if it may not. A browser page could otherwise post
+/// to a local port, so anything sent with an `Origin` or as a form is refused as well.
+fn refuse_kill(request: &Request, readonly: bool, token: Option<&str>) -> Option<(u16, Value)> {
+    if readonly {
+        return Some((403, json!({ "error": "proclynx runs in read-only mode" })));
+    }
+    let Some(token) = token else {
+        return Some((403, json!({ "error": "POST /kill is off; set token under [serve] in config.toml" })));
+    };
+    if header(request, "Origin").is_some() {
+        return Some((403, json!({ "error": "cross-origin requests are refused" })));
+    }
+    if header(request, "Authorization") != Some(format!("Bearer {}", token).as_str()) {
+        return Some((401, json!({ "error": "expected Authorization: Bearer <token>" })));
+    }
+    let json = header(request, "Content-Type").and_then(|t| t.split(';').next()).map(|t| t.trim().eq_ignore_ascii_case("application/json"));
+    if json != Some(true) {
+        return Some((415, json!({ "error": "expected Content-Type: application/json" })));
+    }
+    None
+}
+
+fn handle(mut request: Request, latest: &Mutex<Option<Snapshot>>, readonly: bool, token: Option<&str>) {
+    let route = (request.method().clone(), request.url().split('?').next().unwrap_or_default().to_string());
+    if let (Method::Post, "/kill") = (&route.0, route.1.as_str()) {
+        if let Some((status, reply)) = refuse_kill(&request, readonly, token) {
+            return respond(request, status, reply);
+        }
+        let mut body = String::new();
+        let read = request.as_reader().take(MAX_BODY).read_to_string(&mut body);
+        let (status, reply) = match read.map_err(|e| e.to_string()).and_then(|_| serde_json::from_str::<KillRequest>(&body).map_err(|e| e.to_string())) {
+            Ok(kill_request) => kill_process(&kill_request),
+            Err(e) => (400, json!({ "error": format!("expected {{\"pid\": <pid>, \"signal\": <name>}}: {}", e) })),
+        };
+        return respond(request, status, reply);
+    }
+    let snap = match latest.lock() {
+        Ok(latest) => latest.clone(),
+        Err(_) => None,
+    };
+    let Some(snap) = snap else {
+        return respond(request, 503, json!({ "error": "no sample taken yet" }));
+    };
+    match (&route.0, route.1.as_str()) {
+        (Method::Get, "/processes") => respond(request, 200, json!(snap.processes)),
+        (Method::Get, "/system") => {
+            let sys = System::new();
+            let body = json!({
+                "hostname": gethostname().map(|h| h.to_string_lossy().to_string()).unwrap_or_default(),
+                "kernel": sys.kernel_version(),
+                "os": sys.long_os_version(),
+                "uptime": sys.uptime(),
+                "time": snap.time,
+                "cpu": snap.cpu,
+                "mem": snap.mem,
+                "swap": snap.swap,
+                "load": snap.load,
+                "disk": snap.disk,
+                "net_rx": snap.net_rx,
+                "net_tx": snap.net_tx,
+                "filesystems": snap.filesystems,
+            });
+            respond(request, 200, body)
+        },
+        (Method::Get, "/sensors") => respond(request, 200, json!({ "components": snap.components, "gpus": snap.gpus })),
+        (_, "/processes" | "/system" | "/sensors" | "/kill") => respond(request, 405, json!({ "error": "method not allowed" })),
+        _ => respond(request, 404, json!({ "error": "not found; try /processes, /system, /sensors or POST /kill" })),
+    }
+}
+
+fn kill_process(request: &KillRequest) -> (u16, Value) {
+    // Only a positive PID names one process; 0 and negative ones signal process groups or everything
+    if request.pid <= 0 {
+        return (400, json!({ "error": format!("invalid PID {}; expected a positive PID", request.pid) }));
+    }
+    let name = request.signal.as_deref().unwrap_or("SIGTERM");
+    let Ok(signal) = Signal::from_str(name).or_else(|_| Signal::from_str(&format!("SIG{}", name.to_uppercase()))) else {
+        return (400, json!({ "error": format!("unknown signal '{}'", name) }));
+    };
+    match kill(Pid::from_raw(request.pid), signal) {
+        Ok(_) => (200, json!({ "pid": request.pid, "signal": signal.as_str() })),
+        Err(nix::errno::Errno::ESRCH) => (404, json!({ "error": format!("no process with PID {}", request.pid) })),
+        Err(e) => (403, json!({ "error": e.to_string() })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kill_refuses_0_and_negative_pids() {
+        for pid in [0, -1, -2, i32::MIN] {
+            let (status, _) = kill_process(&KillRequest { pid, signal: Some("SIGCONT".to_string()) });
+            assert_eq!(status, 400, "{}", pid);
+        }
+    }
+
+    #[test]
+    fn kill_refuses_unknown_signals() {
+        let (status, _) = kill_process(&KillRequest { pid: std::process::id() as i32, signal: Some("SIGNOPE".to_string()) });
+        assert_eq!(status, 400);
+    }
+}
@@ -0,0 +1,487 @@
+use crate::{
+    gpu::{self, GpuSample},
+    paths,
+    records::Records,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use sysinfo::{ComponentExt, CpuExt, DiskExt, PidExt, ProcessExt, System, SystemExt};
+
+/// Rule set read by `rules load` and `edit rules` when no file is named
+pub fn path() -> PathBuf {
+    paths::config_dir().join("rules.yaml")
+}
+
+/// Quantity a rule is evaluated against
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Overall CPU usage in percent
+    Cpu,
+    /// Used memory in percent
+    Mem,
+    /// Used swap in percent
+    Swap,
+    /// One minute load average
+    Load,
+    /// Hottest component in °C
+    Temp,
+    /// Fullest filesystem in percent
+    Disk,
+    /// CPU usage of every process matching `pattern`
+    ProcCpu,
+    /// Memory usage in percent of every process matching `pattern`
+    ProcMem,
+    /// Hottest GPU in °C
+    GpuTemp,
+    /// Busiest GPU in percent
+    GpuUtil,
+    /// Fullest GPU memory in percent
+    GpuMem,
+    /// Fewest days until a filesystem fills up at its current growth rate
+    DiskFullDays,
+    /// KiB swapped in per second
+    SwapIn,
+    /// KiB swapped out per second
+    SwapOut,
+    /// Major page faults per second, system-wide
+    MajorFaults,
+    /// Major page faults per second of every process matching `pattern`
+    ProcMajorFaults,
+}
+
+impl Metric {
+    fn is_percentage(self) -> bool {
+        matches!(self, Metric::Cpu | Metric::Mem | Metric::Swap | Metric::Disk | Metric::ProcMem | Metric::GpuUtil | Metric::GpuMem)
+    }
+
+    fn is_per_process(self) -> bool {
+        matches!(self, Metric::ProcCpu | Metric::ProcMem | Metric::ProcMajorFaults)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Op {
+    #[default]
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = "<")]
+    Below,
+}
+
+/// What happens when a rule fires
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    #[default]
+    Alert,
+    /// Alerts, then sends SIGTERM to the offending process and SIGKILL if it outlives the guard grace
+    /// period; logged with the guards' signals and skipped under `--readonly`
+    Autokill,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Rule {
+    pub name: String,
+    pub metric: Metric,
+    #[serde(default)]
+    pub op: Op,
+    pub threshold: f64,
+    /// Substring matched against process names, required by per-process metrics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub action: Action,
+    /// Seconds the condition must hold before the rule fires
+    #[serde(default, rename = "for", skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+    /// URL to POST this rule's alerts to instead of the configured webhook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct RuleFile {
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FsSample {
+    pub mount_point: String,
+    pub total: u64,
+    pub available: u64,
+}
+
+/// Bytes received and transmitted per second on one interface
+#[derive(Serialize, Deserialize, Clone)]
+pub struct InterfaceSample {
+    pub name: String,
+    pub rx: f64,
+    pub tx: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessSample {
+    pub pid: i32,
+    pub name: String,
+    pub cpu: f64,
+    pub mem: f64,
+    /// Resident memory in bytes
+    #[serde(default)]
+    pub rss: u64,
+    /// Major page faults per second, filled in by the sampler
+    #[serde(default)]
+    pub major_faults: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ComponentSample {
+    pub label: String,
+    /// Degrees Celsius
+    pub temp: f64,
+}
+
+/// Point-in-time view of every metric a rule can refer to; recordings store one per line as JSON
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Snapshot {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    pub cpu: f64,
+    /// Utilization of each core in percent
+    #[serde(default)]
+    pub cores: Vec<f64>,
+    pub mem: f64,
+    pub swap: f64,
+    pub load: f64,
+    pub temp: f64,
+    pub disk: f64,
+    #[serde(default)]
+    pub processes: Vec<ProcessSample>,
+    #[serde(default)]
+    pub gpus: Vec<GpuSample>,
+    #[serde(default)]
+    pub filesystems: Vec<FsSample>,
+    #[serde(default)]
+    pub components: Vec<ComponentSample>,
+    /// Projection from the history store, filled in by the sampler
+    #[serde(default)]
+    pub disk_full_days: Option<f64>,
+    /// Swap and major fault rates, filled in by the sampler
+    #[serde(default)]
+    pub swap_in: f64,
+    #[serde(default)]
+    pub swap_out: f64,
+    #[serde(default)]
+    pub major_faults: f64,
+    /// Bytes received and transmitted per second over all interfaces, filled in by the sampler
+    #[serde(default)]
+    pub net_rx: f64,
+    #[serde(default)]
+    pub net_tx: f64,
+    /// The same per interface
+    #[serde(default)]
+    pub interfaces: Vec<InterfaceSample>,
+}
+
+/// A rule that fired, with the value that made it fire
+#[derive(Clone)]
+pub struct Hit {
+    pub value: f64,
+    pub pid: Option<i32>,
+    pub process: Option<String>,
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / total as f64
+}
+
+impl Snapshot {
+    pub fn take(sys: &mut System) -> Snapshot {
+        sys.refresh_all();
+        let total_memory = sys.total_memory();
+        let processes = sys
+            .processes()
+            .values()
+            .map(|p| ProcessSample {
+                pid: p.pid().as_u32() as i32,
+                name: p.name().to_string(),
+                cpu: p.cpu_usage() as f64,
+                mem: percent(p.memory(), total_memory),
+                rss: p.memory(),
+                major_faults: 0.0,
+            })
+            .collect();
+        Snapshot {
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            cpu: sys.global_cpu_info().cpu_usage() as f64,
+            cores: sys.cpus().iter().map(|c| c.cpu_usage() as f64).collect(),
+            mem: percent(sys.used_memory(), total_memory),
+            swap: percent(sys.used_swap(), sys.total_swap()),
+            load: sys.load_average().one,
+            temp: sys.components().iter().map(|c| c.temperature() as f64).fold(0.0, f64::max),
+            disk: sys
+                .disks()
+                .iter()
+                .map(|d| percent(d.total_space() - d.available_space(), d.total_space()))
+                .fold(0.0, f64::max),
+            processes,
+            gpus: gpu::read_gpus(),
+            filesystems: sys
+                .disks()
+                .iter()
+                .map(|d| FsSample {
+                    mount_point: d.mount_point().to_string_lossy().to_string(),
+                    total: d.total_space(),
+                    available: d.available_space(),
+                })
+                .collect(),
+            components: sys
+                .components()
+                .iter()
+                .map(|c| ComponentSample { label: c.label().to_string(), temp: c.temperature() as f64 })
+                .collect(),
+            disk_full_days: None,
+            swap_in: 0.0,
+            swap_out: 0.0,
+            major_faults: 0.0,
+            net_rx: 0.0,
+            net_tx: 0.0,
+            interfaces: vec![],
+        }
+    }
+}
+
+impl Rule {
+    fn matches(&self, value: f64) -> bool {
+        match self.op {
+            Op::Above => value > self.threshold,
+            Op::Below => value < self.threshold,
+        }
+    }
+
+    /// Evaluates the rule against `snap`; per-process rules yield one hit per offending process
+    pub fn evaluate(&self, snap: &Snapshot) -> Vec<Hit> {
+        let value = match self.metric {
+            Metric::Cpu => snap.cpu,
+            Metric::Mem => snap.mem,
+            Metric::Swap => snap.swap,
+            Metric::Load => snap.load,
+            Metric::Temp => snap.temp,
+            Metric::Disk => snap.disk,
+            Metric::GpuTemp => snap.gpus.iter().filter_map(|g| g.temp).fold(0.0, f64::max),
+            Metric::GpuUtil => snap.gpus.iter().filter_map(|g| g.util).fold(0.0, f64::max),
+            Metric::GpuMem => snap.gpus.iter().filter_map(|g| g.mem_percent()).fold(0.0, f64::max),
+            Metric::SwapIn => snap.swap_in,
+            Metric::SwapOut => snap.swap_out,
+            Metric::MajorFaults => snap.major_faults,
+            Metric::DiskFullDays => match snap.disk_full_days {
+                Some(days) => days,
+                None => return vec![],
+            },
+            Metric::ProcCpu | Metric::ProcMem | Metric::ProcMajorFaults => {
+                let pattern = self.pattern.as_deref().unwrap_or_default();
+                return snap
+                    .processes
+                    .iter()
+                    .filter(|p| p.name.contains(pattern))
+                    .map(|p| {
+                        let value = match self.metric {
+                            Metric::ProcCpu => p.cpu,
+                            Metric::ProcMem => p.mem,
+                            _ => p.major_faults,
+                        };
+                        (p, value)
+                    })
+                    .filter(|(_, value)| self.matches(*value))
+                    .map(|(p, value)| Hit {
+                        value,
+                        pid: Some(p.pid),
+                        process: Some(p.name.clone()),
+                    })
+                    .collect();
+            }
+        };
+        if !self.matches(value) {
+            return vec![];
+        }
+        vec![Hit { value, pid: None, process: None }]
+    }
+}
+
+/// Identifies one firing instance: the rule, and for per-process rules the process
+pub type AlertKey = (String, Option<i32>);
+
+/// What changed between two evaluations of a rule set
+#[derive(Default)]
+pub struct Step {
+    /// Rules (by index) that started firing
+    pub fired: Vec<(usize, Hit)>,
+    pub resolved: Vec<AlertKey>,
+    /// Everything firing now, including what just fired
+    pub firing: Vec<(usize, Hit)>,
+}
+
+/// Tracks how long each rule has held so `for` durations are honoured across samples
+#[derive(Default)]
+pub struct RuleState {
+    /// Snapshot time at which each instance started matching
+    pending: HashMap<AlertKey, u64>,
+    firing: HashSet<AlertKey>,
+}
+
+impl RuleState {
+    pub fn step(&mut self, rules: &[Rule], snap: &Snapshot) -> Step {
+        let mut step = Step::default();
+        let mut matching: HashSet<AlertKey> = HashSet::new();
+        for (i, rule) in rules.iter().enumerate() {
+            for hit in rule.evaluate(snap) {
+                let key = (rule.name.clone(), hit.pid);
+                let since = *self.pending.entry(key.clone()).or_insert(snap.time);
+                matching.insert(key.clone());
+                if snap.time.saturating_sub(since) < rule.duration.unwrap_or(0) {
+                    continue;
+                }
+                if self.firing.insert(key) {
+                    step.fired.push((i, hit.clone()));
+                }
+                step.firing.push((i, hit));
+            }
+        }
+        self.pending.retain(|key, _| matching.contains(key));
+        step.resolved = self.firing.iter().filter(|key| !matching.contains(*key)).cloned().collect();
+        self.firing.retain(|key| matching.contains(key));
+        step
+    }
+}
+
+/// Checks a rule set for mistakes serde cannot catch, returning one message per problem
+pub fn validate(rules: &[Rule]) -> Vec<String> {
+    let mut errors: Vec<String> = vec![];
+    for (i, rule) in rules.iter().enumerate() {
+        if rule.name.is_empty() {
+            errors.push(format!("rule #{}: name must not be empty", i + 1));
+        }
+        if rules[..i].iter().any(|r| r.name == rule.name) {
+            errors.push(format!("rule '{}': duplicate name", rule.name));
+        }
+        if rule.threshold < 0.0 || (rule.metric.is_percentage() && rule.threshold > 100.0) {
+            errors.push(format!("rule '{}': threshold {} out of range", rule.name, rule.threshold));
+        }
+        if rule.metric.is_per_process() && rule.pattern.as_deref().unwrap_or_default().is_empty() {
+            errors.push(format!("rule '{}': {:?} needs a process pattern", rule.name, rule.metric));
+        }
+        if rule.action == Action::Autokill && !rule.metric.is_per_process() {
+            errors.push(format!("rule '{}': autokill only applies to per-process metrics", rule.name));
+        }
+        if let Some(url) = &rule.webhook {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                errors.push(format!("rule '{}': webhook must be an http:// or https:// URL", rule.name));
+            }
+        }
+    }
+    errors
+}
+
+pub fn load(path: &Path) -> Result<Vec<Rule>, Box<dyn Error>> {
+    let file: RuleFile = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+    let errors = validate(&file.rules);
+    if !errors.is_empty() {
+        return Err(errors.join("; ").into());
+    }
+    Ok(file.rules)
+}
+
+pub fn save(path: &Path, rules: &[Rule]) -> Result<(), Box<dyn Error>> {
+    let file = RuleFile { rules: rules.to_vec() };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_yaml::to_string(&file)?)?;
+    Ok(())
+}
+
+pub fn describe(rule: &Rule) -> String {
+    let op = match rule.op {
+        Op::Above => ">",
+        Op::Below => "<",
+    };
+    let pattern = match &rule.pattern {
+        Some(pattern) => format!(" [{}]", pattern),
+        None => String::new(),
+    };
+    let duration = match rule.duration {
+        Some(secs) => format!(" for {}s", secs),
+        None => String::new(),
+    };
+    format!("{}: {:?}{} {} {}{} -> {:?}", rule.name, rule.metric, pattern, op, rule.threshold, duration, rule.action)
+}
+
+/// Evaluates every rule against a snapshot without acting on the result
+pub fn dry_run(rules: &[Rule], snap: &Snapshot) -> Vec<String> {
+    let mut vec: Vec<String> = vec![];
+    for rule in rules {
+        let hits = rule.evaluate(snap);
+        if hits.is_empty() {
+            vec.push(format!("ok    {}", describe(rule)));
+        }
+        for hit in hits {
+            match (hit.pid, hit.process) {
+                (Some(pid), Some(name)) => vec.push(format!("FIRE  {} (PID {} {}: {:.1})", describe(rule), pid, name, hit.value)),
+                _ => vec.push(format!("FIRE  {} (value {:.1})", describe(rule), hit.value)),
+            }
+        }
+    }
+    vec
+}
+
+fn format_offset(secs: u64) -> String {
+    format!("+{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Replays a recording (one JSON snapshot per line) through `rules`, reporting when each rule
+/// would have started and stopped firing
+pub fn replay(rules: &[Rule], path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut vec: Vec<String> = vec![];
+    let mut state = RuleState::default();
+    let mut fired: Vec<usize> = vec![0; rules.len()];
+    let mut start: Option<u64> = None;
+    let mut samples = 0;
+    for (n, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let snap: Snapshot = serde_json::from_str(line).map_err(|e| format!("{}:{}: {}", path, n + 1, e))?;
+        let offset = snap.time.saturating_sub(*start.get_or_insert(snap.time));
+        samples += 1;
+        let step = state.step(rules, &snap);
+        for (i, hit) in step.fired {
+            fired[i] += 1;
+            match (hit.pid, &hit.process) {
+                (Some(pid), Some(name)) => vec.push(format!("{} FIRED   {} (PID {} {}: {:.1})", format_offset(offset), rules[i].name, pid, name, hit.value)),
+                _ => vec.push(format!("{} FIRED   {} (value {:.1})", format_offset(offset), rules[i].name, hit.value)),
+            }
+        }
+        for (name, pid) in step.resolved {
+            match pid {
+                Some(pid) => vec.push(format!("{} cleared {} (PID {})", format_offset(offset), name, pid)),
+                None => vec.push(format!("{} cleared {}", format_offset(offset), name)),
+            }
+        }
+    }
+    vec.push(format!("Replayed {} samples from {}", samples, path));
+    let mut counts = Records::new(&["RULE", "FIRED"]);
+    for (rule, count) in rules.iter().zip(fired) {
+        counts.push(vec![rule.name.clone(), format!("{} times", count)]);
+    }
+    vec.extend(counts.lines());
+    Ok(vec)
+}
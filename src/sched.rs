@@ -0,0 +1,93 @@
+use crate::chart;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    time::Instant,
+};
+
+/// Samples of run queue latency kept per process for `schedlat`
+const SERIES_LEN: usize = 60;
+
+/// (time on CPU, time waiting on a run queue) in nanoseconds and the number of timeslices, from /proc/<pid>/schedstat
+fn read_schedstat(pid: i32) -> Option<(u64, u64, u64)> {
+    let stat = fs::read_to_string(format!("/proc/{}/schedstat", pid)).ok()?;
+    let mut fields = stat.split_whitespace().map(|f| f.parse::<u64>().ok());
+    Some((fields.next()??, fields.next()??, fields.next()??))
+}
+
+/// Run queue latency of one process over the last interval
+#[derive(Clone, Copy)]
+pub struct Latency {
+    /// Milliseconds spent runnable but waiting for a CPU, per second
+    pub wait: f64,
+    /// Average wait before each timeslice in milliseconds
+    pub per_slice: f64,
+}
+
+/// Turns the cumulative schedstat counters into per-interval run queue latency
+pub struct SchedTracker {
+    prev: HashMap<i32, (u64, u64, u64)>,
+    at: Instant,
+    pub latency: HashMap<i32, Latency>,
+    /// Recent `wait` values per PID, oldest first
+    pub series: HashMap<i32, VecDeque<f64>>,
+}
+
+impl Default for SchedTracker {
+    fn default() -> SchedTracker {
+        SchedTracker { prev: HashMap::new(), at: Instant::now(), latency: HashMap::new(), series: HashMap::new() }
+    }
+}
+
+impl SchedTracker {
+    pub fn update(&mut self) {
+        let secs = self.at.elapsed().as_secs_f64().max(0.001);
+        let pids: Vec<i32> = fs::read_dir("/proc")
+            .map(|entries| entries.filter_map(|e| e.ok()?.file_name().to_str()?.parse::<i32>().ok()).collect())
+            .unwrap_or_default();
+        let now: HashMap<i32, (u64, u64, u64)> = pids.into_iter().filter_map(|pid| Some((pid, read_schedstat(pid)?))).collect();
+        self.latency = now
+            .iter()
+            .filter_map(|(pid, (_, wait, slices))| {
+                let (_, prev_wait, prev_slices) = self.prev.get(pid)?;
+                let waited = wait.saturating_sub(*prev_wait) as f64 / 1e6;
+                let slices = slices.saturating_sub(*prev_slices);
+                let per_slice = if slices == 0 { 0.0 } else { waited / slices as f64 };
+                Some((*pid, Latency { wait: waited / secs, per_slice }))
+            })
+            .collect();
+        self.series.retain(|pid, _| now.contains_key(pid));
+        for (pid, latency) in &self.latency {
+            let series = self.series.entry(*pid).or_default();
+            if series.len() == SERIES_LEN {
+                series.pop_front();
+            }
+            series.push_back(latency.wait);
+        }
+        self.prev = now;
+        self.at = Instant::now();
+    }
+}
+
+/// Run queue latency of `pid` now and over the last few minutes
+pub fn get_latency_information(tracker: &SchedTracker, pid: i32) -> Vec<String> {
+    let mut vec: Vec<String> = vec![];
+    let name = match fs::read_to_string(format!("/proc/{}/comm", pid)) {
+        Ok(name) => name.trim().to_string(),
+        Err(_) => return vec![format!("Process not found with PID {}", pid)],
+    };
+    vec.push(format!("Run queue latency of PID {} ({})", pid, name));
+    match tracker.latency.get(&pid) {
+        Some(latency) => {
+            vec.push(format!("waiting for a CPU: {:.2} ms/s ({:.1}% of the time)", latency.wait, latency.wait / 10.0));
+            vec.push(format!("average wait per timeslice: {:.3} ms", latency.per_slice));
+        },
+        None => vec.push("Waiting for the next sample...".to_string()),
+    }
+    if let Some(series) = tracker.series.get(&pid) {
+        let values: Vec<f64> = series.iter().copied().collect();
+        let peak = values.iter().copied().fold(0.0, f64::max);
+        vec.push(format!("history (peak {:.2} ms/s): {}", peak, chart::sparkline(&values, SERIES_LEN)));
+    }
+    vec
+}
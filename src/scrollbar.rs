@@ -0,0 +1,43 @@
+use tui::{buffer::Buffer, layout::Rect, style::Style, widgets::Widget};
+
+/// How far a listing is scrolled, drawn over the right border of its box when it does not all fit
+pub struct Scrollbar {
+    /// First item shown, how many fit and how many there are
+    pub top: usize,
+    pub shown: usize,
+    pub len: usize,
+    pub style: Style,
+}
+
+impl Scrollbar {
+    /// The right border of a box drawn in `area`, below `header` rows of its inside
+    pub fn track(area: Rect, header: u16) -> Rect {
+        Rect {
+            x: area.right().saturating_sub(1),
+            y: area.y + 1 + header,
+            width: area.width.min(1),
+            height: area.height.saturating_sub(2 + header),
+        }
+    }
+}
+
+impl Widget for Scrollbar {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.len <= self.shown || area.width == 0 || area.height == 0 {
+            return;
+        }
+        let track = area.height as usize;
+        let thumb = (track * self.shown / self.len).clamp(1, track);
+        // The thumb only touches the bottom once the last item is in sight
+        let start = if self.top + self.shown >= self.len { track - thumb } else { (track * self.top / self.len).min(track - thumb) };
+        for (i, y) in (area.top()..area.bottom()).enumerate() {
+            let symbol = if (start..start + thumb).contains(&i) { "█" } else { "│" };
+            buf.get_mut(area.x, y).set_symbol(symbol).set_style(self.style);
+        }
+    }
+}
+
+/// "lines 41–80 of 200" for the title of a listing scrolled to `top`
+pub fn describe(top: usize, shown: usize, len: usize) -> String {
+    format!("lines {}–{} of {}", top + 1, (top + shown).min(len), len)
+}
@@ -0,0 +1,57 @@
+use tui::{
+    style::Style,
+    text::{Span, Spans},
+};
+
+/// What `/` looks for in the output, and the matching line the output is scrolled to
+pub struct Search {
+    pub query: String,
+    pub current: Option<usize>,
+}
+
+impl Search {
+    pub fn new(query: &str) -> Search {
+        Search { query: query.to_ascii_lowercase(), current: None }
+    }
+
+    /// Ignoring ASCII case, so match positions stay valid in the original text
+    pub fn matches(&self, line: &str) -> bool {
+        !self.query.is_empty() && line.to_ascii_lowercase().contains(&self.query)
+    }
+
+    /// Moves to the next (or previous) matching line from the current one, wrapping around
+    pub fn step(&mut self, lines: &[String], forward: bool) {
+        let matches: Vec<usize> = (0..lines.len()).filter(|i| self.matches(&lines[*i])).collect();
+        self.current = match (self.current, forward) {
+            (None, _) => matches.first().copied(),
+            (Some(at), true) => matches.iter().find(|i| **i > at).or(matches.first()).copied(),
+            (Some(at), false) => matches.iter().rev().find(|i| **i < at).or(matches.last()).copied(),
+        };
+    }
+
+    /// "/query 2 of 5" for the title of the output pane
+    pub fn describe(&self, lines: &[String]) -> String {
+        let matches: Vec<usize> = (0..lines.len()).filter(|i| self.matches(&lines[*i])).collect();
+        match self.current.and_then(|at| matches.iter().position(|i| *i == at)) {
+            Some(n) => format!("/{} {} of {}", self.query, n + 1, matches.len()),
+            None => format!("/{} no matches", self.query),
+        }
+    }
+
+    /// `text` with every match of the query in `style`
+    pub fn highlight(&self, text: &str, style: Style) -> Spans<'static> {
+        if self.query.is_empty() {
+            return Spans::from(text.to_string());
+        }
+        let lower = text.to_ascii_lowercase();
+        let mut spans: Vec<Span> = vec![];
+        let mut at = 0;
+        for (start, _) in lower.match_indices(&self.query) {
+            spans.push(Span::raw(text[at..start].to_string()));
+            spans.push(Span::styled(text[start..start + self.query.len()].to_string(), style));
+            at = start + self.query.len();
+        }
+        spans.push(Span::raw(text[at..].to_string()));
+        Spans::from(spans)
+    }
+}
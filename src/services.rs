@@ -0,0 +1,45 @@
+use crate::records::Records;
+use std::process::Command;
+
+/// Lists systemd service units with their load/active/sub states, failed units first
+pub fn get_services() -> Result<Records, String> {
+    let output = match Command::new("systemctl")
+        .args(["list-units", "--type=service", "--all", "--no-legend", "--no-pager", "--plain"])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => return Err(format!("Error running systemctl: {}", e)),
+    };
+    if !output.status.success() {
+        return Err(format!("systemctl: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut units: Vec<(Vec<&str>, String)> = stdout
+        .lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let description = fields.iter().skip(4).copied().collect::<Vec<&str>>().join(" ");
+            (fields, description)
+        })
+        .filter(|(fields, _)| fields.len() >= 4)
+        .collect();
+    units.sort_by_key(|(fields, _)| (fields[2] != "failed", fields[0].to_string()));
+    let mut records = Records::new(&["UNIT", "LOAD", "ACTIVE", "SUB", "DESCRIPTION"]);
+    for (fields, description) in units {
+        records.push(vec![fields[0].to_string(), fields[1].to_string(), fields[2].to_string(), fields[3].to_string(), description]);
+    }
+    Ok(records)
+}
+
+/// Runs `systemctl <action> <unit>` and reports the outcome
+pub fn control_service(action: &str, unit: &str) -> Vec<String> {
+    if !matches!(action, "start" | "stop" | "restart") {
+        return vec!["usage: service start|stop|restart <unit>".to_string()];
+    }
+    // Never let systemctl prompt for a password: the terminal is in raw mode behind the TUI
+    match Command::new("systemctl").args(["--no-ask-password", action, unit]).output() {
+        Ok(output) if output.status.success() => vec![format!("{} {}: ok", action, unit)],
+        Ok(output) => vec![format!("{} {} failed ({}): {}", action, unit, output.status, String::from_utf8_lossy(&output.stderr).trim())],
+        Err(e) => vec![format!("Error running systemctl: {}", e)],
+    }
+}
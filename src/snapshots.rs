@@ -0,0 +1,101 @@
+use crate::{
+    paths,
+    records::Records,
+    rules::{ProcessSample, Snapshot},
+};
+use chrono::{DateTime, Local};
+use pretty_bytes::converter::convert;
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+
+/// Smallest CPU change, in percent of one core, worth listing in a diff
+const CPU_DELTA: f64 = 10.0;
+/// Smallest memory change, in percent of RAM, worth listing in a diff
+const MEM_DELTA: f64 = 1.0;
+
+fn dir() -> PathBuf {
+    paths::state_dir().join("snapshots")
+}
+
+fn path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name == "now" || !name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) || name.starts_with('.') {
+        return Err(format!("snapshot: invalid name '{}'; use letters, digits, '-', '_' and '.'", name));
+    }
+    Ok(dir().join(format!("{}.json", name)))
+}
+
+pub fn save(name: &str, snap: &Snapshot) -> Result<PathBuf, Box<dyn Error>> {
+    let path = path(name)?;
+    fs::create_dir_all(dir())?;
+    fs::write(&path, serde_json::to_string(snap)?)?;
+    Ok(path)
+}
+
+pub fn load(name: &str) -> Result<Snapshot, Box<dyn Error>> {
+    let path = path(name)?;
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn taken_at(snap: &Snapshot) -> String {
+    DateTime::from_timestamp(snap.time as i64, 0).map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default()
+}
+
+pub fn get_snapshot_list() -> Records {
+    let mut names: Vec<String> = fs::read_dir(dir())
+        .map(|entries| entries.filter_map(|e| Some(e.ok()?.file_name().to_str()?.strip_suffix(".json")?.to_string())).collect())
+        .unwrap_or_default();
+    names.sort();
+    let mut records = Records::new(&["NAME", "TAKEN", "PROCESSES"]);
+    for name in names {
+        match load(&name) {
+            Ok(snap) => records.push(vec![name, taken_at(&snap), snap.processes.len().to_string()]),
+            Err(e) => records.push(vec![name, format!("unreadable: {}", e)]),
+        }
+    }
+    records
+}
+
+/// CPU, memory and RSS of a process in one cell
+fn usage(p: &ProcessSample) -> String {
+    format!("cpu {:.1}% mem {:.1}% rss {}", p.cpu, p.mem, convert(p.rss as f64))
+}
+
+/// When each snapshot was taken, the system-wide deltas, then new and exited processes and large
+/// per-process CPU/MEM changes from `a` to `b`, as BEFORE/AFTER/DELTA rows
+pub fn diff(a: &Snapshot, b: &Snapshot) -> Records {
+    let mut records = Records::new(&["CHANGE", "PID", "NAME", "BEFORE", "AFTER", "DELTA"]);
+    let dash = || "-".to_string();
+    records.push(vec!["taken".to_string(), dash(), dash(), taken_at(a), taken_at(b), format!("{}s", b.time as i64 - a.time as i64)]);
+    for (label, before, after) in [("cpu %", a.cpu, b.cpu), ("mem %", a.mem, b.mem), ("swap %", a.swap, b.swap), ("load", a.load, b.load), ("temp °C", a.temp, b.temp), ("disk %", a.disk, b.disk)] {
+        records.push(vec!["system".to_string(), dash(), label.to_string(), format!("{:.1}", before), format!("{:.1}", after), format!("{:+.1}", after - before)]);
+    }
+    // A PID reused by a different program counts as one process exiting and another starting
+    let key = |p: &ProcessSample| (p.pid, p.name.clone());
+    let before: HashMap<(i32, String), &ProcessSample> = a.processes.iter().map(|p| (key(p), p)).collect();
+    let after: HashMap<(i32, String), &ProcessSample> = b.processes.iter().map(|p| (key(p), p)).collect();
+
+    let mut started: Vec<&ProcessSample> = b.processes.iter().filter(|p| !before.contains_key(&key(p))).collect();
+    started.sort_by_key(|p| p.pid);
+    for p in started {
+        records.push(vec!["started".to_string(), p.pid.to_string(), p.name.clone(), dash(), usage(p), dash()]);
+    }
+
+    let mut exited: Vec<&ProcessSample> = a.processes.iter().filter(|p| !after.contains_key(&key(p))).collect();
+    exited.sort_by_key(|p| p.pid);
+    for p in exited {
+        records.push(vec!["exited".to_string(), p.pid.to_string(), p.name.clone(), usage(p), dash(), dash()]);
+    }
+
+    let mut changed: Vec<(&ProcessSample, &ProcessSample)> = b
+        .processes
+        .iter()
+        .filter_map(|p| Some((*before.get(&key(p))?, p)))
+        .filter(|(old, new)| (new.cpu - old.cpu).abs() >= CPU_DELTA || (new.mem - old.mem).abs() >= MEM_DELTA)
+        .collect();
+    changed.sort_by(|x, y| ((y.1.cpu - y.0.cpu).abs() + (y.1.mem - y.0.mem).abs()).total_cmp(&((x.1.cpu - x.0.cpu).abs() + (x.1.mem - x.0.mem).abs())));
+    for (old, new) in changed {
+        let delta = format!("cpu {:+.1}% mem {:+.1}%", new.cpu - old.cpu, new.mem - old.mem);
+        records.push(vec!["changed".to_string(), new.pid.to_string(), new.name.clone(), usage(old), usage(new), delta]);
+    }
+    records
+}
@@ -0,0 +1,102 @@
+use libbpf_rs::{
+    skel::{OpenSkel, SkelBuilder},
+    RingBufferBuilder,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+mod skel {
+    include!(concat!(env!("OUT_DIR"), "/snoop.skel.rs"));
+}
+use skel::SnoopSkelBuilder;
+
+/// How long a ring buffer poll blocks before the stop flag is checked again
+const POLL: Duration = Duration::from_millis(100);
+
+const TASK_COMM_LEN: usize = 16;
+const NAME_LEN: usize = 256;
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Exec,
+    Open,
+}
+
+/// A NUL terminated C string field
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+/// Formats a `struct event` from snoop.bpf.c: pid, uid, kind, comm, name
+fn format_event(data: &[u8]) -> Option<String> {
+    let pid = u32::from_ne_bytes(data.get(0..4)?.try_into().ok()?);
+    let uid = u32::from_ne_bytes(data.get(4..8)?.try_into().ok()?);
+    let kind = *data.get(8)?;
+    let comm = c_string(data.get(9..9 + TASK_COMM_LEN)?);
+    let name = c_string(data.get(9 + TASK_COMM_LEN..9 + TASK_COMM_LEN + NAME_LEN)?);
+    let event = if kind == 0 { "exec" } else { "open" };
+    Some(format!("{} {:>7} {:>6} {:<16} {} {}", chrono::Local::now().format("%H:%M:%S"), pid, uid, comm, event, name))
+}
+
+fn run(kind: Kind, tx: Sender<String>, stop: Arc<AtomicBool>) -> Result<(), libbpf_rs::Error> {
+    let mut skel = SnoopSkelBuilder::default().open()?.load()?;
+    // Dropping the link detaches the program
+    let _link = match kind {
+        Kind::Exec => skel.progs_mut().handle_exec().attach()?,
+        Kind::Open => skel.progs_mut().handle_openat().attach()?,
+    };
+    let mut builder = RingBufferBuilder::new();
+    builder.add(skel.maps().events(), move |data: &[u8]| {
+        if let Some(line) = format_event(data) {
+            // The receiver is gone once the view closed; the stop flag ends the loop
+            let _ = tx.send(line);
+        }
+        0
+    })?;
+    let ring = builder.build()?;
+    while !stop.load(Ordering::Relaxed) {
+        ring.poll(POLL)?;
+    }
+    Ok(())
+}
+
+/// `execsnoop` or `opensnoop`: new executions or file opens system-wide, read from an eBPF ring buffer
+pub struct Snoop {
+    lines: Receiver<String>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Snoop {
+    /// Loading eBPF programs takes root, or CAP_BPF and CAP_PERFMON
+    pub fn start(kind: Kind) -> Snoop {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let _ = tx.send(format!("{:<8} {:>7} {:>6} {:<16} {}", "TIME", "PID", "UID", "COMM", "EVENT"));
+        thread::spawn(move || {
+            if let Err(e) = run(kind, tx.clone(), thread_stop) {
+                let _ = tx.send(format!("Error loading eBPF program: {}", e));
+            }
+        });
+        Snoop { lines: rx, stop }
+    }
+
+    /// Events received since the last call
+    pub fn drain(&self) -> Vec<String> {
+        self.lines.try_iter().collect()
+    }
+}
+
+impl Drop for Snoop {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
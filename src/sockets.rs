@@ -0,0 +1,166 @@
+use crate::records::Records;
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+/// A TCP or UDP socket from /proc/net
+pub struct Socket {
+    pub proto: &'static str,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: &'static str,
+    pub inode: u64,
+}
+
+impl Socket {
+    pub fn listening(&self) -> bool {
+        // Unconnected UDP sockets are the UDP equivalent of a listener
+        self.state == "LISTEN" || (self.proto.starts_with("udp") && self.state == "UNCONN")
+    }
+}
+
+fn tcp_state(code: &str) -> &'static str {
+    match code {
+        "01" => "ESTAB",
+        "02" => "SYN-SENT",
+        "03" => "SYN-RECV",
+        "04" => "FIN-WAIT-1",
+        "05" => "FIN-WAIT-2",
+        "06" => "TIME-WAIT",
+        "07" => "CLOSE",
+        "08" => "CLOSE-WAIT",
+        "09" => "LAST-ACK",
+        "0A" => "LISTEN",
+        "0B" => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Decodes "0100007F:0035" style addresses: the kernel prints the raw address words as native integers, the port as a number
+fn parse_address(field: &str) -> Option<SocketAddr> {
+    let (ip, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let words: Vec<u32> = (0..ip.len() / 8)
+        .map(|i| u32::from_str_radix(&ip[i * 8..i * 8 + 8], 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let ip = match words.as_slice() {
+        [a] => IpAddr::V4(Ipv4Addr::from(a.to_ne_bytes())),
+        [a, b, c, d] => {
+            let mut bytes = [0u8; 16];
+            for (i, word) in [a, b, c, d].iter().enumerate() {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word.to_ne_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(bytes))
+        },
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Every TCP/UDP socket of the current network namespace
+pub fn read_sockets() -> Vec<Socket> {
+    let mut sockets: Vec<Socket> = vec![];
+    for proto in ["tcp", "tcp6", "udp", "udp6"] {
+        let Ok(table) = fs::read_to_string(format!("/proc/net/{}", proto)) else {
+            continue;
+        };
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                continue;
+            }
+            let (Some(local), Some(remote), Ok(inode)) = (parse_address(fields[1]), parse_address(fields[2]), fields[9].parse::<u64>()) else {
+                continue;
+            };
+            let state = if proto.starts_with("udp") {
+                if fields[3] == "01" { "ESTAB" } else { "UNCONN" }
+            } else {
+                tcp_state(fields[3])
+            };
+            sockets.push(Socket { proto, local, remote, state, inode });
+        }
+    }
+    sockets
+}
+
+/// Maps socket inodes to the (PID, name) of a process holding them open, by walking /proc/<pid>/fd
+pub fn socket_owners() -> HashMap<u64, (i32, String)> {
+    let mut owners: HashMap<u64, (i32, String)> = HashMap::new();
+    let pids: Vec<i32> = fs::read_dir("/proc")
+        .map(|entries| entries.filter_map(|e| e.ok()?.file_name().to_str()?.parse::<i32>().ok()).collect())
+        .unwrap_or_default();
+    for pid in pids {
+        // Other users' fds are unreadable without privileges; those sockets stay unattributed
+        let Ok(fds) = fs::read_dir(format!("/proc/{}/fd", pid)) else {
+            continue;
+        };
+        let name = fs::read_to_string(format!("/proc/{}/comm", pid)).map(|c| c.trim().to_string()).unwrap_or_default();
+        for fd in fds.filter_map(|e| e.ok()) {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok());
+            if let Some(inode) = inode {
+                owners.entry(inode).or_insert_with(|| (pid, name.clone()));
+            }
+        }
+    }
+    owners
+}
+
+fn owner_column(owners: &HashMap<u64, (i32, String)>, inode: u64) -> String {
+    match owners.get(&inode) {
+        Some((pid, name)) => format!("{}/{}", pid, name),
+        None => "-".to_string(),
+    }
+}
+
+/// Lists sockets with their owning process, optionally only those whose addresses or owner contain `filter`
+pub fn get_connections(filter: Option<&str>) -> Records {
+    let owners = socket_owners();
+    let mut records = Records::new(&["Proto", "State", "Local Address", "Peer Address", "Process"]);
+    for socket in read_sockets() {
+        let local = socket.local.to_string();
+        let remote = socket.remote.to_string();
+        let process = owner_column(&owners, socket.inode);
+        if let Some(filter) = filter {
+            if !(local.contains(filter) || remote.contains(filter) || process.contains(filter)) {
+                continue;
+            }
+        }
+        records.push(vec![socket.proto.to_string(), socket.state.to_string(), local, remote, process]);
+    }
+    records
+}
+
+/// Listening sockets ordered by port
+pub fn get_listening_ports() -> Records {
+    let owners = socket_owners();
+    let mut sockets: Vec<Socket> = read_sockets().into_iter().filter(|s| s.listening()).collect();
+    sockets.sort_by_key(|s| (s.local.port(), s.proto));
+    let mut records = Records::new(&["Port", "Proto", "Address", "Process"]);
+    for socket in sockets {
+        records.push(vec![socket.local.port().to_string(), socket.proto.to_string(), socket.local.ip().to_string(), owner_column(&owners, socket.inode)]);
+    }
+    records
+}
+
+/// PIDs of the processes listening on `port`
+pub fn port_owners(port: u16) -> Vec<i32> {
+    let owners = socket_owners();
+    let mut pids: Vec<i32> = read_sockets()
+        .iter()
+        .filter(|s| s.listening() && s.local.port() == port)
+        .filter_map(|s| owners.get(&s.inode).map(|(pid, _)| *pid))
+        .collect();
+    pids.sort();
+    pids.dedup();
+    pids
+}
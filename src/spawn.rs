@@ -0,0 +1,52 @@
+use crate::rules::Snapshot;
+use std::collections::HashSet;
+
+/// A pattern `notify-on-spawn` looks for in newly started process names
+pub struct SpawnWatch {
+    pub id: usize,
+    pub pattern: String,
+    /// Also raise a desktop notification
+    pub desktop: bool,
+}
+
+/// Compares each sample's process list with the previous one. Processes that start and exit
+/// between two samples are never seen.
+#[derive(Default)]
+pub struct SpawnWatcher {
+    pub list: Vec<SpawnWatch>,
+    next_id: usize,
+    /// (PID, name) of every process in the previous sample, `None` before the first one
+    known: Option<HashSet<(i32, String)>>,
+}
+
+impl SpawnWatcher {
+    pub fn add(&mut self, pattern: &str, desktop: bool) -> &SpawnWatch {
+        self.next_id += 1;
+        self.list.push(SpawnWatch { id: self.next_id, pattern: pattern.to_string(), desktop });
+        self.list.last().unwrap()
+    }
+
+    pub fn remove(&mut self, id: usize) -> bool {
+        let before = self.list.len();
+        self.list.retain(|w| w.id != id);
+        self.list.len() != before
+    }
+
+    /// Messages for watched processes that appeared since the previous sample, each with whether
+    /// it should go to the desktop too
+    pub fn check(&mut self, snap: &Snapshot) -> Vec<(String, bool)> {
+        let current: HashSet<(i32, String)> = snap.processes.iter().map(|p| (p.pid, p.name.clone())).collect();
+        let mut events: Vec<(String, bool)> = vec![];
+        if let Some(known) = &self.known {
+            let mut started: Vec<&(i32, String)> = current.difference(known).collect();
+            started.sort();
+            for (pid, name) in started {
+                for watch in self.list.iter().filter(|w| name.contains(w.pattern.as_str())) {
+                    events.push((format!("spawned: PID {} {} matches '{}'", pid, name, watch.pattern), watch.desktop));
+                }
+            }
+        }
+        self.known = Some(current);
+        events
+    }
+}
@@ -0,0 +1,89 @@
+use crate::records::Records;
+use pretty_bytes::converter::convert;
+use std::{collections::HashMap, fs, path::Path};
+
+struct Mount {
+    fs_type: String,
+    mount_point: String,
+}
+
+/// Maps device names (`sda1`) to their /proc/mounts entries
+fn read_mounts() -> HashMap<String, Vec<Mount>> {
+    let mut mounts: HashMap<String, Vec<Mount>> = HashMap::new();
+    for line in fs::read_to_string("/proc/mounts").unwrap_or_default().lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || !fields[0].starts_with("/dev/") {
+            continue;
+        }
+        mounts.entry(fields[0]["/dev/".len()..].to_string()).or_default().push(Mount {
+            fs_type: fields[2].to_string(),
+            mount_point: fields[1].replace("\\040", " "),
+        });
+    }
+    mounts
+}
+
+/// Size of a block device in bytes; sysfs reports it in 512-byte sectors regardless of the device
+fn block_size(dir: &Path) -> u64 {
+    fs::read_to_string(dir.join("size"))
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(0)
+        * 512
+}
+
+fn device_type(name: &str) -> &'static str {
+    if name.starts_with("loop") {
+        "loop"
+    } else if name.starts_with("sr") {
+        "rom"
+    } else if name.starts_with("dm-") {
+        "dm"
+    } else {
+        "disk"
+    }
+}
+
+fn block_row(records: &mut Records, prefix: &str, name: &str, size: u64, kind: &str, mounts: &HashMap<String, Vec<Mount>>) {
+    let entries = mounts.get(name);
+    let fs_type = entries.and_then(|m| m.first()).map(|m| m.fs_type.as_str()).unwrap_or("");
+    let mount_points = entries
+        .map(|m| m.iter().map(|m| m.mount_point.as_str()).collect::<Vec<&str>>().join(","))
+        .unwrap_or_default();
+    records.push(vec![format!("{}{}", prefix, name), convert(size as f64), kind.to_string(), fs_type.to_string(), mount_points]);
+}
+
+/// Renders disks and their partitions as a tree, in the spirit of `lsblk`
+pub fn get_block_devices() -> Result<Records, String> {
+    let mounts = read_mounts();
+    let mut disks: Vec<String> = match fs::read_dir("/sys/block") {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.file_name().to_string_lossy().to_string()).collect(),
+        Err(e) => return Err(format!("Error reading /sys/block: {}", e)),
+    };
+    disks.sort();
+    let mut records = Records::new(&["NAME", "SIZE", "TYPE", "FSTYPE", "MOUNTPOINTS"]);
+    for disk in disks {
+        let dir = Path::new("/sys/block").join(&disk);
+        let size = block_size(&dir);
+        // Unattached loop devices and empty drives report a size of zero
+        if size == 0 {
+            continue;
+        }
+        block_row(&mut records, "", &disk, size, device_type(&disk), &mounts);
+        let mut partitions: Vec<String> = fs::read_dir(&dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().join("partition").exists())
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        partitions.sort_by_key(|p| (p.len(), p.clone()));
+        for (i, partition) in partitions.iter().enumerate() {
+            let prefix = if i + 1 == partitions.len() { "└─" } else { "├─" };
+            block_row(&mut records, prefix, partition, block_size(&dir.join(partition)), "part", &mounts);
+        }
+    }
+    Ok(records)
+}
@@ -0,0 +1,212 @@
+use crate::{config, paths};
+use std::{fs, io::ErrorKind, path::PathBuf};
+use tui::style::{Color, Modifier, Style};
+
+/// Built-in themes, the first being the default
+pub const NAMES: [&str; 4] = ["dark", "light", "solarized", "monochrome"];
+
+/// Every colour `ui()` draws with; `Color::Reset` keeps the terminal's own
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    /// Behind everything
+    pub background: Color,
+    /// Output text and table cells
+    pub text: Color,
+    pub border: Color,
+    /// Input box while not editing, editing and going to a row
+    pub input: Color,
+    pub editing: Color,
+    pub goto: Color,
+    /// Selected tab
+    pub accent: Color,
+    pub alert_fg: Color,
+    pub alert_bg: Color,
+    pub warning_fg: Color,
+    pub warning_bg: Color,
+    /// Alert log entries that fired, and the rest
+    pub fired: Color,
+    pub muted: Color,
+    /// %CPU of process table rows, from idle to busy
+    pub cpu_low: Color,
+    pub cpu_mid: Color,
+    pub cpu_high: Color,
+    /// Zombie rows and the owner of root processes
+    pub zombie: Color,
+    pub root: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::builtin(NAMES[0]).unwrap()
+    }
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "dark" => Theme {
+                name: name.to_string(),
+                background: Color::Reset,
+                text: Color::Green,
+                border: Color::Reset,
+                input: Color::Yellow,
+                editing: Color::Green,
+                goto: Color::Cyan,
+                accent: Color::Yellow,
+                alert_fg: Color::White,
+                alert_bg: Color::Red,
+                warning_fg: Color::Black,
+                warning_bg: Color::Yellow,
+                fired: Color::Red,
+                muted: Color::Gray,
+                cpu_low: Color::Green,
+                cpu_mid: Color::Yellow,
+                cpu_high: Color::Red,
+                zombie: Color::Magenta,
+                root: Color::LightBlue,
+            },
+            "light" => Theme {
+                name: name.to_string(),
+                background: Color::White,
+                text: Color::Rgb(0x1b, 0x5e, 0x20),
+                border: Color::DarkGray,
+                input: Color::Blue,
+                editing: Color::Rgb(0x1b, 0x5e, 0x20),
+                goto: Color::Magenta,
+                accent: Color::Blue,
+                alert_fg: Color::White,
+                alert_bg: Color::Red,
+                warning_fg: Color::Black,
+                warning_bg: Color::LightYellow,
+                fired: Color::Red,
+                muted: Color::DarkGray,
+                cpu_low: Color::Rgb(0x1b, 0x5e, 0x20),
+                cpu_mid: Color::Rgb(0xb5, 0x89, 0x00),
+                cpu_high: Color::Red,
+                zombie: Color::Magenta,
+                root: Color::Blue,
+            },
+            // https://ethanschoonover.com/solarized/
+            "solarized" => Theme {
+                name: name.to_string(),
+                background: Color::Rgb(0x00, 0x2b, 0x36),
+                text: Color::Rgb(0x83, 0x94, 0x96),
+                border: Color::Rgb(0x58, 0x6e, 0x75),
+                input: Color::Rgb(0xb5, 0x89, 0x00),
+                editing: Color::Rgb(0x85, 0x99, 0x00),
+                goto: Color::Rgb(0x2a, 0xa1, 0x98),
+                accent: Color::Rgb(0x26, 0x8b, 0xd2),
+                alert_fg: Color::Rgb(0xfd, 0xf6, 0xe3),
+                alert_bg: Color::Rgb(0xdc, 0x32, 0x2f),
+                warning_fg: Color::Rgb(0x00, 0x2b, 0x36),
+                warning_bg: Color::Rgb(0xb5, 0x89, 0x00),
+                fired: Color::Rgb(0xdc, 0x32, 0x2f),
+                muted: Color::Rgb(0x58, 0x6e, 0x75),
+                cpu_low: Color::Rgb(0x85, 0x99, 0x00),
+                cpu_mid: Color::Rgb(0xb5, 0x89, 0x00),
+                cpu_high: Color::Rgb(0xdc, 0x32, 0x2f),
+                zombie: Color::Rgb(0xd3, 0x36, 0x82),
+                root: Color::Rgb(0x26, 0x8b, 0xd2),
+            },
+            // Alerts and warnings stand out through reversed video rather than colour
+            "monochrome" => Theme {
+                name: name.to_string(),
+                background: Color::Reset,
+                text: Color::Reset,
+                border: Color::Reset,
+                input: Color::Reset,
+                editing: Color::Reset,
+                goto: Color::Reset,
+                accent: Color::Reset,
+                alert_fg: Color::Reset,
+                alert_bg: Color::Reset,
+                warning_fg: Color::Reset,
+                warning_bg: Color::Reset,
+                fired: Color::Reset,
+                muted: Color::Reset,
+                cpu_low: Color::Reset,
+                cpu_mid: Color::Reset,
+                cpu_high: Color::Reset,
+                zombie: Color::Reset,
+                root: Color::Reset,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// The built-in theme `name` with the colours set in theme.toml put over it
+    pub fn load(name: &str) -> Result<Theme, String> {
+        let mut theme = Theme::builtin(name).ok_or_else(|| format!("unknown theme '{}'; try {}", name, NAMES.join(", ")))?;
+        let text = match fs::read_to_string(path()) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(theme),
+            Err(e) => return Err(e.to_string()),
+        };
+        let table: toml::Table = toml::from_str(&text).map_err(|e| e.to_string())?;
+        for (key, value) in &table {
+            let Some(name) = value.as_str() else {
+                return Err(format!("{}: expected a colour name", key));
+            };
+            let color = config::parse_color(name)?.unwrap_or(Color::Reset);
+            *theme.slot(key).ok_or_else(|| format!("unknown theme colour '{}'", key))? = color;
+        }
+        Ok(theme)
+    }
+
+    fn slot(&mut self, key: &str) -> Option<&mut Color> {
+        let slot = match key {
+            "background" => &mut self.background,
+            "text" => &mut self.text,
+            "border" => &mut self.border,
+            "input" => &mut self.input,
+            "editing" => &mut self.editing,
+            "goto" => &mut self.goto,
+            "accent" => &mut self.accent,
+            "alert_fg" => &mut self.alert_fg,
+            "alert_bg" => &mut self.alert_bg,
+            "warning_fg" => &mut self.warning_fg,
+            "warning_bg" => &mut self.warning_bg,
+            "fired" => &mut self.fired,
+            "muted" => &mut self.muted,
+            "cpu_low" => &mut self.cpu_low,
+            "cpu_mid" => &mut self.cpu_mid,
+            "cpu_high" => &mut self.cpu_high,
+            "zombie" => &mut self.zombie,
+            "root" => &mut self.root,
+            _ => return None,
+        };
+        Some(slot)
+    }
+
+    /// Output text
+    pub fn text(&self) -> Style {
+        Style::default().fg(self.text).bg(self.background)
+    }
+
+    pub fn border(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+
+    pub fn alert(&self) -> Style {
+        self.banner(self.alert_fg, self.alert_bg).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn warning(&self) -> Style {
+        self.banner(self.warning_fg, self.warning_bg)
+    }
+
+    fn banner(&self, fg: Color, bg: Color) -> Style {
+        if fg == Color::Reset && bg == Color::Reset {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(fg).bg(bg)
+        }
+    }
+}
+
+/// Colour overrides opened by `edit theme`, e.g. `text = "white"` or `accent = "#268bd2"`
+pub fn path() -> PathBuf {
+    paths::config_dir().join("theme.toml")
+}
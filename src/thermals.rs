@@ -0,0 +1,34 @@
+use crate::{chart, history::History, records::Records};
+
+/// Samples drawn per component, two minutes at the default tick
+const CHART_WIDTH: usize = 60;
+
+/// Every component's temperature now, its range over the history and a sparkline of the recent
+/// trend, or why there is nothing to show yet
+pub fn get_thermals(history: &History) -> Result<Records, String> {
+    let mut records = Records::new(&["Component", "now", "min", "max", "history"]);
+    let Some(latest) = history.samples.back() else {
+        return Err("Waiting for the first sample...".to_string());
+    };
+    if latest.components.is_empty() {
+        return Err("No temperature sensors found".to_string());
+    }
+    for component in &latest.components {
+        let series: Vec<f64> = history
+            .samples
+            .iter()
+            .filter_map(|s| s.components.iter().find(|c| c.label == component.label))
+            .map(|c| c.temp)
+            .collect();
+        let min = series.iter().copied().fold(f64::MAX, f64::min);
+        let max = series.iter().copied().fold(f64::MIN, f64::max);
+        records.push(vec![
+            component.label.clone(),
+            format!("{:.1}°", component.temp),
+            format!("{:.1}°", min),
+            format!("{:.1}°", max),
+            chart::sparkline(&series, CHART_WIDTH),
+        ]);
+    }
+    Ok(records)
+}
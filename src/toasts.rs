@@ -0,0 +1,57 @@
+use crate::{records, theme::Theme};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Toasts stacked at once; an older one makes room for a new one early
+const SHOWN: usize = 3;
+
+/// Share of the output pane's width a toast may take, in percent
+const WIDTH: u16 = 60;
+
+/// Short-lived notes in the corner of the output pane about things that happened in the
+/// background, such as a fired alert, a finished job or a watchdog restart
+#[derive(Default)]
+pub struct Toasts {
+    list: VecDeque<(Instant, String)>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, message: &str) {
+        if self.list.len() == SHOWN {
+            self.list.pop_front();
+        }
+        self.list.push_back((Instant::now(), message.to_string()));
+    }
+
+    /// Drops the toasts shown for longer than `lifetime`
+    pub fn expire(&mut self, lifetime: Duration) {
+        self.list.retain(|(shown, _)| shown.elapsed() < lifetime);
+    }
+}
+
+/// The toasts in the bottom right corner of `area`, the newest at the bottom
+pub fn render<B: Backend>(f: &mut Frame<B>, area: Rect, toasts: &Toasts, theme: &Theme) {
+    let width = (area.width * WIDTH / 100).max(3).min(area.width);
+    let mut bottom = area.bottom().saturating_sub(1);
+    for (_, message) in toasts.list.iter().rev() {
+        if bottom < area.y + 4 {
+            break;
+        }
+        let text = records::ellipsize(message, width.saturating_sub(2) as usize);
+        let box_width = (text.chars().count() as u16 + 2).min(width);
+        let rect = Rect { x: area.right().saturating_sub(box_width + 1), y: bottom - 3, width: box_width, height: 3 };
+        f.render_widget(Clear, rect);
+        let block = Block::default().borders(Borders::ALL).border_style(Style::default().fg(theme.accent));
+        f.render_widget(Paragraph::new(text).block(block).style(theme.text()), rect);
+        bottom -= 3;
+    }
+}
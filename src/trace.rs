@@ -0,0 +1,149 @@
+use crate::records::{self, Records};
+use nix::{
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Instant,
+};
+
+/// Raw strace lines kept for the summary, which shows them when nothing was counted
+const RECENT_LINES: usize = 20;
+
+/// Longest a syscall's last call is shown in the live counts
+const LAST_CALL_WIDTH: usize = 80;
+
+/// Syscalls shown in the live counts and the histogram
+const TOP_SYSCALLS: usize = 15;
+
+/// Width of the longest histogram bar
+const BAR_WIDTH: usize = 40;
+
+/// The syscall name of an strace line, None for signals, exits and resumed calls
+fn parse_syscall(line: &str) -> Option<&str> {
+    // With -f every line of a traced thread starts with its TID
+    let line = match line.strip_prefix("[pid ") {
+        Some(rest) => rest.split_once("] ")?.1,
+        None => line,
+    };
+    let (name, _) = line.split_once('(')?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some(name)
+}
+
+/// `strace -f -p <pid>` attached to a process, counting the syscalls it makes
+pub struct Trace {
+    pid: i32,
+    child: Child,
+    lines: Receiver<String>,
+    counts: HashMap<String, u64>,
+    /// The latest strace line of each syscall
+    last: HashMap<String, String>,
+    total: u64,
+    recent: VecDeque<String>,
+    started: Instant,
+    /// strace exited, because the process did or attaching failed
+    pub finished: bool,
+}
+
+impl Trace {
+    /// Attaching needs ptrace permission over the process: the same user with
+    /// kernel.yama.ptrace_scope at 0, or CAP_SYS_PTRACE
+    pub fn start(pid: i32) -> io::Result<Trace> {
+        let mut child = Command::new("strace")
+            .args(["-f", "-qq", "-p", &pid.to_string()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stderr = child.stderr.take().unwrap();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Trace { pid, child, lines: rx, counts: HashMap::new(), last: HashMap::new(), total: 0, recent: VecDeque::new(), started: Instant::now(), finished: false })
+    }
+
+    fn drain(&mut self) {
+        for line in self.lines.try_iter() {
+            if let Some(name) = parse_syscall(&line) {
+                *self.counts.entry(name.to_string()).or_insert(0) += 1;
+                self.last.insert(name.to_string(), line.clone());
+                self.total += 1;
+            }
+            self.recent.push_back(line);
+        }
+        if self.recent.len() > RECENT_LINES {
+            self.recent.drain(..self.recent.len() - RECENT_LINES);
+        }
+        if !self.finished {
+            self.finished = matches!(self.child.try_wait(), Ok(Some(_)));
+        }
+    }
+
+    fn ranked(&self) -> Vec<(&String, &u64)> {
+        let mut ranked: Vec<(&String, &u64)> = self.counts.iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        ranked
+    }
+
+    fn seconds(&self) -> f64 {
+        self.started.elapsed().as_secs_f64().max(1.0)
+    }
+
+    /// What the live view is titled with
+    pub fn title(&self) -> String {
+        let seconds = self.seconds();
+        format!("Tracing PID {}: {} syscalls in {:.0}s ({:.0}/s), Esc to detach", self.pid, self.total, seconds, self.total as f64 / seconds)
+    }
+
+    /// Takes in the calls made since the last update and returns the busiest syscalls, each with
+    /// its latest call
+    pub fn update(&mut self) -> Records {
+        self.drain();
+        let seconds = self.seconds();
+        let mut records = Records::new(&["SYSCALL", "CALLS", "CALLS/s", "LAST CALL"]);
+        for (name, count) in self.ranked().into_iter().take(TOP_SYSCALLS) {
+            let last = self.last.get(name).map(|line| records::ellipsize(line, LAST_CALL_WIDTH)).unwrap_or_default();
+            records.push(vec![name.clone(), count.to_string(), format!("{:.1}", *count as f64 / seconds), last]);
+        }
+        records
+    }
+
+    /// Histogram of the calls seen while attached
+    pub fn summary(&mut self) -> Vec<String> {
+        self.drain();
+        let mut vec: Vec<String> = vec![format!("PID {}: {} syscalls in {:.0}s", self.pid, self.total, self.started.elapsed().as_secs_f64())];
+        if self.total == 0 {
+            // Usually a failed attach, which strace explains in its last words
+            vec.extend(self.recent.iter().cloned());
+            return vec;
+        }
+        let ranked = self.ranked();
+        let max = *ranked[0].1;
+        for (name, count) in ranked.into_iter().take(TOP_SYSCALLS) {
+            let bar = (*count as f64 / max as f64 * BAR_WIDTH as f64).ceil() as usize;
+            vec.push(format!("{:<24} {:>10} {:>5.1}% {}", name, count, *count as f64 * 100.0 / self.total as f64, "#".repeat(bar)));
+        }
+        vec
+    }
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        // strace detaches cleanly on SIGTERM; SIGKILL could leave the process stopped
+        let _ = kill(Pid::from_raw(self.child.id() as i32), Signal::SIGTERM);
+        let _ = self.child.wait();
+    }
+}
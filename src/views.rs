@@ -0,0 +1,44 @@
+use crate::{ptable::ProcessTable, records::Records};
+
+/// What the output pane shows: the output of typed commands, or one of the dashboards
+/// the sampler keeps up to date
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum View {
+    #[default]
+    Output,
+    Processes,
+    System,
+    Network,
+    Disks,
+    Sensors,
+}
+
+/// In tab order
+pub const VIEWS: [View; 6] = [View::Output, View::Processes, View::System, View::Network, View::Disks, View::Sensors];
+
+impl View {
+    pub fn title(self) -> &'static str {
+        match self {
+            View::Output => "Output",
+            View::Processes => "Processes",
+            View::System => "System",
+            View::Network => "Network",
+            View::Disks => "Disks",
+            View::Sensors => "Sensors",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        VIEWS.iter().position(|v| *v == self).unwrap_or(0)
+    }
+}
+
+/// The dashboard shown and its latest contents; only the shown one is refreshed
+#[derive(Default)]
+pub struct Views {
+    pub current: View,
+    /// Process table of the Processes view, keeping its selection, sort and filters across refreshes
+    pub processes: Option<ProcessTable>,
+    /// Contents of the other dashboards
+    pub records: Records,
+}
@@ -0,0 +1,89 @@
+use nix::unistd::{sysconf, SysconfVar};
+use std::{fs, time::Instant};
+
+/// Monotonic kernel counters sampled from /proc/vmstat and /proc/stat
+#[derive(Default)]
+struct Counters {
+    swap_in: u64,
+    swap_out: u64,
+    block_in: u64,
+    block_out: u64,
+    interrupts: u64,
+    context_switches: u64,
+    running: u64,
+    blocked: u64,
+}
+
+impl Counters {
+    fn read() -> Counters {
+        let mut counters = Counters::default();
+        for line in fs::read_to_string("/proc/vmstat").unwrap_or_default().lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(key), Some(value)) = (fields.next(), fields.next().and_then(|v| v.parse::<u64>().ok())) else {
+                continue;
+            };
+            match key {
+                "pswpin" => counters.swap_in = value,
+                "pswpout" => counters.swap_out = value,
+                "pgpgin" => counters.block_in = value,
+                "pgpgout" => counters.block_out = value,
+                _ => {}
+            }
+        }
+        for line in fs::read_to_string("/proc/stat").unwrap_or_default().lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(key), Some(value)) = (fields.next(), fields.next().and_then(|v| v.parse::<u64>().ok())) else {
+                continue;
+            };
+            match key {
+                "intr" => counters.interrupts = value,
+                "ctxt" => counters.context_switches = value,
+                "procs_running" => counters.running = value,
+                "procs_blocked" => counters.blocked = value,
+                _ => {}
+            }
+        }
+        counters
+    }
+}
+
+/// A running `vmstat` session producing one row of per-second rates per sample
+pub struct Vmstat {
+    prev: Counters,
+    at: Instant,
+    /// Rows still to be produced
+    pub remaining: usize,
+}
+
+impl Vmstat {
+    pub fn start(count: usize) -> Vmstat {
+        Vmstat { prev: Counters::read(), at: Instant::now(), remaining: count }
+    }
+
+    pub fn header() -> String {
+        format!("{:>6} {:>6} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}", "r", "b", "si", "so", "bi", "bo", "in", "cs")
+    }
+
+    /// Rates since the previous row; swap is reported in KiB/s and block I/O in KiB/s like procps
+    pub fn next_row(&mut self) -> String {
+        let now = Counters::read();
+        let secs = self.at.elapsed().as_secs_f64().max(0.001);
+        let page_kib = sysconf(SysconfVar::PAGE_SIZE).ok().flatten().unwrap_or(4096) as u64 / 1024;
+        let rate = |cur: u64, prev: u64| (cur.saturating_sub(prev) as f64 / secs).round() as u64;
+        let row = format!(
+            "{:>6} {:>6} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            now.running,
+            now.blocked,
+            rate(now.swap_in, self.prev.swap_in) * page_kib,
+            rate(now.swap_out, self.prev.swap_out) * page_kib,
+            rate(now.block_in, self.prev.block_in),
+            rate(now.block_out, self.prev.block_out),
+            rate(now.interrupts, self.prev.interrupts),
+            rate(now.context_switches, self.prev.context_switches),
+        );
+        self.prev = now;
+        self.at = Instant::now();
+        self.remaining = self.remaining.saturating_sub(1);
+        row
+    }
+}
@@ -0,0 +1,132 @@
+use crate::jobs::{Jobs, Launch};
+use std::fs;
+
+/// Longest process name the kernel keeps in /proc/<pid>/comm
+const COMM_LEN: usize = 15;
+
+pub enum Target {
+    Pid(i32),
+    Name(String),
+}
+
+/// A process kept an eye on by `watchdog`
+pub struct Watch {
+    pub id: usize,
+    pub target: Target,
+    /// Command re-ignited when the process is gone
+    pub restart: Option<Vec<String>>,
+    pub restarts: u32,
+    /// Whether the process was running at the last check, so an exit is only reported once
+    alive: bool,
+}
+
+impl Watch {
+    pub fn describe(&self) -> String {
+        let target = match &self.target {
+            Target::Pid(pid) => format!("PID {}", pid),
+            Target::Name(name) => name.clone(),
+        };
+        let state = if self.alive { "running" } else { "gone" };
+        match &self.restart {
+            Some(command) => format!("[{}] {} {}, restarted {} times with: {}", self.id, target, state, self.restarts, command.join(" ")),
+            None => format!("[{}] {} {}", self.id, target, state),
+        }
+    }
+
+    fn running(&self) -> bool {
+        match &self.target {
+            Target::Pid(pid) => is_running(*pid),
+            Target::Name(name) => !find_by_name(name).is_empty(),
+        }
+    }
+}
+
+/// Whether the PID exists and is not a zombie waiting to be reaped
+fn is_running(pid: i32) -> bool {
+    let Ok(stat) = fs::read_to_string(format!("/proc/{}/stat", pid)) else {
+        return false;
+    };
+    // The state letter follows the parenthesised command name, which may itself contain ") "
+    match stat.rsplit_once(") ") {
+        Some((_, rest)) => !rest.starts_with('Z') && !rest.starts_with('X'),
+        None => false,
+    }
+}
+
+/// Live PIDs whose command name is `name`, allowing for the kernel's truncation of long names
+fn find_by_name(name: &str) -> Vec<i32> {
+    let comm_name: String = name.chars().take(COMM_LEN).collect();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+    entries
+        .filter_map(|e| e.ok()?.file_name().to_str()?.parse::<i32>().ok())
+        .filter(|pid| fs::read_to_string(format!("/proc/{}/comm", pid)).map(|c| c.trim_end() == comm_name).unwrap_or(false))
+        .filter(|pid| is_running(*pid))
+        .collect()
+}
+
+#[derive(Default)]
+pub struct Watchdogs {
+    pub list: Vec<Watch>,
+    next_id: usize,
+}
+
+impl Watchdogs {
+    /// Starts watching a PID or process name, which has to be running now
+    pub fn add(&mut self, target: &str, restart: Option<Vec<String>>) -> Result<&Watch, String> {
+        let target = match target.parse::<i32>() {
+            Ok(pid) if is_running(pid) => Target::Pid(pid),
+            Ok(pid) => return Err(format!("watchdog: no running process with PID {}", pid)),
+            Err(_) if !find_by_name(target).is_empty() => Target::Name(target.to_string()),
+            Err(_) => return Err(format!("watchdog: no running process named '{}'", target)),
+        };
+        self.next_id += 1;
+        self.list.push(Watch { id: self.next_id, target, restart, restarts: 0, alive: true });
+        Ok(self.list.last().unwrap())
+    }
+
+    pub fn remove(&mut self, id: usize) -> bool {
+        let before = self.list.len();
+        self.list.retain(|w| w.id != id);
+        self.list.len() != before
+    }
+
+    /// Looks for watched processes that exited since the last check, re-igniting those with a restart
+    /// command through `jobs`. Returns one line per event for the user.
+    pub fn check(&mut self, jobs: &mut Jobs) -> Vec<String> {
+        let mut events: Vec<String> = vec![];
+        for watch in &mut self.list {
+            if watch.running() {
+                watch.alive = true;
+                continue;
+            }
+            let Some(command) = &watch.restart else {
+                if watch.alive {
+                    watch.alive = false;
+                    events.push(format!("watchdog: {} exited", watch.describe()));
+                }
+                continue;
+            };
+            match jobs.spawn(&Launch::new(&command[0], &command[1..])) {
+                Ok(job) => {
+                    watch.restarts += 1;
+                    if let Target::Pid(pid) = &mut watch.target {
+                        *pid = job.pid;
+                    }
+                    watch.alive = true;
+                    events.push(format!("watchdog: restarted as PID {} ({} restarts)  {}", job.pid, watch.restarts, job.command));
+                },
+                Err(e) => {
+                    // Retrying every tick would only repeat the error; fall back to reporting exits
+                    events.push(format!("watchdog [{}]: restarting {} failed: {}; no longer restarting", watch.id, command[0], e));
+                    watch.restart = None;
+                    watch.alive = false;
+                },
+            }
+        }
+        // A PID never comes back, so a watch on one is done once it has been reported
+        self.list.retain(|w| w.alive || !matches!(w.target, Target::Pid(_)));
+        events
+    }
+}
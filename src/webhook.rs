@@ -0,0 +1,77 @@
+use crate::alerts::Transition;
+use chrono::Utc;
+use nix::unistd::gethostname;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+/// Give up on a webhook that has not answered by then
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shape of the JSON body posted for each alert
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// `{"text": ...}`, understood by Slack, Mattermost and most chat incoming webhooks
+    #[default]
+    Slack,
+    /// A one-element list for Alertmanager's `/api/v2/alerts`
+    Alertmanager,
+}
+
+fn payload(format: Format, transition: &Transition) -> Value {
+    let host = gethostname().map(|h| h.to_string_lossy().to_string()).unwrap_or_default();
+    match format {
+        Format::Slack => {
+            let state = if transition.resolved { "resolved" } else { "FIRING" };
+            json!({ "text": format!("[proclynx {}] {}: {}", host, state, transition.message) })
+        },
+        Format::Alertmanager => {
+            let now = Utc::now().to_rfc3339();
+            let mut labels = json!({ "alertname": transition.rule, "instance": host, "job": "proclynx" });
+            if let Some(pid) = transition.pid {
+                labels["pid"] = json!(pid.to_string());
+            }
+            let mut alert = json!({ "labels": labels, "annotations": { "summary": transition.message } });
+            // Alertmanager resolves an alert once its end time has passed
+            alert[if transition.resolved { "endsAt" } else { "startsAt" }] = json!(now);
+            json!([alert])
+        },
+    }
+}
+
+/// Posts alerts from background threads; delivery failures come back through `errors`
+pub struct Webhooks {
+    sender: Sender<String>,
+    receiver: Receiver<String>,
+}
+
+impl Default for Webhooks {
+    fn default() -> Webhooks {
+        let (sender, receiver) = channel();
+        Webhooks { sender, receiver }
+    }
+}
+
+impl Webhooks {
+    pub fn post(&self, url: &str, format: Format, transition: &Transition) {
+        let body = payload(format, transition).to_string();
+        let url = url.to_string();
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let response = ureq::post(&url).timeout(TIMEOUT).set("Content-Type", "application/json").send_string(&body);
+            if let Err(e) = response {
+                let _ = sender.send(format!("webhook {}: {}", url, e));
+            }
+        });
+    }
+
+    /// Failures reported since the last call
+    pub fn errors(&self) -> Vec<String> {
+        self.receiver.try_iter().collect()
+    }
+}
@@ -0,0 +1,58 @@
+use crate::records::Records;
+use chrono::{DateTime, Local};
+use nix::libc;
+
+pub struct Session {
+    pub user: String,
+    pub tty: String,
+    pub login: i64,
+    pub host: String,
+}
+
+fn field(chars: &[libc::c_char]) -> String {
+    // utmp fields are NUL-padded but not NUL-terminated when full
+    let bytes: Vec<u8> = chars.iter().map(|c| *c as u8).take_while(|b| *b != 0).collect();
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Login sessions recorded in utmp
+pub fn sessions() -> Vec<Session> {
+    let mut sessions: Vec<Session> = vec![];
+    // SAFETY: the utmpx iterator is process-global; the entries are copied out before the next call
+    unsafe {
+        libc::setutxent();
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+            let entry = &*entry;
+            if entry.ut_type != libc::USER_PROCESS {
+                continue;
+            }
+            sessions.push(Session {
+                user: field(&entry.ut_user),
+                tty: field(&entry.ut_line),
+                login: entry.ut_tv.tv_sec as i64,
+                host: field(&entry.ut_host),
+            });
+        }
+        libc::endutxent();
+    }
+    sessions
+}
+
+pub fn get_logged_in_users() -> Records {
+    let mut records = Records::new(&["USER", "TTY", "LOGIN", "FROM"]);
+    for session in sessions() {
+        let login = DateTime::from_timestamp(session.login, 0)
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        records.push(vec![session.user, session.tty, login, session.host]);
+    }
+    records
+}
+
+pub fn count() -> usize {
+    sessions().len()
+}